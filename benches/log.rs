@@ -0,0 +1,169 @@
+//! Benchmarks for logging in mGBA.
+//!
+//! Run using `cargo bench`. Like the tests, this needs to be run on nightly-2023-03-24.
+//!
+//! The number of cycles taken to log each message is printed in a table, alongside the number
+//! recorded in the baseline saved by an earlier run using `cargo bench -- --save-baseline`. The
+//! benchmark fails if any message takes more than [`TOLERANCE`] percent more cycles than it did in
+//! the baseline.
+
+use cargo_metadata::Message;
+use mgba_log_reporter::{Level, Record};
+use std::{
+    collections::HashMap,
+    convert::AsRef,
+    env,
+    fmt::Write,
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+};
+
+/// The percentage by which a message may exceed its baseline before it counts as a regression.
+const TOLERANCE: u32 = 5;
+
+/// The measurements of a single benchmarked message.
+struct Measurement {
+    name: String,
+    bytes: u32,
+    cycles: u32,
+}
+
+fn build_rom<P>(path: P) -> String
+where
+    P: AsRef<Path>,
+{
+    let mut command = Command::new("cargo")
+        .args([
+            "build",
+            "--release",
+            "--message-format=json-render-diagnostics",
+        ])
+        .stdout(Stdio::piped())
+        .current_dir(path)
+        .spawn()
+        .expect("failed to build rom");
+
+    // Find the executable name.
+    let reader = BufReader::new(command.stdout.as_mut().expect("failed to read stdout"));
+    for message in Message::parse_stream(reader) {
+        match message.expect("failed to obtain message from stdout") {
+            Message::CompilerArtifact(artifact) => {
+                if let Some(executable) = artifact.executable {
+                    return executable.into();
+                }
+            }
+            Message::BuildFinished(_) => {
+                break;
+            }
+            _ => (), // Unknown message
+        }
+    }
+    panic!("failed to find executable name")
+}
+
+fn execute_rom(rom: &str) -> Vec<Record> {
+    let mut command = Command::new("cargo")
+        .args(["run", rom])
+        .stdout(Stdio::piped())
+        .current_dir("tests/mgba_log_reporter")
+        .spawn()
+        .expect("failed to run rom");
+
+    serde_json::from_reader(command.stdout.as_mut().expect("failed to read stdout"))
+        .expect("failed to deserialize output")
+}
+
+/// Extracts the measurements reported by the benchmark ROM.
+///
+/// Each measurement is reported at the debug level as `bench <name> <bytes> <cycles>`.
+fn parse_measurements(records: &[Record]) -> Vec<Measurement> {
+    records
+        .iter()
+        .filter(|record| record.level == Level::Debug)
+        .filter_map(|record| {
+            let mut fields = record.message.strip_prefix("bench ")?.split(' ');
+            let measurement = Measurement {
+                name: fields.next()?.to_owned(),
+                bytes: fields.next()?.parse().ok()?,
+                cycles: fields.next()?.parse().ok()?,
+            };
+            Some(measurement)
+        })
+        .collect()
+}
+
+/// Returns the path of the saved baseline.
+fn baseline_path() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("writer_baseline.txt")
+}
+
+/// Loads the saved baseline, mapping each message's name to its number of cycles.
+///
+/// Returns an empty baseline if none has been saved.
+fn load_baseline() -> HashMap<String, u32> {
+    fs::read_to_string(baseline_path())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (name, cycles) = line.split_once(' ')?;
+            Some((name.to_owned(), cycles.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Saves the given measurements as the baseline for later runs.
+fn save_baseline(measurements: &[Measurement]) {
+    let mut contents = String::new();
+    for measurement in measurements {
+        writeln!(contents, "{} {}", measurement.name, measurement.cycles)
+            .expect("failed to format baseline");
+    }
+    fs::write(baseline_path(), contents).expect("failed to save baseline");
+}
+
+fn main() {
+    let save = env::args().any(|arg| arg == "--save-baseline");
+
+    let rom = build_rom("benches/writer");
+    let measurements = parse_measurements(&execute_rom(&rom));
+    assert!(!measurements.is_empty(), "no measurements were reported");
+    let baseline = load_baseline();
+
+    let mut regressed = false;
+    println!(
+        "{:<12} {:>6} {:>8} {:>12} {:>9} {:>8}",
+        "message", "bytes", "cycles", "cycles/byte", "baseline", "change"
+    );
+    for measurement in &measurements {
+        let (baseline_cycles, change) = match baseline.get(&measurement.name) {
+            Some(&baseline_cycles) => {
+                regressed |= u64::from(measurement.cycles) * 100
+                    > u64::from(baseline_cycles) * u64::from(100 + TOLERANCE);
+                let change =
+                    (f64::from(measurement.cycles) / f64::from(baseline_cycles) - 1.0) * 100.0;
+                (baseline_cycles.to_string(), format!("{change:+.1}%"))
+            }
+            None => ("-".to_owned(), "-".to_owned()),
+        };
+        println!(
+            "{:<12} {:>6} {:>8} {:>12.2} {:>9} {:>8}",
+            measurement.name,
+            measurement.bytes,
+            measurement.cycles,
+            f64::from(measurement.cycles) / f64::from(measurement.bytes),
+            baseline_cycles,
+            change,
+        );
+    }
+
+    if save {
+        save_baseline(&measurements);
+        println!("saved baseline to {}", baseline_path().display());
+    }
+    if regressed {
+        eprintln!("logging regressed by more than {TOLERANCE}% compared to the baseline");
+        process::exit(1);
+    }
+}
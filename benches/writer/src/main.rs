@@ -0,0 +1,74 @@
+//! Benchmarks the number of cycles taken to log messages of different shapes.
+//!
+//! Each message is logged at the info level several times, and the fewest cycles taken by any
+//! single call is reported at the debug level as `bench <name> <bytes> <cycles>`, where `<bytes>`
+//! is the length of the message.
+
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+/// Counter register for timer 0.
+const TM0CNT_L: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0100) };
+/// Control register for timer 0.
+const TM0CNT_H: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0102) };
+/// Counter register for timer 1.
+const TM1CNT_L: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0104) };
+/// Control register for timer 1.
+const TM1CNT_H: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0106) };
+
+/// The number of times each message is logged.
+const RUNS: usize = 8;
+
+/// The messages that are benchmarked, along with their names.
+const CASES: [(&str, &str); 4] = [
+    ("short", "Hello, world!"),
+    ("long", "The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog."),
+    ("overflow", "The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog."),
+    ("multi-line", "The quick brown fox\njumps over\nthe lazy dog.\nThe quick brown fox\njumps over\nthe lazy dog."),
+];
+
+/// Returns the number of cycles counted by timers 0 and 1.
+fn cycles() -> u32 {
+    loop {
+        let high = TM1CNT_L.read();
+        let low = TM0CNT_L.read();
+        if high == TM1CNT_L.read() {
+            return u32::from(high) << 16 | u32::from(low);
+        }
+    }
+}
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+
+    // Cascade timer 1 from timer 0, counting every cycle.
+    TM1CNT_H.write(0x0084);
+    TM0CNT_H.write(0x0080);
+
+    for (name, message) in CASES {
+        let mut fewest = u32::MAX;
+        for _ in 0..RUNS {
+            let start = cycles();
+            log::info!("{}", message);
+            fewest = fewest.min(cycles().wrapping_sub(start));
+        }
+        log::debug!("bench {} {} {}", name, message.len(), fewest);
+    }
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,20 @@
+use std::{env, process::Command};
+
+/// Returns the minor version of the compiler building the crate, if it can be determined.
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    // The version is printed as in `rustc 1.81.0 (eeb90cda1 2024-09-04)`.
+    version.split(['.', ' ']).nth(2)?.parse().ok()
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(core_error)");
+
+    // `core::error::Error` was stabilized in Rust 1.81.
+    if matches!(rustc_minor_version(), Some(minor) if minor >= 81) {
+        println!("cargo:rustc-cfg=core_error");
+    }
+}
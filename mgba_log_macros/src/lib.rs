@@ -0,0 +1,243 @@
+//! Procedural macros for [`mgba_log`](https://docs.rs/mgba_log).
+//!
+//! These are re-exported by `mgba_log` when its `entry` and `instrument` features are enabled, and
+//! should be used through it rather than depended on directly.
+
+#![warn(clippy::pedantic, missing_docs)]
+#![allow(
+    // Clippy erroneously believes "mGBA" is an item that requires backticks.
+    clippy::doc_markdown,
+)]
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Parses `source` as tokens.
+///
+/// This is only used with hardcoded sources, which are always valid tokens.
+fn tokens(source: &str) -> TokenStream {
+    source.parse().expect("invalid tokens")
+}
+
+/// Returns tokens expanding to a `compile_error!` with the given `message`, reported at `span`.
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    tokens(&format!("::core::compile_error!({message:?});"))
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
+
+/// Splits a function into the tokens preceding its body, its name, and its body.
+///
+/// Returns a `compile_error!` naming `attribute` if `item` is not a function.
+fn split_function(
+    item: TokenStream,
+    attribute: &str,
+) -> Result<(Vec<TokenTree>, Ident, Group), TokenStream> {
+    let mut item: Vec<TokenTree> = item.into_iter().collect();
+    let name = item
+        .iter()
+        .skip_while(|token| !matches!(token, TokenTree::Ident(ident) if ident.to_string() == "fn"))
+        .nth(1);
+    match (name.cloned(), item.pop()) {
+        (Some(TokenTree::Ident(name)), Some(TokenTree::Group(body)))
+            if body.delimiter() == Delimiter::Brace =>
+        {
+            Ok((item, name, body))
+        }
+        (_, token) => Err(compile_error(
+            &format!("`#[mgba_log::{attribute}]` must be applied to a function"),
+            token.map_or_else(Span::call_site, |token| token.span()),
+        )),
+    }
+}
+
+/// Returns `signature` followed by a body consisting of `prologue` and then `body` as the tail
+/// expression, so that whatever the function returns is unaffected.
+fn wrap_body(mut signature: Vec<TokenTree>, mut prologue: TokenStream, body: Group) -> TokenStream {
+    let span = body.span();
+    prologue.extend([TokenTree::Group(body)]);
+    let mut wrapped = Group::new(Delimiter::Brace, prologue);
+    wrapped.set_span(span);
+    signature.push(TokenTree::Group(wrapped));
+    signature.into_iter().collect()
+}
+
+/// Parses the attribute's arguments, returning the tokens of the banner expression, if any.
+fn parse_banner(attr: TokenStream) -> Result<Option<TokenStream>, TokenStream> {
+    let mut args = attr.into_iter();
+    let Some(name) = args.next() else {
+        return Ok(None);
+    };
+    match &name {
+        TokenTree::Ident(ident) if ident.to_string() == "banner" => {}
+        _ => return Err(compile_error("expected `banner = ...`", name.span())),
+    }
+    match args.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+        _ => return Err(compile_error("expected `=` after `banner`", name.span())),
+    }
+    let banner: TokenStream = args.collect();
+    if banner.is_empty() {
+        return Err(compile_error("expected a banner after `=`", name.span()));
+    }
+    Ok(Some(banner))
+}
+
+/// Initializes logging at the start of the annotated function, typically `main`.
+///
+/// This collapses the setup every program starts with into a single attribute. Before the body of
+/// the function runs, the logger is initialized using `mgba_log::try_init_or_noop()`, so the same
+/// program runs unchanged outside of mGBA, with all records discarded. The `entry` feature also
+/// enables the `panic-handler` feature, so panics are logged without any further setup.
+///
+/// A banner, such as the program's name and version, can be given as any expression evaluating to
+/// a `&str`. It is logged at the `Info` level once the logger is initialized, and is not logged if
+/// the program is not running in mGBA.
+///
+/// ``` ignore
+/// #[no_mangle]
+/// #[mgba_log::entry(banner = concat!("my_game v", env!("CARGO_PKG_VERSION")))]
+/// pub fn main() -> ! {
+///     log::info!("Hello, world!");
+///     loop {}
+/// }
+/// ```
+///
+/// The function's signature is left as is, so this can be combined with whatever attribute or
+/// symbol name the program's runtime uses to find its entry point.
+///
+/// # Panics
+/// The annotated function panics if initialization fails for any reason other than mGBA not being
+/// present, such as when another logger has already been installed.
+#[proc_macro_attribute]
+pub fn entry(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let banner = match parse_banner(attr) {
+        Ok(banner) => banner,
+        Err(error) => return error,
+    };
+
+    let (signature, _, body) = match split_function(item, "entry") {
+        Ok(function) => function,
+        Err(error) => return error,
+    };
+
+    // `::mgba_log::__entry(banner);`
+    let banner = match banner {
+        Some(banner) => {
+            let mut some = tokens("::core::option::Option::Some");
+            some.extend([TokenTree::Group(Group::new(Delimiter::Parenthesis, banner))]);
+            some
+        }
+        None => tokens("::core::option::Option::None"),
+    };
+    let mut prologue = tokens("::mgba_log::__entry");
+    prologue.extend([
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, banner)),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    wrap_body(signature, prologue, body)
+}
+
+/// Parses the attribute's arguments as a comma separated list of names of the function's
+/// arguments.
+fn parse_argument_names(attr: TokenStream) -> Result<Vec<Ident>, TokenStream> {
+    let mut names = Vec::new();
+    let mut args = attr.into_iter();
+    while let Some(token) = args.next() {
+        match token {
+            TokenTree::Ident(name) => names.push(name),
+            token => {
+                return Err(compile_error(
+                    "expected the name of an argument",
+                    token.span(),
+                ))
+            }
+        }
+        match args.next() {
+            None => break,
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+            Some(token) => return Err(compile_error("expected `,`", token.span())),
+        }
+    }
+    Ok(names)
+}
+
+/// Logs entering and exiting the annotated function at the `Debug` level.
+///
+/// Entering the function logs `enter name`, or `enter name(a=1, b=2)` if the names of arguments
+/// are listed, formatting their values using their `Debug` implementations. Exiting the function,
+/// whether by returning, through `?`, or by unwinding, logs `exit name`. With the `timestamps`
+/// feature enabled, the cycles spent within the function are also logged, as in
+/// `exit name: 1234 cycles`. As with `mgba_log::scope!`, every message logged in between is
+/// indented by two spaces, making the flow of calls through game logic visible in mGBA's log.
+///
+/// ``` ignore
+/// #[mgba_log::instrument(frame)]
+/// fn update(frame: u32, world: &mut World) {
+///     log::info!("updating");
+/// }
+/// ```
+///
+/// Calling `update(42, &mut world)` logs the following:
+///
+/// ``` text
+/// enter update(frame=42)
+///   updating
+/// exit update
+/// ```
+///
+/// Messages are logged with the module containing the function as their target, so they can be
+/// filtered in the same way as any other record.
+#[proc_macro_attribute]
+pub fn instrument(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let names = match parse_argument_names(attr) {
+        Ok(names) => names,
+        Err(error) => return error,
+    };
+    let (signature, name, body) = match split_function(item, "instrument") {
+        Ok(function) => function,
+        Err(error) => return error,
+    };
+
+    // `let _instrument = ::mgba_log::__Instrument::new(module_path!(), "name", arguments);`
+    let arguments = if names.is_empty() {
+        tokens("::core::option::Option::None")
+    } else {
+        let format = names
+            .iter()
+            .map(|name| format!("{}={{:?}}", name.to_string().trim_start_matches("r#")))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut format_args = vec![TokenTree::Literal(Literal::string(&format))];
+        for name in names {
+            format_args.push(TokenTree::Punct(Punct::new(',', Spacing::Alone)));
+            format_args.push(TokenTree::Ident(name));
+        }
+        let mut arguments = tokens("::core::format_args!");
+        arguments.extend([TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            format_args.into_iter().collect(),
+        ))]);
+        let mut some = tokens("::core::option::Option::Some");
+        some.extend([TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            arguments,
+        ))]);
+        some
+    };
+    let mut new_arguments = tokens("::core::module_path!(),");
+    new_arguments.extend([
+        TokenTree::Literal(Literal::string(name.to_string().trim_start_matches("r#"))),
+        TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+    ]);
+    new_arguments.extend(arguments);
+    let mut prologue = tokens("let _instrument = ::mgba_log::__Instrument::new");
+    prologue.extend([
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, new_arguments)),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    wrap_body(signature, prologue, body)
+}
@@ -0,0 +1,91 @@
+//! Output through the AGBPrint protocol.
+//!
+//! AGBPrint was provided by Nintendo's development hardware, and is emulated by VisualBoyAdvance
+//! and its forks, such as VBA-M. Text is written to a ring buffer in an unused bank of the
+//! cartridge address space, and is printed by calling a flush routine that the emulator places at
+//! a fixed address in that same space. Writes to the cartridge address space are only accepted
+//! while the protection register is unlocked.
+
+use crate::{backend::level_prefix, Level};
+
+/// The protection register.
+///
+/// Writing [`UNLOCK`] allows writing to the ring buffer and the control structure, and writing `0`
+/// locks them again.
+const PROTECT: *mut u16 = 0x09FE_2FFE as *mut u16;
+/// The value unlocking the protection register.
+const UNLOCK: u16 = 0x20;
+/// The request field of the control structure.
+const REQUEST: *mut u16 = 0x09FE_20F8 as *mut u16;
+/// The bank of the cartridge address space containing the ring buffer.
+const BANK: *mut u16 = 0x09FE_20FA as *mut u16;
+/// The offset within the ring buffer of the next byte to be printed.
+const GET: *mut u16 = 0x09FE_20FC as *mut u16;
+/// The offset within the ring buffer of the next byte to be written.
+const PUT: *mut u16 = 0x09FE_20FE as *mut u16;
+/// The address of the flush routine, which is Thumb code.
+const FLUSH: usize = 0x09FE_209D;
+/// The bank used for the ring buffer.
+const BUFFER_BANK: u16 = 0xFD;
+/// The ring buffer, which is 64 KiB long and only supports halfword accesses.
+const BUFFER: *mut u16 = 0x08FD_0000 as *mut u16;
+
+/// Initializes the AGBPrint control structure, returning whether the emulator accepted it.
+///
+/// Outside of an emulator supporting AGBPrint, writes to the cartridge address space are ignored,
+/// so the control structure can not be read back.
+pub(crate) fn init() -> bool {
+    // SAFETY: These are all valid addresses within the cartridge address space, and the control
+    // structure is only ever accessed while interrupts are disabled.
+    unsafe {
+        PROTECT.write_volatile(UNLOCK);
+        REQUEST.write_volatile(0);
+        GET.write_volatile(0);
+        PUT.write_volatile(0);
+        BANK.write_volatile(BUFFER_BANK);
+        PROTECT.write_volatile(0);
+        BANK.read_volatile() == BUFFER_BANK
+    }
+}
+
+/// Writes a single byte to the ring buffer.
+///
+/// # Safety
+/// AGBPrint must have been initialized, and the protection register must be unlocked.
+unsafe fn put(byte: u8) {
+    // SAFETY: The caller guarantees that the ring buffer can be written to. Offsets wrap around
+    // at the end of the 64 KiB buffer, so every halfword accessed is within it.
+    unsafe {
+        let offset = PUT.read_volatile();
+        let halfword = BUFFER.add(usize::from(offset / 2));
+        let previous = halfword.read_volatile();
+        halfword.write_volatile(if offset & 1 == 0 {
+            previous & 0xff00 | u16::from(byte)
+        } else {
+            previous & 0x00ff | u16::from(byte) << 8
+        });
+        PUT.write_volatile(offset.wrapping_add(1));
+    }
+}
+
+/// Prints `text` at the given `level`, followed by a newline.
+///
+/// AGBPrint must have been initialized, and this must not be called while interrupts are enabled
+/// if an interrupt handler may print as well.
+pub(crate) fn print(level: Level, text: &[u8]) {
+    // SAFETY: The emulator provides the flush routine once AGBPrint has been initialized.
+    let flush: extern "C" fn() = unsafe { core::mem::transmute(FLUSH as *const ()) };
+    // SAFETY: AGBPrint has been initialized, and the protection register is unlocked while
+    // writing.
+    unsafe {
+        PROTECT.write_volatile(UNLOCK);
+        for &byte in level_prefix(level).iter().chain(text).chain(b"\n") {
+            put(byte);
+        }
+        // Each call to the flush routine may only print part of the ring buffer.
+        while GET.read_volatile() != PUT.read_volatile() {
+            flush();
+        }
+        PROTECT.write_volatile(0);
+    }
+}
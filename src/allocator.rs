@@ -0,0 +1,200 @@
+//! A global allocator wrapper that logs failed allocations.
+//!
+//! This is enabled by the `allocator` feature.
+
+use crate::{__print, racy::GuardedCell, HeapUsage, Level};
+use core::alloc::{GlobalAlloc, Layout};
+
+/// Statistics about the memory allocated through a [`LoggingAllocator`].
+///
+/// This is returned by [`LoggingAllocator::stats()`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct HeapStats {
+    /// The number of bytes currently allocated.
+    pub allocated: usize,
+    /// The number of allocations currently live.
+    pub allocations: usize,
+    /// The largest number of bytes that were allocated at once.
+    pub peak: usize,
+}
+
+impl HeapStats {
+    /// Creates statistics with every counter set to zero.
+    const fn new() -> Self {
+        Self {
+            allocated: 0,
+            allocations: 0,
+            peak: 0,
+        }
+    }
+}
+
+/// Wraps a global allocator, logging any allocation it fails at the error level.
+///
+/// Without this, a failed allocation halts the program through the panic handler with little more
+/// than the requested size. Instead, the failed allocation's size and alignment are logged along
+/// with the [`HeapStats`] at the time, and the free space left if the heap size was set through
+/// [`with_heap_size()`](Self::with_heap_size()).
+///
+/// The failure is still reported to the caller, so fallible allocations such as
+/// `Vec::try_reserve()` can recover from it, while infallible ones go on to halt through the
+/// panic handler.
+///
+/// ``` ignore
+/// #[global_allocator]
+/// static ALLOCATOR: mgba_log::LoggingAllocator<MyAllocator> =
+///     mgba_log::LoggingAllocator::new(MyAllocator::new()).with_heap_size(0x1_0000);
+/// ```
+#[derive(Debug)]
+pub struct LoggingAllocator<A> {
+    /// The wrapped allocator.
+    allocator: A,
+    /// The size of the heap, in bytes, if known.
+    heap_size: Option<usize>,
+    /// The statistics collected so far.
+    stats: GuardedCell<HeapStats>,
+}
+
+impl<A> LoggingAllocator<A> {
+    /// Wraps `allocator`.
+    #[must_use]
+    pub const fn new(allocator: A) -> Self {
+        Self {
+            allocator,
+            heap_size: None,
+            stats: GuardedCell::new(HeapStats::new()),
+        }
+    }
+
+    /// Sets the size of the heap `allocator` allocates from, in bytes.
+    ///
+    /// The free space logged on failure is this size minus the bytes allocated, so it does not
+    /// account for fragmentation or for the allocator's own overhead.
+    #[must_use]
+    pub const fn with_heap_size(mut self, heap_size: usize) -> Self {
+        self.heap_size = Some(heap_size);
+        self
+    }
+
+    /// Returns a copy of the statistics collected so far.
+    pub fn stats(&self) -> HeapStats {
+        self.stats.get()
+    }
+
+    /// Returns the current heap usage, if the heap size was set through
+    /// [`with_heap_size()`](Self::with_heap_size()).
+    ///
+    /// This allows the allocator to be registered as the source of
+    /// [`log_heap_stats!`](crate::log_heap_stats!) through
+    /// [`LoggerBuilder::with_heap_stats()`](crate::LoggerBuilder::with_heap_stats()). As with the
+    /// free space logged on failure, fragmentation is not accounted for, so the largest free block
+    /// is not known.
+    ///
+    /// ``` ignore
+    /// fn heap_stats() -> mgba_log::HeapUsage {
+    ///     ALLOCATOR.usage().unwrap_or_default()
+    /// }
+    /// ```
+    pub fn usage(&self) -> Option<HeapUsage> {
+        let heap_size = self.heap_size?;
+        let allocated = self.stats().allocated;
+        Some(HeapUsage {
+            used: allocated,
+            free: heap_size.saturating_sub(allocated),
+            largest_free: None,
+        })
+    }
+
+    /// Updates the statistics.
+    fn update<F>(&self, f: F)
+    where
+        F: FnOnce(&mut HeapStats),
+    {
+        self.stats.update(|stats| {
+            f(stats);
+            stats.peak = stats.peak.max(stats.allocated);
+        });
+    }
+
+    /// Counts an allocation of `layout` at `ptr`, logging it if the allocation failed.
+    fn allocated(&self, ptr: *mut u8, layout: Layout) -> *mut u8 {
+        if ptr.is_null() {
+            self.fail(layout);
+        } else {
+            self.update(|stats| {
+                stats.allocated += layout.size();
+                stats.allocations += 1;
+            });
+        }
+        ptr
+    }
+
+    /// Logs the failed allocation of `layout` at the error level.
+    ///
+    /// The message lists the layout and the statistics as `key=value` pairs, as in
+    /// `allocation failed: size=2000 align=1 allocated=100 allocations=1 peak=100 free=924`.
+    fn fail(&self, layout: Layout) {
+        let HeapStats {
+            allocated,
+            allocations,
+            peak,
+        } = self.stats();
+        let (size, align) = (layout.size(), layout.align());
+        match self.heap_size {
+            Some(heap_size) => __print(
+                Level::Error,
+                format_args!(
+                    "allocation failed: size={size} align={align} allocated={allocated} \
+                     allocations={allocations} peak={peak} free={}",
+                    heap_size.saturating_sub(allocated)
+                ),
+            ),
+            None => __print(
+                Level::Error,
+                format_args!(
+                    "allocation failed: size={size} align={align} allocated={allocated} \
+                     allocations={allocations} peak={peak}"
+                ),
+            ),
+        }
+    }
+}
+
+// SAFETY: Every call is forwarded to the wrapped allocator, which upholds the contract of
+// `GlobalAlloc`. Failed allocations are returned as null, as from the wrapped allocator.
+unsafe impl<A> GlobalAlloc for LoggingAllocator<A>
+where
+    A: GlobalAlloc,
+{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::alloc()`.
+        self.allocated(unsafe { self.allocator.alloc(layout) }, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::alloc_zeroed()`.
+        self.allocated(unsafe { self.allocator.alloc_zeroed(layout) }, layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::dealloc()`.
+        unsafe { self.allocator.dealloc(ptr, layout) };
+        self.update(|stats| {
+            stats.allocated -= layout.size();
+            stats.allocations -= 1;
+        });
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::realloc()`.
+        let new_ptr = unsafe { self.allocator.realloc(ptr, layout, new_size) };
+        if new_ptr.is_null() {
+            // SAFETY: The caller guarantees that `new_size` is a valid size for `layout`'s
+            // alignment.
+            self.fail(unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) });
+        } else {
+            self.update(|stats| stats.allocated = stats.allocated - layout.size() + new_size);
+        }
+        new_ptr
+    }
+}
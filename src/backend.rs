@@ -0,0 +1,144 @@
+//! The emulator interfaces that messages can be sent through.
+
+use crate::{
+    agbprint, is_mgba, mgba_log_buffer, mgba_log_enabled, mgba_log_send, nocash, uart, BaudRate,
+    Level, MGBA_LOG_BUFFER_LEN,
+};
+use core::cell::UnsafeCell;
+
+/// The interface that messages are sent through.
+///
+/// This is set using [`LoggerBuilder::with_backend()`](crate::LoggerBuilder::with_backend()).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Backend {
+    /// mGBA's memory mapped debug registers.
+    ///
+    /// Messages are logged at their own level, and mGBA halts execution when a message is logged
+    /// at the [`Fatal`](Level::Fatal) level.
+    #[default]
+    Mgba,
+    /// NO$GBA's debug message output.
+    ///
+    /// NO$GBA has no notion of levels, so each message is instead prefixed with its level, as in
+    /// `[WARN] `, and ends with a newline. Execution is not halted by fatal messages.
+    NoCashGba,
+    /// The AGBPrint protocol, as emulated by VisualBoyAdvance and its forks, such as VBA-M.
+    ///
+    /// AGBPrint has no notion of levels, so each message is instead prefixed with its level, as in
+    /// `[WARN] `, and ends with a newline. Execution is not halted by fatal messages.
+    ///
+    /// The protocol works by writing to an unused bank of the cartridge address space, which
+    /// flash carts may interpret as commands. It should therefore not be used on real hardware, and
+    /// is never selected by [`init_auto()`](crate::init_auto()).
+    AgbPrint,
+    /// The serial port in UART mode, sending at the given baud rate.
+    ///
+    /// This allows capturing messages from real hardware through a USB link cable adapter. Each
+    /// message is prefixed with its level, as in `[WARN] `, and ends with a newline. Execution is
+    /// not halted by fatal messages. The `decode_serial` binary of the `mgba_log_reporter` test
+    /// crate decodes the captured output.
+    ///
+    /// The serial port can not be used for anything else, such as multiplayer, while logging
+    /// through it.
+    Uart(BaudRate),
+}
+
+/// The buffer messages are written to before being sent through a backend other than mGBA.
+///
+/// mGBA's own log buffer is used directly when sending through mGBA.
+static BUFFER: Buffer = Buffer(UnsafeCell::new([0; MGBA_LOG_BUFFER_LEN]));
+
+/// A buffer of the same size as mGBA's log buffer.
+struct Buffer(UnsafeCell<[u8; MGBA_LOG_BUFFER_LEN]>);
+
+// SAFETY: The buffer is only accessed by the writer currently writing a message, in the same way
+// as mGBA's log buffer. As the Game Boy Advance has a single core, this means it is never accessed
+// concurrently.
+unsafe impl Sync for Buffer {}
+
+impl Backend {
+    /// Returns the buffer that messages sent through this backend are written to.
+    ///
+    /// The buffer is [`MGBA_LOG_BUFFER_LEN`] bytes long, and supports byte, halfword, and word
+    /// writes.
+    pub(crate) fn buffer(self) -> *mut u8 {
+        match self {
+            Self::Mgba => mgba_log_buffer(),
+            Self::NoCashGba | Self::AgbPrint | Self::Uart(_) => ram_buffer(),
+        }
+    }
+
+    /// Returns whether messages sent through this backend are currently received.
+    ///
+    /// The other backends provide no way to check this, so they are assumed to be listening once
+    /// initialized.
+    pub(crate) fn is_listening(self) -> bool {
+        match self {
+            Self::Mgba => mgba_log_enabled(),
+            Self::NoCashGba | Self::AgbPrint | Self::Uart(_) => true,
+        }
+    }
+
+    /// Sends the first `len` bytes of the buffer as a message at the given `level`.
+    ///
+    /// # Safety
+    /// `len` must not exceed [`MGBA_LOG_BUFFER_LEN`]. If it is less, the message must be followed
+    /// by a null byte. No other message may be written to the buffer at the same time.
+    pub(crate) unsafe fn send(self, level: Level, len: usize) {
+        match self {
+            // SAFETY: The caller guarantees that the buffer is not being written to elsewhere.
+            Self::Mgba => unsafe { mgba_log_send(level) },
+            // SAFETY: The caller guarantees that the buffer is not being written to elsewhere and
+            // that `len` is within its bounds.
+            Self::NoCashGba => nocash::print(level, unsafe { ram_message(len) }),
+            // SAFETY: Same as above.
+            Self::AgbPrint => agbprint::print(level, unsafe { ram_message(len) }),
+            // SAFETY: Same as above.
+            Self::Uart(_) => uart::print(level, unsafe { ram_message(len) }),
+        }
+    }
+}
+
+/// Returns the buffer in RAM that messages are written to before being sent.
+///
+/// This is used instead of mGBA's log buffer whenever messages need to be read back, as mGBA's
+/// log buffer can only be written to.
+pub(crate) fn ram_buffer() -> *mut u8 {
+    BUFFER.0.get().cast()
+}
+
+/// Returns the first `len` bytes of the buffer returned by [`ram_buffer()`].
+///
+/// # Safety
+/// `len` must not exceed [`MGBA_LOG_BUFFER_LEN`], and the buffer must not be written to while the
+/// returned slice is alive.
+pub(crate) unsafe fn ram_message<'a>(len: usize) -> &'a [u8] {
+    // SAFETY: The caller guarantees that `len` is within the buffer, and that the buffer is not
+    // written to for the lifetime of the slice.
+    unsafe { &(&*BUFFER.0.get())[..len] }
+}
+
+/// Returns the first backend found to be available, probing mGBA and NO$GBA in turn.
+///
+/// AGBPrint is never probed, as probing it writes to the cartridge address space, which flash carts
+/// may interpret as commands. It can only be selected explicitly.
+pub(crate) fn detect() -> Option<Backend> {
+    if is_mgba() {
+        Some(Backend::Mgba)
+    } else if nocash::is_nocash() {
+        Some(Backend::NoCashGba)
+    } else {
+        None
+    }
+}
+
+/// Returns the prefix identifying messages at `level` on backends without a notion of levels.
+pub(crate) fn level_prefix(level: Level) -> &'static [u8] {
+    match level {
+        Level::Fatal => b"[FATAL] ",
+        Level::Error => b"[ERROR] ",
+        Level::Warning => b"[WARN] ",
+        Level::Info => b"[INFO] ",
+        Level::Debug => b"[DEBUG] ",
+    }
+}
@@ -0,0 +1,73 @@
+//! Backtraces found by walking the chain of frame pointers.
+//!
+//! This is enabled by the `backtrace` feature. The program must be compiled with
+//! `-Cforce-frame-pointers=yes`, including the `core` crate if it is built through `build-std`.
+//! Otherwise, `r7` is used as a general purpose register, and the backtrace ends early.
+
+use crate::{fmt::hex_u32, stack_region_end};
+use core::fmt::{self, Write};
+
+/// The most return addresses written.
+const MAX_FRAMES: usize = 16;
+
+/// Returns the frame pointer of the calling function.
+///
+/// Thumb code uses `r7` as its frame pointer. ARM code, such as the functions placed in IWRAM by
+/// the `iwram` feature, uses `r11` instead, so the backtrace ends at any such function.
+#[cfg(target_arch = "arm")]
+#[inline(always)]
+fn frame_pointer() -> usize {
+    let frame_pointer: usize;
+    // SAFETY: Reading a register has no side effects. ARMv4T only supports moving between low
+    // registers in Thumb code through an addition, which sets the flags.
+    unsafe {
+        core::arch::asm!(
+            "adds {}, r7, #0",
+            out(reg) frame_pointer,
+            options(nomem, nostack),
+        );
+    }
+    frame_pointer
+}
+
+/// Returns the frame pointer of the calling function.
+///
+/// Frame pointers can only be read on the Game Boy Advance's ARM CPU, so this is always null.
+#[cfg(not(target_arch = "arm"))]
+fn frame_pointer() -> usize {
+    0
+}
+
+/// Writes the return addresses of the current call stack, starting with the caller of this
+/// function, as in `backtrace: 0x08000d2f 0x08000c1b`.
+///
+/// Each frame record holds the previous frame pointer followed by the return address. The walk
+/// stops at the first frame pointer outside of the memory region containing the stack, or when the
+/// chain stops moving up the stack.
+pub(crate) fn write<W>(writer: &mut W) -> fmt::Result
+where
+    W: Write,
+{
+    writer.write_str("backtrace:")?;
+    let mut frame = frame_pointer();
+    for _ in 0..MAX_FRAMES {
+        match stack_region_end(frame) {
+            Some(end) if frame.trailing_zeros() >= 2 && frame + 8 <= end => {}
+            _ => break,
+        }
+        let record = frame as *const usize;
+        // SAFETY: Both words of the frame record are within the memory region containing the
+        // stack, and are word aligned.
+        let (previous, return_address) =
+            unsafe { (record.read_volatile(), record.add(1).read_volatile()) };
+        // Addresses on the Game Boy Advance always fit in 32 bits.
+        #[allow(clippy::cast_possible_truncation)]
+        write!(writer, " {}", hex_u32(return_address as u32))?;
+        // The frames of callers are further up the stack, which grows downwards.
+        if previous <= frame {
+            break;
+        }
+        frame = previous;
+    }
+    Ok(())
+}
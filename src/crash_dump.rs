@@ -0,0 +1,114 @@
+//! Compact crash dumps written by the panic handler.
+//!
+//! This is enabled by the `crash-dump` feature. A crash dump is laid out as follows, with every
+//! value in little endian:
+//!
+//! | Offset   | Length | Contents                                             |
+//! |----------|--------|------------------------------------------------------|
+//! | 0        | 4      | The magic bytes `MCRD`.                              |
+//! | 4        | 4      | The build id.                                        |
+//! | 8        | 68     | `r0` through `r15`, followed by the CPSR.            |
+//! | 76       | 1      | The number of words of the stack that follow, `n`.   |
+//! | 77       | 4n     | The words at the top of the stack, starting at `sp`. |
+//! | 77 + 4n  | 2      | The CRC-16/CCITT-FALSE of the preceding bytes.       |
+//!
+//! When logged, the dump is hex encoded and split across messages at the error level, each
+//! prefixed with `crash dump: `. The `mgba_log_reporter` test crate's `CrashDump` decodes dumps
+//! from either the logged messages or a save file.
+
+use crate::{sram, stack_region_end, without_interrupts, Level, MgbaWriter, LOGGER};
+use core::fmt::Write;
+
+/// The bytes identifying the start of a crash dump.
+const MAGIC: [u8; 4] = *b"MCRD";
+/// The number of words at the top of the stack that are included.
+const STACK_WORDS: usize = 16;
+/// The length of the longest possible crash dump.
+pub(crate) const MAX_LEN: usize = 77 + 4 * STACK_WORDS + 2;
+/// The number of bytes of the dump logged in each message.
+const BYTES_PER_MESSAGE: usize = 64;
+
+/// A crash dump being encoded.
+struct Dump {
+    /// The encoded bytes.
+    bytes: [u8; MAX_LEN],
+    /// The number of bytes encoded so far.
+    len: usize,
+}
+
+impl Dump {
+    /// Creates an empty dump.
+    const fn new() -> Self {
+        Self {
+            bytes: [0; MAX_LEN],
+            len: 0,
+        }
+    }
+
+    /// Appends `bytes` to the dump.
+    fn push(&mut self, bytes: &[u8]) {
+        self.bytes[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    /// Returns the bytes encoded so far.
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// Encodes a crash dump of `registers` and the stack they point to.
+fn encode(build_id: u32, registers: &[u32; 17]) -> Dump {
+    let mut dump = Dump::new();
+    dump.push(&MAGIC);
+    dump.push(&build_id.to_le_bytes());
+    for register in registers {
+        dump.push(&register.to_le_bytes());
+    }
+
+    let sp = registers[13] as usize;
+    let words = stack_region_end(sp).map_or(0, |end| ((end - sp) / 4).min(STACK_WORDS));
+    // There are at most `STACK_WORDS` words, so the count always fits in a `u8`.
+    #[allow(clippy::cast_possible_truncation)]
+    dump.push(&[words as u8]);
+    let stack = sp as *const u32;
+    for index in 0..words {
+        // SAFETY: The address is within the memory region containing the stack, and is word
+        // aligned as the stack pointer always is.
+        dump.push(&unsafe { stack.add(index).read_volatile() }.to_le_bytes());
+    }
+
+    let crc = sram::crc16(dump.as_bytes().iter().copied());
+    dump.push(&crc.to_le_bytes());
+    dump
+}
+
+/// Writes a crash dump of `registers` and the stack they point to.
+///
+/// The dump is written to SRAM if an offset was set through
+/// [`LoggerBuilder::with_crash_dump_sram()`](crate::LoggerBuilder::with_crash_dump_sram()), and is
+/// logged at the error level if running in mGBA.
+pub(crate) fn write(registers: &[u32; 17], is_mgba: bool) {
+    // SAFETY: Interrupts are disabled, so the configuration cannot be accessed concurrently.
+    let (build_id, sram_offset) = without_interrupts(|| unsafe {
+        let config = &*LOGGER.config.get();
+        (config.build_id, config.crash_dump_sram)
+    });
+    let dump = encode(build_id, registers);
+
+    if let Some(offset) = sram_offset {
+        sram::write_bytes(offset, dump.as_bytes());
+    }
+    if is_mgba {
+        let mut writer = MgbaWriter::new(Level::Error);
+        for (index, chunk) in dump.as_bytes().chunks(BYTES_PER_MESSAGE).enumerate() {
+            // Write failures can not be reported from within the panic handler, so they are
+            // ignored.
+            let _ = writer.write_str(if index == 0 { "" } else { "\n" });
+            let _ = writer.write_str("crash dump: ");
+            for byte in chunk {
+                let _ = write!(writer, "{byte:02x}");
+            }
+        }
+    }
+}
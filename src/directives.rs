@@ -0,0 +1,159 @@
+//! Filtering of records by target and level using a filter string parsed at compile time.
+//!
+//! See [`directives!`](crate::directives!) for details.
+
+use crate::TargetFilters;
+use log::LevelFilter;
+
+/// The maximum number of directives naming a target.
+const CAPACITY: usize = 8;
+
+/// A set of directives setting the maximum level logged for each target, parsed from a filter
+/// string such as `warn,game::ai=debug,agb=off`.
+///
+/// Directives are created at compile time by [`directives!`](crate::directives!) and applied
+/// through [`LoggerBuilder::with_directives()`](crate::LoggerBuilder::with_directives()).
+#[derive(Clone, Copy, Debug)]
+pub struct Directives {
+    /// The targets named by directives, along with their maximum levels.
+    targets: [Option<(&'static str, LevelFilter)>; CAPACITY],
+    /// The maximum level of targets not named by any directive, if set by a bare level.
+    default: Option<LevelFilter>,
+}
+
+/// Returns the part of `bytes` from `start` up to, but not including, `end`, with surrounding
+/// spaces removed.
+///
+/// `start` and `end` must not split a UTF-8 character.
+const fn trimmed(bytes: &'static [u8], mut start: usize, mut end: usize) -> &'static str {
+    while start < end && bytes[start] == b' ' {
+        start += 1;
+    }
+    while end > start && bytes[end - 1] == b' ' {
+        end -= 1;
+    }
+    // SAFETY: `start` and `end` are within `bytes` and do not split a UTF-8 character, so the
+    // slice is valid UTF-8, as `bytes` was.
+    unsafe {
+        core::str::from_utf8_unchecked(core::slice::from_raw_parts(
+            bytes.as_ptr().add(start),
+            end - start,
+        ))
+    }
+}
+
+/// Returns whether `a` and `b` are equal, ignoring ASCII case.
+const fn eq_ignore_case(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut index = 0;
+    while index < a.len() {
+        if !a[index].eq_ignore_ascii_case(&b[index]) {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+/// Parses `level` as a level filter, returning `None` if it is not one.
+const fn parse_level(level: &str) -> Option<LevelFilter> {
+    if eq_ignore_case(level, "off") {
+        Some(LevelFilter::Off)
+    } else if eq_ignore_case(level, "error") {
+        Some(LevelFilter::Error)
+    } else if eq_ignore_case(level, "warn") {
+        Some(LevelFilter::Warn)
+    } else if eq_ignore_case(level, "info") {
+        Some(LevelFilter::Info)
+    } else if eq_ignore_case(level, "debug") {
+        Some(LevelFilter::Debug)
+    } else if eq_ignore_case(level, "trace") {
+        Some(LevelFilter::Trace)
+    } else {
+        None
+    }
+}
+
+impl Directives {
+    /// Parses `filter` as a comma separated list of directives.
+    ///
+    /// See [`directives!`](crate::directives!) for the syntax. This should be evaluated at compile
+    /// time, which [`directives!`](crate::directives!) guarantees.
+    ///
+    /// # Panics
+    /// Panics if a directive's level is not one of `off`, `error`, `warn`, `info`, `debug`, or
+    /// `trace`, or if more than eight directives name a target.
+    #[must_use]
+    pub const fn parse(filter: &'static str) -> Self {
+        let mut directives = Self {
+            targets: [None; CAPACITY],
+            default: None,
+        };
+        let mut count = 0;
+        let bytes = filter.as_bytes();
+        let mut start = 0;
+        while start <= bytes.len() {
+            let mut end = start;
+            let mut equals = None;
+            while end < bytes.len() && bytes[end] != b',' {
+                if bytes[end] == b'=' && equals.is_none() {
+                    equals = Some(end);
+                }
+                end += 1;
+            }
+
+            let directive = if let Some(equals) = equals {
+                match parse_level(trimmed(bytes, equals + 1, end)) {
+                    Some(level) => Some((trimmed(bytes, start, equals), level)),
+                    None => panic!("invalid level in filter directive"),
+                }
+            } else {
+                let directive = trimmed(bytes, start, end);
+                if directive.is_empty() {
+                    None
+                } else {
+                    match parse_level(directive) {
+                        Some(level) => Some(("", level)),
+                        // A target without a level logs all of its records.
+                        None => Some((directive, LevelFilter::Trace)),
+                    }
+                }
+            };
+            if let Some((target, level)) = directive {
+                if target.is_empty() {
+                    directives.default = Some(level);
+                } else {
+                    assert!(count < CAPACITY, "too many filter directives");
+                    directives.targets[count] = Some((target, level));
+                    count += 1;
+                }
+            }
+
+            start = end + 1;
+        }
+        directives
+    }
+
+    /// Returns whether records at `level` with the given `target` are enabled by these directives.
+    ///
+    /// The directive naming the longest target matching `target` applies. If none do, the bare
+    /// level applies, and if there is none either, the record is not enabled. Empty directives
+    /// enable every record.
+    pub(crate) fn enabled(&self, level: log::Level, target: &str) -> bool {
+        let mut applicable: Option<(&str, LevelFilter)> = None;
+        for &(filter, filter_level) in self.targets.iter().flatten() {
+            if TargetFilters::matches(filter, target)
+                && !matches!(applicable, Some((longest, _)) if longest.len() >= filter.len())
+            {
+                applicable = Some((filter, filter_level));
+            }
+        }
+        match applicable.map(|(_, level)| level).or(self.default) {
+            Some(max_level) => level <= max_level,
+            None => self.targets[0].is_none(),
+        }
+    }
+}
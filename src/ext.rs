@@ -0,0 +1,81 @@
+//! Extension traits for reporting errors through mGBA.
+
+use crate::__fatal_halt;
+use core::fmt::Debug;
+
+/// Extension methods on [`Result`] for reporting errors through mGBA.
+///
+/// ``` no_run
+/// use mgba_log::ResultExt;
+///
+/// fn load_save() -> Result<u32, &'static str> {
+///     Err("checksum mismatch")
+/// }
+///
+/// // Logs `"checksum mismatch"` at the error level and continues.
+/// let save = load_save().log_err("save").unwrap_or_default();
+/// // Logs `save required: "checksum mismatch"` at the fatal level and halts.
+/// let save = load_save().expect_fatal("save required");
+/// ```
+pub trait ResultExt<T, E> {
+    /// Logs the contained error, if there is one, at the error level.
+    ///
+    /// The error is logged to the given `target` using its [`Debug`] implementation. The result is
+    /// then returned unchanged, allowing it to be handled as usual.
+    #[must_use]
+    fn log_err(self, target: &str) -> Self;
+
+    /// Returns the contained [`Ok`] value, logging a fatal message if there is none.
+    ///
+    /// If the result is an [`Err`], `msg` is logged at the fatal level followed by the error's
+    /// [`Debug`] representation, as in `msg: error`, and execution halts in the same way as
+    /// [`fatal!`](crate::fatal!).
+    fn expect_fatal(self, msg: &str) -> T;
+}
+
+impl<T, E> ResultExt<T, E> for Result<T, E>
+where
+    E: Debug,
+{
+    fn log_err(self, target: &str) -> Self {
+        if let Err(error) = &self {
+            log::error!(target: target, "{error:?}");
+        }
+        self
+    }
+
+    #[track_caller]
+    fn expect_fatal(self, msg: &str) -> T {
+        match self {
+            Ok(value) => value,
+            Err(error) => __fatal_halt(format_args!("{msg}: {error:?}")),
+        }
+    }
+}
+
+/// Extension methods on [`Option`] for reporting missing values through mGBA.
+///
+/// ``` no_run
+/// use mgba_log::OptionExt;
+///
+/// let sprite: Option<u32> = None;
+/// // Logs `sprite not loaded` at the fatal level and halts.
+/// let sprite = sprite.expect_fatal("sprite not loaded");
+/// ```
+pub trait OptionExt<T> {
+    /// Returns the contained [`Some`] value, logging a fatal message if there is none.
+    ///
+    /// If the option is [`None`], `msg` is logged at the fatal level and execution halts in the
+    /// same way as [`fatal!`](crate::fatal!).
+    fn expect_fatal(self, msg: &str) -> T;
+}
+
+impl<T> OptionExt<T> for Option<T> {
+    #[track_caller]
+    fn expect_fatal(self, msg: &str) -> T {
+        match self {
+            Some(value) => value,
+            None => __fatal_halt(format_args!("{msg}")),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Logging fatal messages formatted into a fixed-capacity buffer on the stack.
+//!
+//! See [`fatal_fixed()`] for details.
+
+use crate::{
+    char_boundary, without_interrupts, writer_config, InterruptGuard, Level, Overflow, Writer,
+    MGBA_LOG_BUFFER_LEN, TRUNCATION_MARKER,
+};
+use core::fmt::{self, Arguments, Write};
+
+/// A message formatted into a buffer of `N` bytes on the stack.
+struct FixedBuffer<const N: usize> {
+    /// The formatted bytes.
+    bytes: [u8; N],
+    /// The number of bytes formatted so far.
+    len: usize,
+    /// Whether the message has been truncated, meaning the rest of it is discarded.
+    truncated: bool,
+}
+
+impl<const N: usize> FixedBuffer<N> {
+    /// Fails to compile unless a message of `N` bytes fits in a single message, and has room for
+    /// the truncation marker.
+    const VALID_CAPACITY: () = assert!(
+        N >= TRUNCATION_MARKER.len() && N <= MGBA_LOG_BUFFER_LEN,
+        "fixed capacity must be between 3 and 256 bytes"
+    );
+
+    /// Creates an empty buffer.
+    const fn new() -> Self {
+        Self {
+            bytes: [0; N],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    /// Returns the bytes formatted so far.
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+impl<const N: usize> Write for FixedBuffer<N> {
+    /// Appends `s` to the buffer, truncating the message if it does not fit.
+    ///
+    /// Newlines and null characters are written as spaces, as either would end the message early.
+    /// Once the message is truncated, this fails, so that formatting stops as early as possible.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.truncated {
+            return Err(fmt::Error);
+        }
+        let available = N - self.len;
+        let (bytes, overflowed) = if s.len() > available {
+            (&s.as_bytes()[..available], true)
+        } else {
+            (s.as_bytes(), false)
+        };
+        for (slot, &byte) in self.bytes[self.len..].iter_mut().zip(bytes) {
+            *slot = if matches!(byte, b'\n' | b'\x00') {
+                b' '
+            } else {
+                byte
+            };
+        }
+        self.len += bytes.len();
+        if !overflowed {
+            return Ok(());
+        }
+
+        // The buffer is full of a valid prefix of the message, so the end of the last character
+        // that leaves room for the marker can be found within it.
+        let boundary = char_boundary(&self.bytes, N - TRUNCATION_MARKER.len());
+        self.bytes[boundary..boundary + TRUNCATION_MARKER.len()].copy_from_slice(TRUNCATION_MARKER);
+        self.len = boundary + TRUNCATION_MARKER.len();
+        self.truncated = true;
+        Err(fmt::Error)
+    }
+}
+
+/// Logs a message at the fatal level, formatted into a buffer of `N` bytes on the stack, and never
+/// returns.
+///
+/// This is a more predictable alternative to [`fatal!`](crate::fatal!) for use where formatting
+/// could fail in unexpected ways, such as within a panic handler. The message is fully formatted
+/// before anything is written to the log, and is then sent as a single message, so mGBA always
+/// displays all of it. Messages longer than `N` bytes are truncated, ending with `…` in place of
+/// as many characters as needed to fit it, and formatting stops as soon as the buffer is full.
+/// Newlines and null characters are written as spaces.
+///
+/// Unlike [`fatal!`](crate::fatal!), the message is not prefixed, its location is not included,
+/// and no backtrace is logged, as each of these would be formatted separately. The message is
+/// written using the logger's backend, and is still mirrored to SRAM if configured.
+///
+/// `N` must be between `3` and `256`, the length of mGBA's log buffer, which is checked at compile
+/// time.
+///
+/// ``` no_run
+/// let frame = 42;
+/// mgba_log::fatal_fixed::<64>(format_args!("stack overflow at frame {}", frame));
+/// ```
+///
+/// If the message could not be logged (for example, if [`init()`](crate::init()) has not been
+/// successfully run or the program is not running in mGBA), execution loops forever instead.
+///
+/// # Panics
+/// When the `mock` feature is enabled, this panics once the message is captured, rather than
+/// halting.
+#[cold]
+pub fn fatal_fixed<const N: usize>(args: Arguments) -> ! {
+    #[allow(clippy::let_unit_value)]
+    let () = FixedBuffer::<N>::VALID_CAPACITY;
+
+    let mut buffer = FixedBuffer::<N>::new();
+    // Truncating the message stops formatting with an error, which is expected.
+    let _ = buffer.write_fmt(args);
+
+    if !cfg!(feature = "disabled") && without_interrupts(|| writer_config().backend).is_listening()
+    {
+        // Interrupts are never reenabled, because writing with `Level::Fatal` will always cause
+        // mGBA to halt execution.
+        #[allow(clippy::forget_non_drop)]
+        core::mem::forget(InterruptGuard::new());
+
+        let mut config = writer_config();
+        // The message fits in the log buffer, so without a continuation marker it is never split.
+        config.overflow = Overflow::Split("");
+        // The writer sends the message when it is dropped.
+        Writer::new(Level::Fatal, config).write_bytes(buffer.as_bytes());
+    }
+
+    // The mock captures fatal messages without halting, so tests observe them as panics instead.
+    #[cfg(feature = "mock")]
+    panic!("a message was logged at the fatal level");
+    // Logging at the fatal level halts mGBA. If execution continues, the message could not be
+    // logged, so execution is halted here instead.
+    #[allow(unreachable_code)]
+    loop {
+        core::hint::spin_loop();
+    }
+}
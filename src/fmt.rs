@@ -0,0 +1,279 @@
+//! Lightweight formatting of integers.
+//!
+//! Formatting integers through [`core::fmt`], as in `log::debug!("{:x}", value)`, uses the
+//! generic integer formatting code of `core`, which handles every flag a format string can contain
+//! and divides by ten using library calls on the Game Boy Advance. The adapters in this module
+//! instead format their values into a small buffer on the stack using only shifts, comparisons,
+//! and subtractions, and write the result as a single string. This makes them suitable for logging
+//! values such as register contents many times per frame.
+//!
+//! ``` no_run
+//! use mgba_log::fmt::{dec_u32, hex_u16};
+//!
+//! let dispcnt: u16 = 0x0403;
+//! let frame: u32 = 60;
+//! log::debug!("DISPCNT = {} on frame {}", hex_u16(dispcnt), dec_u32(frame));
+//! ```
+//!
+//! Byte slices can similarly be displayed in hexadecimal using [`hex_slice()`], or in hexadecimal
+//! followed by their ASCII characters using [`ascii_dump()`]. Unlike the
+//! [`hexdump!`](crate::hexdump!) macro, these can be used within any message.
+//!
+//! ``` no_run
+//! use mgba_log::fmt::{ascii_dump, hex_slice};
+//!
+//! let header = [0x2e, 0x00, 0x00, 0xea, b'A', b'G', b'B'];
+//! log::info!("header: {}", hex_slice(&header));
+//! log::info!("header: {}", ascii_dump(&header));
+//! ```
+//!
+//! Formatting flags such as width and fill are ignored by these adapters.
+
+use core::{fmt, fmt::Display, str};
+
+/// The digits used when formatting in hexadecimal.
+pub(crate) const HEX_DIGITS: [u8; 16] = *b"0123456789abcdef";
+
+/// The number of bytes in each space-separated group of a hex slice.
+const BYTES_PER_GROUP: usize = 8;
+
+/// The powers of ten that fit in a `u32`, from largest to smallest.
+const POWERS_OF_TEN: [u32; 10] = [
+    1_000_000_000,
+    100_000_000,
+    10_000_000,
+    1_000_000,
+    100_000,
+    10_000,
+    1_000,
+    100,
+    10,
+    1,
+];
+
+/// Formats `value` into `buffer` as `0x` followed by exactly `digits` hexadecimal digits.
+///
+/// `digits` must be at most 8.
+fn encode_hex(buffer: &mut [u8; 10], value: u32, digits: usize) -> &str {
+    buffer[0] = b'0';
+    buffer[1] = b'x';
+    for (index, byte) in buffer[2..2 + digits].iter_mut().enumerate() {
+        let shift = 4 * (digits - 1 - index);
+        *byte = HEX_DIGITS[(value >> shift & 0xf) as usize];
+    }
+    // SAFETY: Only ASCII characters were written to the buffer.
+    unsafe { str::from_utf8_unchecked(&buffer[..2 + digits]) }
+}
+
+/// Formats `value` into `buffer` as a decimal number without leading zeros.
+///
+/// Each digit is found by repeatedly subtracting a power of ten, as the ARM7TDMI has no divide
+/// instruction.
+fn encode_dec(buffer: &mut [u8; 10], mut value: u32) -> &str {
+    let mut len = 0;
+    for power in POWERS_OF_TEN {
+        let mut digit = b'0';
+        while value >= power {
+            value -= power;
+            digit += 1;
+        }
+        // Leading zeros are skipped, but the final digit is always kept so that zero is written.
+        if len > 0 || digit != b'0' || power == 1 {
+            buffer[len] = digit;
+            len += 1;
+        }
+    }
+    // SAFETY: Only ASCII characters were written to the buffer.
+    unsafe { str::from_utf8_unchecked(&buffer[..len]) }
+}
+
+/// Writes `bytes` in hexadecimal using `write`.
+///
+/// Bytes are separated by a space, and groups of [`BYTES_PER_GROUP`] bytes by an additional space.
+fn write_hex_slice<E, F>(bytes: &[u8], mut write: F) -> Result<(), E>
+where
+    F: FnMut(&str) -> Result<(), E>,
+{
+    for (index, &byte) in bytes.iter().enumerate() {
+        if index > 0 {
+            write(if index % BYTES_PER_GROUP == 0 {
+                "  "
+            } else {
+                " "
+            })?;
+        }
+        let digits = [
+            HEX_DIGITS[usize::from(byte >> 4)],
+            HEX_DIGITS[usize::from(byte & 0xf)],
+        ];
+        // SAFETY: Only ASCII characters are used as digits.
+        write(unsafe { str::from_utf8_unchecked(&digits) })?;
+    }
+    Ok(())
+}
+
+/// Writes `bytes` in hexadecimal followed by their ASCII characters using `write`.
+///
+/// Characters that are not printable are written as `.`.
+fn write_ascii_dump<E, F>(bytes: &[u8], mut write: F) -> Result<(), E>
+where
+    F: FnMut(&str) -> Result<(), E>,
+{
+    write_hex_slice(bytes, &mut write)?;
+    write("  |")?;
+    for &byte in bytes {
+        let character = if byte.is_ascii_graphic() || byte == b' ' {
+            byte
+        } else {
+            b'.'
+        };
+        // SAFETY: Only ASCII characters are written.
+        write(unsafe { str::from_utf8_unchecked(&[character]) })?;
+    }
+    write("|")
+}
+
+/// Displays a `u16` in hexadecimal.
+///
+/// This is created by [`hex_u16()`].
+#[derive(Clone, Copy, Debug)]
+pub struct HexU16(u16);
+
+impl Display for HexU16 {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(encode_hex(&mut [0; 10], u32::from(self.0), 4))
+    }
+}
+
+/// Displays a `u32` in hexadecimal.
+///
+/// This is created by [`hex_u32()`].
+#[derive(Clone, Copy, Debug)]
+pub struct HexU32(u32);
+
+impl Display for HexU32 {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(encode_hex(&mut [0; 10], self.0, 8))
+    }
+}
+
+/// Displays a `u32` in decimal.
+///
+/// This is created by [`dec_u32()`].
+#[derive(Clone, Copy, Debug)]
+pub struct DecU32(u32);
+
+impl Display for DecU32 {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(encode_dec(&mut [0; 10], self.0))
+    }
+}
+
+/// Displays a byte slice in hexadecimal.
+///
+/// This is created by [`hex_slice()`].
+#[derive(Clone, Copy, Debug)]
+pub struct HexSlice<'a>(&'a [u8]);
+
+impl Display for HexSlice<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write_hex_slice(self.0, |s| formatter.write_str(s))
+    }
+}
+
+/// Displays a byte slice in hexadecimal followed by its ASCII characters.
+///
+/// This is created by [`ascii_dump()`].
+#[derive(Clone, Copy, Debug)]
+pub struct AsciiDump<'a>(&'a [u8]);
+
+impl Display for AsciiDump<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write_ascii_dump(self.0, |s| formatter.write_str(s))
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for HexU16 {
+    fn fmt<W>(&self, formatter: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        formatter.write_str(encode_hex(&mut [0; 10], u32::from(self.0), 4))
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for HexU32 {
+    fn fmt<W>(&self, formatter: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        formatter.write_str(encode_hex(&mut [0; 10], self.0, 8))
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for DecU32 {
+    fn fmt<W>(&self, formatter: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        formatter.write_str(encode_dec(&mut [0; 10], self.0))
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for HexSlice<'_> {
+    fn fmt<W>(&self, formatter: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        write_hex_slice(self.0, |s| formatter.write_str(s))
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for AsciiDump<'_> {
+    fn fmt<W>(&self, formatter: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        write_ascii_dump(self.0, |s| formatter.write_str(s))
+    }
+}
+
+/// Displays `value` as `0x` followed by four zero-padded hexadecimal digits, as in `0x04a0`.
+#[must_use]
+pub const fn hex_u16(value: u16) -> HexU16 {
+    HexU16(value)
+}
+
+/// Displays `value` as `0x` followed by eight zero-padded hexadecimal digits, as in `0x080004a0`.
+#[must_use]
+pub const fn hex_u32(value: u32) -> HexU32 {
+    HexU32(value)
+}
+
+/// Displays `value` in decimal, as in `1234`.
+#[must_use]
+pub const fn dec_u32(value: u32) -> DecU32 {
+    DecU32(value)
+}
+
+/// Displays `bytes` as space-separated hexadecimal, as in `2e 00 00 ea 41 47 42`.
+///
+/// Every eight bytes are separated by an additional space.
+#[must_use]
+pub const fn hex_slice(bytes: &[u8]) -> HexSlice<'_> {
+    HexSlice(bytes)
+}
+
+/// Displays `bytes` as space-separated hexadecimal followed by their ASCII characters, as in
+/// `2e 00 00 ea 41 47 42  |....AGB|`.
+///
+/// Characters that are not printable are displayed as `.`.
+#[must_use]
+pub const fn ascii_dump(bytes: &[u8]) -> AsciiDump<'_> {
+    AsciiDump(bytes)
+}
@@ -0,0 +1,75 @@
+//! Formatting of records into RAM before they are written to mGBA.
+//!
+//! When buffered formatting is enabled through [`LoggerBuilder::with_buffered_formatting()`],
+//! records are formatted into a buffer on the stack while interrupts are still enabled. Interrupts
+//! then only need to be disabled while the already formatted text is copied to mGBA's log buffer.
+//!
+//! [`LoggerBuilder::with_buffered_formatting()`]: crate::LoggerBuilder::with_buffered_formatting()
+
+use core::{fmt, fmt::Write, str};
+
+/// The number of bytes a single record can be formatted into.
+///
+/// This is documented on [`LoggerBuilder::with_buffered_formatting()`], which must be updated if
+/// it changes.
+///
+/// [`LoggerBuilder::with_buffered_formatting()`]: crate::LoggerBuilder::with_buffered_formatting()
+const CAPACITY: usize = 256;
+
+/// A buffer that a single record is formatted into.
+pub(crate) struct FormatBuffer {
+    /// The formatted text.
+    ///
+    /// Only the first `len` bytes are initialized text, which is always valid UTF-8.
+    buffer: [u8; CAPACITY],
+    /// The number of bytes of `buffer` in use.
+    len: usize,
+    /// Whether text was discarded because it did not fit.
+    truncated: bool,
+}
+
+impl FormatBuffer {
+    /// Creates a new, empty buffer.
+    pub(crate) const fn new() -> Self {
+        Self {
+            buffer: [0; CAPACITY],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    /// Returns the formatted text.
+    pub(crate) fn as_str(&self) -> &str {
+        // SAFETY: Only whole characters are ever written to the buffer, so its contents are always
+        // valid UTF-8.
+        unsafe { str::from_utf8_unchecked(&self.buffer[..self.len]) }
+    }
+
+    /// Returns whether text was discarded because it did not fit.
+    pub(crate) fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl Write for FormatBuffer {
+    /// Appends `s` to the buffer.
+    ///
+    /// If `s` does not fit, as many of its characters as fit are appended and an error is
+    /// returned.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let available = CAPACITY - self.len;
+        let (text, result) = if s.len() <= available {
+            (s, Ok(()))
+        } else {
+            let mut end = available;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            self.truncated = true;
+            (&s[..end], Err(fmt::Error))
+        };
+        self.buffer[self.len..self.len + text.len()].copy_from_slice(text.as_bytes());
+        self.len += text.len();
+        result
+    }
+}
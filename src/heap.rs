@@ -0,0 +1,69 @@
+//! Reporting of heap usage.
+//!
+//! See [`log_heap_stats!`](crate::log_heap_stats!) for details.
+
+use crate::{without_interrupts, LOGGER};
+use core::fmt::{self, Display};
+
+/// A snapshot of how much of a heap is in use.
+///
+/// This is returned by the function registered through
+/// [`LoggerBuilder::with_heap_stats()`](crate::LoggerBuilder::with_heap_stats()), and is logged by
+/// [`log_heap_stats!`](crate::log_heap_stats!), as in
+/// `heap: used=768 free=256 largest_free=64 fragmentation=75%`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct HeapUsage {
+    /// The number of bytes currently allocated.
+    pub used: usize,
+    /// The number of bytes available for allocation.
+    pub free: usize,
+    /// The size of the largest single block available for allocation, if the allocator tracks it.
+    pub largest_free: Option<usize>,
+}
+
+impl HeapUsage {
+    /// Returns the percentage of free bytes that are not part of the largest free block.
+    ///
+    /// A heap whose free space is a single contiguous block is not fragmented at all, while a
+    /// fragmentation close to `100` means that even small allocations may fail despite plenty of
+    /// free space. Returns `None` if the size of the largest free block is not known.
+    #[must_use]
+    pub fn fragmentation(&self) -> Option<u8> {
+        let largest_free = self.largest_free?;
+        if self.free == 0 {
+            return Some(0);
+        }
+        // The percentage is computed in 64 bits, so it can not overflow for any heap that fits in
+        // the address space, and is at most `100`, so it always fits in a `u8`.
+        #[allow(clippy::cast_possible_truncation)]
+        let contiguous = (largest_free.min(self.free) as u64 * 100 / self.free as u64) as u8;
+        Some(100 - contiguous)
+    }
+}
+
+impl Display for HeapUsage {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "used={} free={}", self.used, self.free)?;
+        if let (Some(largest_free), Some(fragmentation)) = (self.largest_free, self.fragmentation())
+        {
+            write!(
+                formatter,
+                " largest_free={largest_free} fragmentation={fragmentation}%"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the current heap usage, as reported by the function registered through
+/// [`LoggerBuilder::with_heap_stats()`](crate::LoggerBuilder::with_heap_stats()).
+///
+/// Returns `None` if no function was registered, or if no logger was initialized. The function is
+/// called with interrupts enabled, so that it may itself disable them while reading the heap.
+#[doc(hidden)]
+#[must_use]
+pub fn __heap_usage() -> Option<HeapUsage> {
+    // SAFETY: Interrupts are disabled, so the configuration cannot be accessed concurrently.
+    let heap_stats = without_interrupts(|| unsafe { (*LOGGER.config.get()).heap_stats });
+    heap_stats.map(|heap_stats| heap_stats())
+}
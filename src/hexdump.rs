@@ -0,0 +1,97 @@
+//! Hex dumps of memory regions.
+//!
+//! This is the implementation of the [`hexdump!`](crate::hexdump!) macro.
+
+use core::{fmt, fmt::Display};
+
+/// The number of bytes displayed on each line of a hex dump.
+const BYTES_PER_LINE: usize = 16;
+
+/// A single line of a hex dump.
+///
+/// This displays the address of the first byte, followed by up to sixteen bytes in hexadecimal,
+/// followed by those same bytes as ASCII characters. Non-printable characters are displayed as
+/// `.`.
+struct Line {
+    /// The address of the first byte.
+    address: usize,
+    /// The bytes displayed on this line.
+    bytes: [u8; BYTES_PER_LINE],
+    /// The number of valid bytes within `bytes`.
+    len: usize,
+}
+
+impl Display for Line {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{:08x}:", self.address)?;
+        for (index, byte) in self.bytes.iter().enumerate() {
+            if index < self.len {
+                write!(formatter, " {byte:02x}")?;
+            } else {
+                // Pad the final line so that the ASCII columns line up.
+                formatter.write_str("   ")?;
+            }
+        }
+        formatter.write_str("  |")?;
+        for &byte in &self.bytes[..self.len] {
+            let character = if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            };
+            write!(formatter, "{character}")?;
+        }
+        formatter.write_str("|")
+    }
+}
+
+/// Logs a hex dump of `len` bytes starting at `address` at the debug level.
+///
+/// Each byte is obtained by calling `read` with its offset from `address`.
+fn dump<F>(target: &str, address: usize, len: usize, read: F)
+where
+    F: Fn(usize) -> u8,
+{
+    // This also allows the dump to be compiled out entirely when the debug level is disabled at
+    // compile time.
+    if !log::log_enabled!(target: target, log::Level::Debug) {
+        return;
+    }
+    for start in (0..len).step_by(BYTES_PER_LINE) {
+        let mut line = Line {
+            address: address + start,
+            bytes: [0; BYTES_PER_LINE],
+            len: (len - start).min(BYTES_PER_LINE),
+        };
+        for (offset, byte) in line.bytes[..line.len].iter_mut().enumerate() {
+            *byte = read(start + offset);
+        }
+        log::debug!(target: target, "{line}");
+    }
+}
+
+/// Logs a hex dump of `data`.
+///
+/// This is an implementation detail of the [`hexdump!`](crate::hexdump!) macro. It is not
+/// considered part of the public API and should not be used directly by external code.
+#[doc(hidden)]
+pub fn __hexdump(target: &str, data: &[u8]) {
+    dump(target, data.as_ptr() as usize, data.len(), |offset| {
+        data[offset]
+    });
+}
+
+/// Logs a hex dump of `len` bytes starting at `ptr`.
+///
+/// This is an implementation detail of the [`hexdump!`](crate::hexdump!) macro. It is not
+/// considered part of the public API and should not be used directly by external code.
+///
+/// # Safety
+/// `ptr` must be valid for reads of `len` bytes.
+#[doc(hidden)]
+pub unsafe fn __hexdump_raw(target: &str, ptr: *const u8, len: usize) {
+    dump(target, ptr as usize, len, |offset| {
+        // SAFETY: The caller guarantees that `ptr` is valid for reads of `len` bytes.
+        unsafe { ptr.add(offset).read_volatile() }
+    });
+}
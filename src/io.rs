@@ -0,0 +1,131 @@
+//! Snapshots of the Game Boy Advance's I/O registers.
+//!
+//! This is the implementation of [`dump_io_registers()`](crate::dump_io_registers()).
+
+use crate::fmt::hex_u16;
+use core::{
+    fmt::{self, Display},
+    ops::BitOr,
+};
+
+/// A selection of groups of I/O registers to be logged by
+/// [`dump_io_registers()`](crate::dump_io_registers()).
+///
+/// Groups are combined using `|`, as in `IoRegisters::DISPLAY | IoRegisters::DMA`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IoRegisters(u8);
+
+impl IoRegisters {
+    /// `DISPCNT`, `DISPSTAT`, and `VCOUNT`.
+    pub const DISPLAY: Self = Self(1 << 0);
+    /// `BG0CNT` through `BG3CNT`.
+    pub const BACKGROUNDS: Self = Self(1 << 1);
+    /// `DMA0CNT_H` through `DMA3CNT_H`.
+    ///
+    /// The other DMA registers can only be written to, so they are not logged.
+    pub const DMA: Self = Self(1 << 2);
+    /// The counter and control registers of timers 0 through 3, `TM0CNT_L` through `TM3CNT_H`.
+    pub const TIMERS: Self = Self(1 << 3);
+    /// `IE`, `IF`, and `IME`.
+    pub const INTERRUPTS: Self = Self(1 << 4);
+    /// Every group of registers.
+    pub const ALL: Self = Self(0x1f);
+
+    /// Returns whether every group in `other` is also selected by `self`.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for IoRegisters {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// The registers of each group, by name and address, in the order they are logged.
+const GROUPS: [(IoRegisters, &[(&str, usize)]); 5] = [
+    (
+        IoRegisters::DISPLAY,
+        &[
+            ("DISPCNT", 0x0400_0000),
+            ("DISPSTAT", 0x0400_0004),
+            ("VCOUNT", 0x0400_0006),
+        ],
+    ),
+    (
+        IoRegisters::BACKGROUNDS,
+        &[
+            ("BG0CNT", 0x0400_0008),
+            ("BG1CNT", 0x0400_000A),
+            ("BG2CNT", 0x0400_000C),
+            ("BG3CNT", 0x0400_000E),
+        ],
+    ),
+    (
+        IoRegisters::DMA,
+        &[
+            ("DMA0CNT_H", 0x0400_00BA),
+            ("DMA1CNT_H", 0x0400_00C6),
+            ("DMA2CNT_H", 0x0400_00D2),
+            ("DMA3CNT_H", 0x0400_00DE),
+        ],
+    ),
+    (
+        IoRegisters::TIMERS,
+        &[
+            ("TM0CNT_L", 0x0400_0100),
+            ("TM0CNT_H", 0x0400_0102),
+            ("TM1CNT_L", 0x0400_0104),
+            ("TM1CNT_H", 0x0400_0106),
+            ("TM2CNT_L", 0x0400_0108),
+            ("TM2CNT_H", 0x0400_010A),
+            ("TM3CNT_L", 0x0400_010C),
+            ("TM3CNT_H", 0x0400_010E),
+        ],
+    ),
+    (
+        IoRegisters::INTERRUPTS,
+        &[
+            ("IE", 0x0400_0200),
+            ("IF", 0x0400_0202),
+            ("IME", 0x0400_0208),
+        ],
+    ),
+];
+
+/// Displays the current values of a group of registers, as in `DISPCNT=0x0403 DISPSTAT=0x0008`.
+struct Registers(&'static [(&'static str, usize)]);
+
+impl Display for Registers {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for (index, &(name, address)) in self.0.iter().enumerate() {
+            if index > 0 {
+                formatter.write_str(" ")?;
+            }
+            // SAFETY: Every address is a readable halfword I/O register.
+            let value = unsafe { (address as *const u16).read_volatile() };
+            write!(formatter, "{name}={}", hex_u16(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// Logs the selected groups of I/O registers at the debug level.
+///
+/// This is the implementation of [`dump_io_registers()`](crate::dump_io_registers()).
+pub(crate) fn dump(registers: IoRegisters) {
+    // This also allows the dump to be compiled out entirely when the debug level is disabled at
+    // compile time.
+    if !log::log_enabled!(log::Level::Debug) {
+        return;
+    }
+    for (group, list) in GROUPS {
+        if registers.contains(group) {
+            log::debug!("{}", Registers(list));
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Rendering of structured key-value pairs.
+//!
+//! This is only available when the `kv` feature is enabled.
+
+use core::{fmt, fmt::Write};
+use log::{
+    kv,
+    kv::{Key, Value, VisitSource},
+    Record,
+};
+
+/// Writes each key-value pair it visits as ` key=value`.
+struct KeyValueWriter<'a, W>(&'a mut W);
+
+impl<'kvs, W> VisitSource<'kvs> for KeyValueWriter<'_, W>
+where
+    W: Write,
+{
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        write!(self.0, " {key}={value}")?;
+        Ok(())
+    }
+}
+
+/// Writes the key-value pairs of `record` to `writer`, each preceded by a space.
+pub(crate) fn write_key_values<W>(writer: &mut W, record: &Record) -> fmt::Result
+where
+    W: Write,
+{
+    record
+        .key_values()
+        .visit(&mut KeyValueWriter(writer))
+        .map_err(|_| fmt::Error)
+}
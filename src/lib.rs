@@ -15,15 +15,132 @@
 //! | Error | [`log::error!`]      |                                                                                   |
 //! | Fatal | [`mgba_log::fatal!`] | Not a standard [`log`] level. Only usable when using this logging implementation. |
 //!
+//! Additionally, [`mgba_log::print!`] and [`mgba_log::println!`] print directly to mGBA's log at
+//! the `Info` level without requiring the logger to be initialized. [`mgba_log::eprint!`] and
+//! [`mgba_log::eprintln!`] do the same at the `Error` level.
+//!
+//! # Features
+//! - `trace-as-debug`: Logs records at the `Trace` level at mGBA's `Debug` level, prefixed with
+//!   `[TRACE]`, instead of silently dropping them.
+//! - `critical-section`: Uses the [`critical-section`] implementation provided by the program
+//!   to prevent interrupts from disturbing logging, instead of writing to the `IME` register
+//!   directly. This keeps interrupt masking consistent with crates such as `agb` and `gba`.
+//! - `agb`: Enables the `critical-section` feature, so that interrupts are disabled through the
+//!   critical section implementation `agb` provides, rather than by writing to `IME` behind its
+//!   back. As `agb` provides its own panic handler, this cannot be combined with the
+//!   `panic-handler` or `crash-dump` features. See [Using with `agb`](#using-with-agb) for
+//!   details.
+//! - `no-ime`: Never disables interrupts while logging, removing the overhead of reading and
+//!   writing the `IME` register for every record. Programs enabling this feature must not log,
+//!   print, or change the logger's configuration from within interrupt handlers. This takes
+//!   precedence over the `critical-section` feature.
+//! - `disabled`: Compiles the crate down to no-ops, so that release builds can keep the same
+//!   logging code while none of it ends up in the ROM. Initialization always succeeds without
+//!   installing a logger, [`is_mgba()`] always returns `false`, nothing is ever written to mGBA's
+//!   debug registers, and [`fatal!`] only halts execution. This also enables the [`log`] crate's
+//!   `max_level_off` feature, compiling every `log` macro out of the program, so it cannot be
+//!   combined with this crate's `max_level_*` features.
+//! - `iwram`: Places the code copying text into mGBA's log buffer in the `.iwram` section and
+//!   compiles it as ARM code, which runs considerably faster than Thumb code executed from ROM.
+//!   The program's linker script must place `.iwram` sections in IWRAM, and its runtime must copy
+//!   them there before logging, as is done by crates such as `agb` and `gba`. The linker must also
+//!   generate ARMv4T interworking veneers for calls between ROM and IWRAM, as GNU `ld` does.
+//! - `kv`: Enables the [`log`] crate's `kv` feature, and appends the key-value pairs of each
+//!   record to its message as ` key=value`, after the file and line if they are logged.
+//! - `timestamps`: Starts a cycle counter using hardware timers 2 and 3 during initialization,
+//!   and prefixes every message with the time elapsed since then, as in `[12.345ms] `. This gives
+//!   sub-frame resolution when investigating performance. Timestamps wrap around after about 256
+//!   seconds. The program must not use timers 2 and 3 itself.
+//! - `post-mortem`: Keeps the most recent messages of each session in the `.noinit` section,
+//!   allowing them to be re-emitted by `recover_previous_session()` after a soft reset. The
+//!   program's linker script must place `.noinit` sections in EWRAM without initializing them,
+//!   such as by marking them `NOLOAD`, and its runtime must not clear them.
+//! - `max_level_*` and `release_max_level_*`: Set the maximum level at compile time, in the same
+//!   way as the [`log`] crate's features of the same names, which they enable. Records above the
+//!   maximum level are compiled out of the `log` macros and this crate's macros entirely, and the
+//!   logger is never configured to accept them.
+//! - `ufmt`: Provides the `ulog!`, `udebug!`, `uinfo!`, `uwarn!`, and `uerror!` macros, which
+//!   format messages using [`ufmt`] rather than [`core::fmt`], and implements `ufmt::uWrite` for
+//!   [`MgbaWriter`]. Programs formatting all of their messages this way avoid
+//!   linking `core::fmt`'s formatting machinery, which can take up tens of kilobytes of ROM.
+//! - `embedded-io`: Implements [`embedded_io::Write`] for [`MgbaWriter`], allowing libraries
+//!   writing to `embedded-io` sinks to write to mGBA's log directly.
+//! - `panic-handler`: Provides a panic handler that logs the CPU's registers, including the
+//!   CPSR, and the top 64 bytes of the stack at the `Error` level, followed by the panic message at
+//!   the `Fatal` level. The registers are captured from within the panic handler, but the stack
+//!   pointer, link register, and stack contents identify the calls that led to the panic. Programs
+//!   enabling this feature must not define their own `#[panic_handler]`.
+//! - `crash-dump`: Enables the `panic-handler` feature, and replaces its registers and stack lines
+//!   with a compact crash dump holding the registers, the top 64 bytes of the stack, and the build
+//!   id set through `LoggerBuilder::with_build_id()`. The dump is logged hex encoded at the `Error`
+//!   level, and is also written to cartridge SRAM if an offset is set through
+//!   `LoggerBuilder::with_crash_dump_sram()`.
+//! - `entry`: Provides the `#[entry]` attribute, which initializes the logger at the start of
+//!   `main`, tolerating the absence of mGBA, and optionally logs a startup banner. This enables the
+//!   `panic-handler` feature.
+//! - `instrument`: Provides the `#[instrument]` attribute, which logs entering and exiting a
+//!   function at the `Debug` level, along with the values of selected arguments, and the cycles
+//!   spent within it if the `timestamps` feature is also enabled.
+//! - `test-runner`: Provides `test_runner()`, a runner for the unstable `custom_test_frameworks`
+//!   feature that runs tests on the Game Boy Advance, logs `TEST name ... ok` or
+//!   `TEST name ... FAILED` for each, and halts mGBA with a summary at the `Fatal` level. This
+//!   enables the `panic-handler` feature, which reports the failing test.
+//! - `backtrace`: Logs the return addresses of the calls that led to each fatal message at the
+//!   `Error` level, as in `backtrace: 0x080001c9 0x080001e9`, before the message itself. This
+//!   includes the messages logged by the panic handler of the `panic-handler` feature. The return
+//!   addresses are found by walking the chain of frame pointers, so the program, including `core`
+//!   if it is built through `build-std`, must be compiled with `-Cforce-frame-pointers=yes`.
+//! - `allocator`: Provides `LoggingAllocator`, which wraps the program's global allocator and
+//!   logs any failed allocation at the `Error` level, along with its size and alignment and the
+//!   `HeapStats` at the time.
+//! - `mock`: Captures messages sent to mGBA in memory instead of writing them to mGBA's debug
+//!   registers, allowing logging code to be tested on the host, including under Miri. Captured
+//!   messages are returned by `mock::capture()`. This links the standard library, and enables the
+//!   `no-ime` feature. See the `mock` module for details.
+//!
 //! # Compatibility
 //! This logger uses memory mapped IO registers specific to the Game Boy Advance. It is therefore
 //! only safe to use this library when building to run on the Game Boy Advance or a Game Boy
 //! Advance emulator.
 //!
 //! If this logger is attempted to be initialized when not running on mGBA, it will fail to
-//! initialize with an [`Error`] identifying the failure.
+//! initialize with an [`Error`] identifying the failure. Whether the program is running on mGBA
+//! can also be checked directly using [`is_mgba()`].
+//!
+//! Integers can be formatted without `core::fmt`'s integer formatting code using the adapters in
+//! the [`fmt`] module, which is useful when logging register values many times per frame.
+//!
+//! Assertions can be made using [`mgba_assert!`] and [`mgba_assert_eq!`], which log failures at
+//! the `Fatal` level so that mGBA halts and displays them.
 //!
+//! # Using with `agb`
+//! `agb` prints to mGBA through its own `agb::println!` and panic handler, which write to mGBA's
+//! debug registers directly rather than installing a [`log`] logger. This crate can therefore be
+//! initialized alongside it, taking over the [`log`] macros while `agb`'s own output keeps
+//! working. Enabling the `agb` feature makes this crate disable interrupts through `agb`'s
+//! critical sections, so that the two never toggle `IME` independently of each other, and so
+//! that `agb`'s interrupt handlers can not print in the middle of a record being written.
+//!
+//! ``` ignore
+//! #[agb::entry]
+//! fn main(mut gba: agb::Gba) -> ! {
+//!     mgba_log::init().expect("unable to initialize mGBA logger");
+//!     log::info!("Hello, world!");
+//!     loop {}
+//! }
+//! ```
+//!
+//! As `agb` already provides a panic handler, panics are reported by `agb` rather than by this
+//! crate's `panic-handler` feature, which cannot be enabled along with the `agb` feature.
+//!
+//! [`critical-section`]: https://docs.rs/critical-section/latest/critical_section/
+//! [`ufmt`]: https://docs.rs/ufmt/latest/ufmt/
+//! [`embedded_io::Write`]: https://docs.rs/embedded-io/latest/embedded_io/trait.Write.html
 //! [`mgba_log::fatal!`]: fatal!
+//! [`mgba_log::print!`]: print!
+//! [`mgba_log::println!`]: println!
+//! [`mgba_log::eprint!`]: eprint!
+//! [`mgba_log::eprintln!`]: eprintln!
 
 #![no_std]
 #![warn(clippy::pedantic, missing_docs)]
@@ -32,52 +149,405 @@
     clippy::doc_markdown,
 )]
 
+#[cfg(all(feature = "agb", feature = "panic-handler"))]
+compile_error!(
+    "the `panic-handler` and `crash-dump` features cannot be used with the `agb` feature, as `agb` \
+     provides its own panic handler"
+);
+
+mod agbprint;
+#[cfg(feature = "allocator")]
+mod allocator;
+mod backend;
+#[cfg(feature = "backtrace")]
+mod backtrace;
+#[cfg(feature = "crash-dump")]
+mod crash_dump;
+mod directives;
+mod ext;
+mod fixed;
+pub mod fmt;
+mod format;
+mod heap;
+mod hexdump;
+mod io;
+#[cfg(feature = "kv")]
+mod kv;
+mod mask;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod nocash;
+#[cfg(feature = "panic-handler")]
+mod panic;
+#[cfg(feature = "post-mortem")]
+mod post_mortem;
+mod racy;
+mod scope;
+mod sram;
+mod stack;
+mod staging;
+mod stats;
+#[cfg(feature = "test-runner")]
+mod test_runner;
+mod throttle;
+mod timer;
+mod uart;
+
+#[cfg(feature = "allocator")]
+pub use allocator::{HeapStats, LoggingAllocator};
+pub use backend::Backend;
+pub use directives::Directives;
+pub use ext::{OptionExt, ResultExt};
+pub use fixed::fatal_fixed;
+#[doc(hidden)]
+pub use heap::__heap_usage;
+pub use heap::HeapUsage;
+#[doc(hidden)]
+pub use hexdump::{__hexdump, __hexdump_raw};
+pub use io::IoRegisters;
+pub use mask::InterruptMask;
+#[cfg(feature = "entry")]
+pub use mgba_log_macros::entry;
+#[cfg(feature = "instrument")]
+pub use mgba_log_macros::instrument;
+#[doc(hidden)]
+pub use racy::RacyCell as __RacyCell;
+use racy::RacyCell;
+#[cfg(feature = "instrument")]
+#[doc(hidden)]
+pub use scope::__Instrument;
+#[doc(hidden)]
+pub use scope::__Scope;
+use sram::SramLog;
+#[doc(hidden)]
+pub use stack::__stack_usage;
+pub use stack::StackUsage;
+pub use stats::{LevelStats, Stats};
+#[cfg(feature = "test-runner")]
+pub use test_runner::{test_runner, Testable};
+#[doc(hidden)]
+pub use throttle::{__Skipped, __every_n, __once};
+#[doc(hidden)]
+pub use timer::__TimeScope;
+pub use uart::BaudRate;
+
+#[cfg(not(any(feature = "critical-section", feature = "no-ime")))]
+use core::sync::{atomic, atomic::compiler_fence};
 use core::{
+    cell::UnsafeCell,
     convert::Into,
-    fmt,
-    fmt::{write, Display, Write},
-    sync::{atomic, atomic::compiler_fence},
+    fmt::{write, Arguments, Display, Formatter, Write},
+    panic::Location,
 };
+use fmt::{dec_u32, HEX_DIGITS};
+use format::FormatBuffer;
 use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
 
+/// Re-export of the [`log`] crate for use within this crate's macros.
+///
+/// This is an implementation detail. It is not considered part of the public API and should not be
+/// used directly by external code.
+#[doc(hidden)]
+pub use log as __log;
+
 /// Buffer for log messages to be written to.
+///
+/// The buffer supports byte, halfword, and word writes.
+#[cfg_attr(feature = "mock", allow(dead_code))]
 const MGBA_LOG_BUFFER: *mut u8 = 0x04FF_F600 as *mut u8;
+/// The length of the log buffer, in bytes.
+const MGBA_LOG_BUFFER_LEN: usize = 256;
+/// The maximum length of a continuation marker, in bytes.
+///
+/// This is documented on [`LoggerBuilder::with_continuation_marker()`], which must be updated if
+/// it changes.
+const MAX_CONTINUATION_MARKER_LEN: usize = 16;
+/// The maximum length of a continuation line prefix, in bytes.
+///
+/// This is documented on [`LoggerBuilder::with_continuation_line_prefix()`], which must be
+/// updated if it changes.
+const MAX_LINE_PREFIX_LEN: usize = 16;
+/// The marker ending truncated messages.
+const TRUNCATION_MARKER: &[u8] = "…".as_bytes();
+/// The position past which a truncated message can not be marked as truncated.
+///
+/// Truncated messages are at most 255 bytes long, leaving room for the null byte ending them.
+const TRUNCATION_LIMIT: usize = MGBA_LOG_BUFFER_LEN - 1 - TRUNCATION_MARKER.len();
 /// Send register.
 ///
 /// Writing a level to this address drains the log buffer, logging it at the given log level.
+#[cfg_attr(feature = "mock", allow(dead_code))]
 const MGBA_LOG_SEND: *mut Level = 0x04FF_F700 as *mut Level;
 /// Register for enabling logging.
 ///
 /// Writing a value of `0xC0DE` to this address will initialize logging. If logging was initialized
 /// properly in mGBA, reading this address will return the value `0x1DEA`.
+#[cfg_attr(feature = "mock", allow(dead_code))]
 const MGBA_LOG_ENABLE: *mut u16 = 0x04FF_F780 as *mut u16;
 /// Interrupt Master Enable.
 ///
 /// This register allows enabling and disabling interrupts.
 const IME: *mut bool = 0x0400_0208 as *mut bool;
 
+/// Returns mGBA's log buffer, which is [`MGBA_LOG_BUFFER_LEN`] bytes long.
+#[cfg(not(feature = "mock"))]
+fn mgba_log_buffer() -> *mut u8 {
+    MGBA_LOG_BUFFER
+}
+
+/// Returns mGBA's log buffer, which is [`MGBA_LOG_BUFFER_LEN`] bytes long.
+#[cfg(feature = "mock")]
+fn mgba_log_buffer() -> *mut u8 {
+    mock::buffer()
+}
+
+/// Sends the contents of mGBA's log buffer at the given `level`.
+///
+/// # Safety
+/// No message may be written to the log buffer at the same time.
+#[cfg(not(feature = "mock"))]
+unsafe fn mgba_log_send(level: Level) {
+    // SAFETY: This is guaranteed to be a write to a valid address.
+    unsafe { MGBA_LOG_SEND.write_volatile(level) };
+}
+
+/// Sends the contents of mGBA's log buffer at the given `level`.
+///
+/// # Safety
+/// No message may be written to the log buffer at the same time.
+#[cfg(feature = "mock")]
+unsafe fn mgba_log_send(level: Level) {
+    mock::send(level);
+}
+
+/// Writes `value` to mGBA's enable register.
+///
+/// Writing `0xC0DE` enables mGBA's debug output, and any other value disables it.
+#[cfg(not(feature = "mock"))]
+fn mgba_log_enable(value: u16) {
+    // SAFETY: This is guaranteed to be a valid write.
+    unsafe { MGBA_LOG_ENABLE.write_volatile(value) };
+}
+
+/// Writes `value` to mGBA's enable register.
+///
+/// Writing `0xC0DE` enables mGBA's debug output, and any other value disables it.
+#[cfg(feature = "mock")]
+fn mgba_log_enable(value: u16) {
+    mock::enable(value);
+}
+
+/// Reads mGBA's enable register.
+///
+/// This reads `0x1DEA` if mGBA's debug output is enabled.
+#[cfg(not(feature = "mock"))]
+fn mgba_log_read_enable() -> u16 {
+    // SAFETY: This is guaranteed to be a valid read.
+    unsafe { MGBA_LOG_ENABLE.read_volatile() }
+}
+
+/// Reads mGBA's enable register.
+///
+/// This reads `0x1DEA` if mGBA's debug output is enabled.
+#[cfg(feature = "mock")]
+fn mgba_log_read_enable() -> u16 {
+    if mock::is_enabled() {
+        0x1DEA
+    } else {
+        0
+    }
+}
+
+/// Returns whether mGBA's debug output is enabled.
+fn mgba_log_enabled() -> bool {
+    mgba_log_read_enable() == 0x1DEA
+}
+
+/// Returns the end of the memory region containing `address`, if stacks may be located in it.
+///
+/// Stacks are usually placed in IWRAM, but may also be placed in EWRAM.
+#[cfg(any(feature = "backtrace", feature = "panic-handler"))]
+fn stack_region_end(address: usize) -> Option<usize> {
+    [0x0300_0000..0x0300_8000, 0x0200_0000..0x0204_0000]
+        .into_iter()
+        .find(|region| region.contains(&address))
+        .map(|region| region.end)
+}
+
+/// The most verbose level filter that mGBA can display.
+///
+/// mGBA has no analog for the `Trace` level, so this is `Debug` unless the `trace-as-debug`
+/// feature is enabled.
+#[cfg(feature = "trace-as-debug")]
+const MGBA_LEVEL_FILTER: LevelFilter = LevelFilter::Trace;
+#[cfg(not(feature = "trace-as-debug"))]
+const MGBA_LEVEL_FILTER: LevelFilter = LevelFilter::Debug;
+/// The most verbose level filter supported by this logger.
+///
+/// This is [`MGBA_LEVEL_FILTER`], further limited by the maximum level the [`log`] crate was
+/// compiled with, so that the logger is never configured to accept records that the `log` macros
+/// have already compiled out.
+const MAX_LEVEL_FILTER: LevelFilter =
+    if (log::STATIC_MAX_LEVEL as usize) < (MGBA_LEVEL_FILTER as usize) {
+        log::STATIC_MAX_LEVEL
+    } else {
+        MGBA_LEVEL_FILTER
+    };
+/// The maximum number of targets that can be allowed or denied at once.
+const TARGET_FILTER_CAPACITY: usize = 8;
+
+/// Disables interrupts for as long as it is alive.
+///
+/// Interrupts are disabled using the current [`InterruptMask`], which disables all interrupts
+/// using `IME` by default. The previous state is restored when the guard is dropped. Interrupts
+/// triggered while the guard is alive will be handled once interrupts are reenabled.
+#[cfg(not(any(feature = "critical-section", feature = "no-ime")))]
+#[derive(Debug)]
+struct InterruptGuard {
+    /// Restores the state from before the guard was created.
+    release: fn(u16),
+    /// The state from before the guard was created.
+    state: u16,
+}
+
+#[cfg(not(any(feature = "critical-section", feature = "no-ime")))]
+impl InterruptGuard {
+    /// Disables interrupts, storing the previous state.
+    fn new() -> Self {
+        let mask = mask::current();
+        let state = mask.acquire();
+        // Compiler fences are used to prevent accesses from being reordered outside of the section
+        // with interrupts disabled during compilation.
+        compiler_fence(atomic::Ordering::Acquire);
+        Self {
+            release: mask.release(),
+            state,
+        }
+    }
+}
+
+#[cfg(not(any(feature = "critical-section", feature = "no-ime")))]
+impl Drop for InterruptGuard {
+    /// Restores the previous state.
+    fn drop(&mut self) {
+        compiler_fence(atomic::Ordering::Release);
+        (self.release)(self.state);
+    }
+}
+
+/// Holds a critical section for as long as it is alive.
+///
+/// The critical section is acquired using the [`critical_section`] implementation provided by the
+/// program, and is released when the guard is dropped.
+#[cfg(all(feature = "critical-section", not(feature = "no-ime")))]
+#[derive(Debug)]
+struct InterruptGuard {
+    /// The state to restore when the critical section is released.
+    restore_state: critical_section::RestoreState,
+}
+
+#[cfg(all(feature = "critical-section", not(feature = "no-ime")))]
+impl InterruptGuard {
+    /// Acquires the critical section.
+    fn new() -> Self {
+        // SAFETY: The critical section is released when this guard is dropped. Guards are always
+        // dropped in the reverse order of their creation.
+        let restore_state = unsafe { critical_section::acquire() };
+        Self { restore_state }
+    }
+}
+
+#[cfg(all(feature = "critical-section", not(feature = "no-ime")))]
+impl Drop for InterruptGuard {
+    /// Releases the critical section.
+    fn drop(&mut self) {
+        // SAFETY: The state was returned by the corresponding `acquire()` call in `new()`, and
+        // guards are always dropped in the reverse order of their creation.
+        unsafe { critical_section::release(self.restore_state) };
+    }
+}
+
+/// Does nothing.
+///
+/// With the `no-ime` feature enabled, interrupts are never disabled, as the program guarantees that
+/// it does not log from interrupt handlers.
+#[cfg(feature = "no-ime")]
+#[derive(Debug)]
+struct InterruptGuard;
+
+#[cfg(feature = "no-ime")]
+impl InterruptGuard {
+    /// Does nothing.
+    fn new() -> Self {
+        Self
+    }
+}
+
+/// Runs `f` with interrupts disabled, restoring the previous interrupt enable value afterwards.
+///
+/// This prevents synchronization issues when `f` accesses state that may also be accessed within
+/// an interrupt handler.
+fn without_interrupts<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let _guard = InterruptGuard::new();
+    f()
+}
+
+/// Returns whether the CPU is currently handling an interrupt.
+///
+/// This is the case when the CPU is in IRQ mode, or when IRQs are disabled in the CPSR, as they
+/// are upon entering an interrupt handler. Interrupt handlers that switch to another mode and
+/// reenable IRQs in the CPSR, such as to allow nested interrupts, are not detected.
+#[cfg(target_arch = "arm")]
+#[instruction_set(arm::a32)]
+fn in_interrupt() -> bool {
+    let cpsr: u32;
+    // SAFETY: Reading the CPSR has no side effects.
+    unsafe {
+        core::arch::asm!("mrs {}, cpsr", out(reg) cpsr, options(nomem, nostack, preserves_flags));
+    }
+    cpsr & 0x1F == 0x12 || cpsr & 0x80 != 0
+}
+
+/// Returns whether the CPU is currently handling an interrupt.
+///
+/// Interrupts can only be detected on the Game Boy Advance's ARM CPU.
+#[cfg(not(target_arch = "arm"))]
+fn in_interrupt() -> bool {
+    false
+}
+
 /// A log level within mGBA.
 ///
-/// The enum values correspond to their values within mGBA's logging system. Therefore, these
-/// values can simply be written directly to `MGBA_LOG_SEND`.
-#[derive(Clone, Copy, Debug)]
-enum Level {
+/// Every level besides [`Fatal`](Level::Fatal) corresponds to a level of the [`log`] crate.
+// The enum values correspond to their values within mGBA's logging system. Therefore, these values
+// can simply be written directly to `MGBA_LOG_SEND`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Level {
     /// Fatal causes mGBA to halt execution.
     Fatal = 0x100,
+    /// An error.
     Error = 0x101,
+    /// A warning.
     Warning = 0x102,
+    /// Informational messages.
     Info = 0x103,
+    /// Debugging messages.
     Debug = 0x104,
 }
 
 /// Attempt to convert a generic `log::Level` to an mGBA-compatible level.
 ///
 /// This will succeed for every level except `Trace`. mGBA's log system does not have a level
-/// analogous to `Trace`.
+/// analogous to `Trace`. If the `trace-as-debug` feature is enabled, `Trace` is converted to
+/// `Debug` instead.
 impl TryFrom<log::Level> for Level {
     type Error = ();
 
-    /// Can only fail when `level == log::Level::Trace`.
+    /// Can only fail when `level == log::Level::Trace` and the `trace-as-debug` feature is not
+    /// enabled.
     fn try_from(level: log::Level) -> Result<Self, <Self as TryFrom<log::Level>>::Error> {
         match level {
             log::Level::Error => Ok(Self::Error),
@@ -85,6 +555,9 @@ impl TryFrom<log::Level> for Level {
             log::Level::Info => Ok(Self::Info),
             log::Level::Debug => Ok(Self::Debug),
             // There is no analog for trace in mGBA's log system.
+            #[cfg(feature = "trace-as-debug")]
+            log::Level::Trace => Ok(Self::Debug),
+            #[cfg(not(feature = "trace-as-debug"))]
             log::Level::Trace => Err(()),
         }
     }
@@ -92,7 +565,13 @@ impl TryFrom<log::Level> for Level {
 
 /// Writes bytes directly to mGBA's log buffer for a given level.
 ///
-/// This writer automatically handles flushing the buffer when it is at capacity (256 bytes).
+/// This writer automatically handles flushing the buffer when it is at capacity (256 bytes). If a
+/// UTF-8 encoded character would not fit in the remainder of the buffer, the buffer is flushed
+/// early and the character starts the next message.
+///
+/// If a continuation marker is set, it ends each message that is continued in the next message,
+/// and starts the message continuing it. If messages are truncated instead, the remainder of a
+/// message that does not fit is discarded, and the message ends with an ellipsis.
 #[derive(Debug)]
 struct Writer {
     /// The mGBA log level of the bytes written by this writer.
@@ -102,67 +581,474 @@ struct Writer {
 
     /// The current position within the log buffer.
     index: u8,
+
+    /// Whether there is anything left to send when the writer is dropped.
+    ///
+    /// This is `true` if bytes have been written since the buffer was last sent, or if nothing has
+    /// been sent yet. It prevents a trailing newline from logging an additional empty message.
+    unsent: bool,
+
+    /// How text is written.
+    config: WriterConfig,
+
+    /// The latest position at which the current message can be truncated without splitting a
+    /// character.
+    ///
+    /// This is only used when messages are truncated, and is never past [`TRUNCATION_LIMIT`].
+    boundary: u8,
+
+    /// Whether the current message has been truncated, meaning the rest of it is discarded.
+    truncated: bool,
+
+    /// Whether the last byte written was a carriage return treated as a line break.
+    ///
+    /// A newline directly following such a carriage return does not start another message.
+    after_carriage_return: bool,
+}
+
+/// How messages that do not fit in mGBA's log buffer are handled.
+#[derive(Clone, Copy, Debug)]
+enum Overflow {
+    /// Messages are split across multiple sends.
+    ///
+    /// If the marker is not empty, messages that are split end with the marker, and their
+    /// remainder starts with the marker. The marker must be at most
+    /// [`MAX_CONTINUATION_MARKER_LEN`] bytes long and must not contain newlines or null
+    /// characters.
+    Split(&'static str),
+    /// Messages are truncated to 255 bytes, ending with an ellipsis.
+    Truncate,
+}
+
+/// Configuration of how a [`Writer`] writes text.
+#[derive(Clone, Copy, Debug)]
+struct WriterConfig {
+    /// The interface messages are sent through.
+    backend: Backend,
+    /// The region of SRAM that warnings and more severe messages are mirrored to, if any.
+    sram_log: Option<SramLog>,
+    /// How messages that do not fit in the log buffer are handled.
+    overflow: Overflow,
+    /// The character written in place of null characters.
+    ///
+    /// This must not be a newline or null character.
+    null_substitute: char,
+    /// Whether control bytes other than newlines are escaped as `\xNN`, and backslashes as `\\`.
+    escape_control: bool,
+    /// Whether `\r\n` and lone `\r` are treated as line breaks.
+    carriage_return: bool,
+    /// The distance between tab stops that tabs are expanded to, or `0` if tabs are not expanded.
+    tab_width: u8,
+    /// The prefix written at the start of each line of a message after the first.
+    ///
+    /// This must be at most [`MAX_LINE_PREFIX_LEN`] bytes long and must not contain newlines or
+    /// null characters.
+    line_prefix: &'static str,
 }
 
 impl Writer {
-    /// Creates a new writer for the given mGBA log level.
-    fn new(level: Level) -> Self {
-        Self { level, index: 0 }
+    /// Creates a new writer for the given mGBA log level, writing text as specified by `config`.
+    fn new(level: Level, config: WriterConfig) -> Self {
+        Self {
+            level,
+            index: 0,
+            unsent: true,
+            config,
+            boundary: 0,
+            truncated: false,
+            after_carriage_return: false,
+        }
+    }
+
+    /// Returns the continuation marker, which is empty if messages are not split with a marker.
+    fn marker(&self) -> &'static str {
+        match self.config.overflow {
+            Overflow::Split(marker) => marker,
+            Overflow::Truncate => "",
+        }
+    }
+
+    /// Returns the number of bytes of the log buffer available for text.
+    ///
+    /// Space for the continuation marker is reserved at the end of the buffer. When truncating,
+    /// space is reserved for the null byte ending the message instead.
+    fn capacity(&self) -> usize {
+        match self.config.overflow {
+            Overflow::Split(marker) => MGBA_LOG_BUFFER_LEN - marker.len(),
+            Overflow::Truncate => MGBA_LOG_BUFFER_LEN - 1,
+        }
     }
 
+    #[cfg_attr(
+        all(feature = "iwram", target_arch = "arm"),
+        link_section = ".iwram",
+        instruction_set(arm::a32)
+    )]
     fn write_byte(&mut self, byte: u8) {
+        if let Overflow::Truncate = self.config.overflow {
+            self.write_bytes_truncated(&[byte]);
+            return;
+        }
+        // Only possible with a continuation marker, as the buffer is otherwise sent as soon as it
+        // is full.
+        if usize::from(self.index) == self.capacity() {
+            self.continue_message();
+        }
         // Write the new byte.
         // SAFETY: This is guaranteed to be valid and in-bounds.
         unsafe {
-            MGBA_LOG_BUFFER
-                .add(self.index as usize)
-                .write_volatile(byte);
+            self.buffer().add(self.index as usize).write_volatile(byte);
         }
+        self.unsent = true;
 
         let (index, overflowed) = self.index.overflowing_add(1);
         self.index = index;
         if overflowed {
-            // SAFETY: This is guaranteed to be a write to a valid address.
-            unsafe {
-                MGBA_LOG_SEND.write_volatile(self.level);
-            }
+            self.emit(MGBA_LOG_BUFFER_LEN);
+            self.unsent = false;
         }
     }
 
+    #[cfg_attr(
+        all(feature = "iwram", target_arch = "arm"),
+        link_section = ".iwram",
+        instruction_set(arm::a32)
+    )]
     fn send(&mut self) {
-        // Write a null byte, indicating that this is the end of the message.
-        self.write_byte(b'\x00');
-        if self.index != 0 {
-            // SAFETY: This is guaranteed to be a write to a valid address.
-            unsafe {
-                MGBA_LOG_SEND.write_volatile(self.level);
+        // Write a null byte, indicating that this is the end of the message. The buffer is never
+        // full at this point, so this always fits.
+        // SAFETY: This is guaranteed to be valid and in-bounds.
+        unsafe {
+            self.buffer()
+                .add(self.index as usize)
+                .write_volatile(b'\x00');
+        }
+        self.emit(usize::from(self.index));
+        self.index = 0;
+        self.unsent = false;
+        self.boundary = 0;
+        self.truncated = false;
+    }
+
+    /// Returns whether sent messages are also kept elsewhere, such as in SRAM.
+    fn is_mirrored(&self) -> bool {
+        self.config.sram_log.is_some() || cfg!(feature = "post-mortem")
+    }
+
+    /// Returns the log buffer messages are written to.
+    ///
+    /// Mirrored messages are written to RAM first, as mGBA's log buffer cannot be read back.
+    fn buffer(&self) -> *mut u8 {
+        if self.is_mirrored() {
+            backend::ram_buffer()
+        } else {
+            self.config.backend.buffer()
+        }
+    }
+
+    /// Sends the first `len` bytes of the buffer as a message.
+    ///
+    /// If `len` is less than the length of the buffer, the message must be followed by a null
+    /// byte.
+    #[cfg_attr(
+        all(feature = "iwram", target_arch = "arm"),
+        link_section = ".iwram",
+        instruction_set(arm::a32)
+    )]
+    fn emit(&self, len: usize) {
+        if self.is_mirrored() {
+            // SAFETY: Mirrored messages are written to the buffer in RAM, and `len` is never past
+            // its end.
+            let message = unsafe { backend::ram_message(len) };
+            if let Some(sram_log) = self.config.sram_log {
+                if matches!(self.level, Level::Fatal | Level::Error | Level::Warning) {
+                    sram_log.append(self.level, message);
+                }
+            }
+            #[cfg(feature = "post-mortem")]
+            post_mortem::append(self.level, message);
+            if self.config.backend == Backend::Mgba {
+                // SAFETY: The message is never longer than mGBA's log buffer, and is followed by a
+                // null byte if it is shorter.
+                unsafe {
+                    copy_to_buffer(mgba_log_buffer(), 0, message);
+                    if len < MGBA_LOG_BUFFER_LEN {
+                        mgba_log_buffer().add(len).write_volatile(b'\x00');
+                    }
+                }
             }
-            self.index = 0;
         }
+        // SAFETY: This writer is the only one writing to the buffer, and `len` is never past its
+        // end.
+        unsafe { self.config.backend.send(self.level, len) };
+        stats::count_sent(self.level, len, self.truncated);
     }
-}
 
-impl Write for Writer {
-    /// Write the given string to the log buffer.
+    /// Sends the buffer as a message that is continued in the next message.
     ///
-    /// The buffer is flushed automatically when it becomes full.
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        for &byte in s.as_bytes() {
-            match byte {
-                b'\n' => {
-                    // For readability purposes, just start a new log line.
-                    self.send();
+    /// The continuation marker is written at the end of the sent message and at the start of the
+    /// next one. Without a continuation marker, this just sends the buffer.
+    #[cfg_attr(
+        all(feature = "iwram", target_arch = "arm"),
+        link_section = ".iwram",
+        instruction_set(arm::a32)
+    )]
+    fn continue_message(&mut self) {
+        let index = usize::from(self.index);
+        let marker = self.marker().as_bytes();
+        // SAFETY: Space for the marker is always reserved at the end of the buffer.
+        unsafe { copy_to_buffer(self.buffer(), index, marker) };
+        if index + marker.len() == MGBA_LOG_BUFFER_LEN {
+            self.emit(MGBA_LOG_BUFFER_LEN);
+        } else {
+            // The end is less than the length of the buffer, so it always fits in a `u8`.
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                self.index = (index + marker.len()) as u8;
+            }
+            self.send();
+        }
+        // SAFETY: The marker is never longer than the buffer.
+        unsafe { copy_to_buffer(self.buffer(), 0, marker) };
+        // The marker is never longer than `MAX_CONTINUATION_MARKER_LEN`, so it always fits in a
+        // `u8`.
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            self.index = marker.len() as u8;
+        }
+        // The marker alone is not worth sending.
+        self.unsent = false;
+    }
+
+    /// Writes `bytes` to the log buffer as-is, sending the buffer whenever it becomes full.
+    ///
+    /// This is considerably faster than writing each byte using
+    /// [`write_byte()`](Self::write_byte()), as the bytes are copied using aligned halfword and word
+    /// writes wherever possible.
+    #[cfg_attr(
+        all(feature = "iwram", target_arch = "arm"),
+        link_section = ".iwram",
+        instruction_set(arm::a32)
+    )]
+    fn write_bytes(&mut self, mut bytes: &[u8]) {
+        if let Overflow::Truncate = self.config.overflow {
+            self.write_bytes_truncated(bytes);
+            return;
+        }
+        while !bytes.is_empty() {
+            let index = usize::from(self.index);
+            let capacity = self.capacity();
+            if index == capacity {
+                // Only possible with a continuation marker, as the buffer is otherwise sent as
+                // soon as it is full.
+                self.continue_message();
+                continue;
+            }
+            let available = capacity - index;
+            let (chunk, rest) = if bytes.len() > available {
+                bytes.split_at(char_boundary(bytes, available))
+            } else {
+                (bytes, &[][..])
+            };
+            // SAFETY: `chunk` fits within the remainder of the buffer.
+            unsafe { copy_to_buffer(self.buffer(), index, chunk) };
+            self.unsent = true;
+
+            let end = index + chunk.len();
+            if end == MGBA_LOG_BUFFER_LEN {
+                self.emit(MGBA_LOG_BUFFER_LEN);
+                self.index = 0;
+                self.unsent = false;
+            } else {
+                // `end` is less than the length of the buffer, so it always fits in a `u8`.
+                #[allow(clippy::cast_possible_truncation)]
+                {
+                    self.index = end as u8;
+                }
+                if !rest.is_empty() {
+                    // The next character does not fit in the buffer, so the message is continued
+                    // in the next one.
+                    self.continue_message();
+                }
+            }
+            bytes = rest;
+        }
+    }
+
+    /// Writes `bytes` to the log buffer as-is, truncating the message if it does not fit.
+    ///
+    /// A truncated message ends with [`TRUNCATION_MARKER`], replacing as many characters as
+    /// needed to fit it. The rest of the message is discarded until the next message is started.
+    #[cfg_attr(
+        all(feature = "iwram", target_arch = "arm"),
+        link_section = ".iwram",
+        instruction_set(arm::a32)
+    )]
+    fn write_bytes_truncated(&mut self, bytes: &[u8]) {
+        if self.truncated || bytes.is_empty() {
+            return;
+        }
+        let index = usize::from(self.index);
+        if bytes.len() <= self.capacity() - index {
+            // SAFETY: `bytes` fits within the remainder of the buffer.
+            unsafe { copy_to_buffer(self.buffer(), index, bytes) };
+            self.unsent = true;
+            let end = index + bytes.len();
+            if index <= TRUNCATION_LIMIT {
+                let boundary = if end <= TRUNCATION_LIMIT {
+                    end
+                } else {
+                    index + char_boundary(bytes, TRUNCATION_LIMIT - index)
+                };
+                // `boundary` is never past `TRUNCATION_LIMIT`, so it always fits in a `u8`.
+                #[allow(clippy::cast_possible_truncation)]
+                {
+                    self.boundary = boundary as u8;
                 }
-                b'\x00' => {
-                    // mGBA interprets null as the end of a line, so we replace null characters
-                    // with substitute characters when they are intentionally logged.
-                    self.write_byte(b'\x1a');
+            }
+            // `end` is less than the length of the buffer, so it always fits in a `u8`.
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                self.index = end as u8;
+            }
+        } else {
+            let boundary = if index <= TRUNCATION_LIMIT {
+                let len = char_boundary(bytes, TRUNCATION_LIMIT - index);
+                // SAFETY: `len` bytes never extend past `TRUNCATION_LIMIT`.
+                unsafe { copy_to_buffer(self.buffer(), index, &bytes[..len]) };
+                index + len
+            } else {
+                usize::from(self.boundary)
+            };
+            // SAFETY: `boundary` is never past `TRUNCATION_LIMIT`, so the marker fits.
+            unsafe { copy_to_buffer(self.buffer(), boundary, TRUNCATION_MARKER) };
+            // The end of the marker is less than the length of the buffer, so it always fits in a
+            // `u8`.
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                self.index = (boundary + TRUNCATION_MARKER.len()) as u8;
+            }
+            self.unsent = true;
+            self.truncated = true;
+        }
+    }
+
+    /// Writes the given text, handling newlines and other control bytes.
+    ///
+    /// Runs of bytes that need no special handling are written together using
+    /// [`write_bytes()`](Self::write_bytes()).
+    #[cfg_attr(
+        all(feature = "iwram", target_arch = "arm"),
+        link_section = ".iwram",
+        instruction_set(arm::a32)
+    )]
+    fn write_text(&mut self, mut text: &[u8]) {
+        while let Some(position) = text.iter().position(|&byte| is_special(byte)) {
+            if position > 0 {
+                self.after_carriage_return = false;
+                self.write_bytes(&text[..position]);
+            }
+            self.write_text_byte(text[position]);
+            text = &text[position + 1..];
+        }
+        if !text.is_empty() {
+            self.after_carriage_return = false;
+            self.write_bytes(text);
+        }
+    }
+
+    /// Sends the current line, starting the next line of the message with the line prefix.
+    ///
+    /// The prefix alone is not sent, so a message ending in a newline does not log an additional
+    /// message.
+    #[cfg_attr(
+        all(feature = "iwram", target_arch = "arm"),
+        link_section = ".iwram",
+        instruction_set(arm::a32)
+    )]
+    fn break_line(&mut self) {
+        self.send();
+        let prefix = self.config.line_prefix.as_bytes();
+        // SAFETY: The prefix is never longer than the buffer.
+        unsafe { copy_to_buffer(self.buffer(), 0, prefix) };
+        // The prefix is never longer than `MAX_LINE_PREFIX_LEN`, so it always fits in a `u8`.
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            self.index = prefix.len() as u8;
+        }
+        self.boundary = self.index;
+    }
+
+    /// Writes a single byte of text, handling newlines and other control bytes.
+    #[cfg_attr(
+        all(feature = "iwram", target_arch = "arm"),
+        link_section = ".iwram",
+        instruction_set(arm::a32)
+    )]
+    fn write_text_byte(&mut self, byte: u8) {
+        let after_carriage_return = core::mem::replace(&mut self.after_carriage_return, false);
+        match byte {
+            b'\n' if after_carriage_return => {
+                // The line was already broken by the carriage return.
+            }
+            b'\n' => {
+                // For readability purposes, just start a new log line.
+                self.break_line();
+            }
+            b'\r' if self.config.carriage_return => {
+                self.break_line();
+                self.after_carriage_return = true;
+            }
+            b'\t' if self.config.tab_width != 0 => {
+                // Columns are counted in bytes from the start of the message.
+                let width = self.config.tab_width;
+                for _ in 0..width - self.index % width {
+                    self.write_byte(b' ');
                 }
-                _ => {
-                    self.write_byte(byte);
+            }
+            _ if self.config.escape_control => {
+                // This includes null characters, which are then distinguishable from any
+                // substitute characters logged intentionally.
+                if byte == b'\\' {
+                    self.write_bytes(b"\\\\");
+                } else {
+                    self.write_bytes(&[
+                        b'\\',
+                        b'x',
+                        HEX_DIGITS[usize::from(byte >> 4)],
+                        HEX_DIGITS[usize::from(byte & 0xf)],
+                    ]);
                 }
             }
+            b'\x00' => {
+                // mGBA interprets null as the end of a line, so we replace null characters
+                // with substitute characters when they are intentionally logged.
+                let mut substitute = [0; 4];
+                self.write_bytes(
+                    self.config
+                        .null_substitute
+                        .encode_utf8(&mut substitute)
+                        .as_bytes(),
+                );
+            }
+            _ => {
+                self.write_byte(byte);
+            }
         }
+    }
+}
+
+impl Write for Writer {
+    /// Write the given string to the log buffer.
+    ///
+    /// The buffer is flushed automatically when it becomes full.
+    #[cfg_attr(
+        all(feature = "iwram", target_arch = "arm"),
+        link_section = ".iwram",
+        instruction_set(arm::a32)
+    )]
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.write_text(s.as_bytes());
         Ok(())
     }
 }
@@ -170,135 +1056,2616 @@ impl Write for Writer {
 impl Drop for Writer {
     /// Flushes the buffer, ensuring that the remaining bytes are sent.
     fn drop(&mut self) {
-        self.send();
+        if self.unsent {
+            self.send();
+        }
     }
 }
 
-/// Implements the logging interface for mGBA logging.
+/// Returns whether `byte` needs special handling when written as text.
 ///
-/// This struct implements `log::Log`, allowing it to be used as a logger with the `log` crate.
-/// Logging can be done using the standard log interface.
-///
-/// Note that this logger does not support `log::trace!`, since there are no trace logs available
-/// on mGBA.
-#[derive(Debug)]
-struct Logger;
+/// This is the case for newlines, null characters, and, as they may be escaped, all other control
+/// bytes and backslashes.
+#[cfg_attr(
+    all(feature = "iwram", target_arch = "arm"),
+    link_section = ".iwram",
+    instruction_set(arm::a32)
+)]
+fn is_special(byte: u8) -> bool {
+    byte < 0x20 || byte == 0x7f || byte == b'\\'
+}
 
-impl Log for Logger {
-    /// Logging is enabled for all log messages besides those whose level is `Trace`.
-    ///
-    /// This is because there is no analog for the `Trace` log level within mGBA.
-    fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= log::Level::Debug
+/// Returns the position at which `bytes` should be split so that the first part fits within `len`
+/// bytes without splitting a UTF-8 encoded character.
+///
+/// If `len` falls within a character, the position of the start of that character is returned.
+/// Bytes that are not valid UTF-8 are split at `len`.
+///
+/// `len` must be less than the length of `bytes`.
+#[cfg_attr(
+    all(feature = "iwram", target_arch = "arm"),
+    link_section = ".iwram",
+    instruction_set(arm::a32)
+)]
+fn char_boundary(bytes: &[u8], len: usize) -> usize {
+    /// Whether `byte` continues a multi-byte character rather than starting a new one.
+    fn is_continuation(byte: u8) -> bool {
+        byte & 0b1100_0000 == 0b1000_0000
     }
 
-    /// Directly logs the `record` to mGBA's memory mapped IO registers for logging.
-    ///
-    /// Buffer flushing is handled automatically during logging.
-    fn log(&self, record: &Record) {
-        if let Ok(level) = Level::try_from(record.level()) {
-            // Disable interrupts, storing the previous value.
-            //
-            // This prevents synchronization issues when messages are logged in interrupt handling.
-            // Interrupts triggered during this time will be handled when interrupts are reenabled.
-            let previous_ime = unsafe { IME.read_volatile() };
-            unsafe { IME.write_volatile(false) };
-
-            // Write log record.
-            //
-            // Note that the writer is dropped after this, causing the buffer to be flushed.
-            write(&mut &mut Writer::new(level), *record.args())
-                .unwrap_or_else(|error| panic!("write to mGBA log buffer failed: {}", error));
-
-            // Restore previous interrupt enable value.
-            unsafe {
-                IME.write_volatile(previous_ime);
+    // A character is at most four bytes long, so its start is at most three bytes back.
+    (len.saturating_sub(3)..=len)
+        .rev()
+        .find(|&position| !is_continuation(bytes[position]))
+        .unwrap_or(len)
+}
+
+/// Copies `bytes` into the log buffer at `buffer`, starting `offset` bytes into it.
+///
+/// Aligned halfword and word writes are used wherever possible, with byte writes only used for an
+/// unaligned byte at either end.
+///
+/// # Safety
+/// `buffer` must be a log buffer returned by [`Backend::buffer()`], and `offset + bytes.len()`
+/// must not exceed [`MGBA_LOG_BUFFER_LEN`].
+// The destination is always checked to be aligned before halfword and word writes.
+#[allow(clippy::cast_ptr_alignment)]
+#[cfg_attr(
+    all(feature = "iwram", target_arch = "arm"),
+    link_section = ".iwram",
+    instruction_set(arm::a32)
+)]
+unsafe fn copy_to_buffer(buffer: *mut u8, offset: usize, bytes: &[u8]) {
+    // SAFETY: The caller guarantees that all writes are within the bounds of the buffer.
+    unsafe {
+        let mut destination = buffer.add(offset);
+        let mut bytes = bytes;
+        if destination as usize & 1 != 0 {
+            if let Some((&byte, rest)) = bytes.split_first() {
+                destination.write_volatile(byte);
+                destination = destination.add(1);
+                bytes = rest;
             }
         }
+        if destination as usize & 2 != 0 && bytes.len() >= 2 {
+            destination
+                .cast::<u16>()
+                .write_volatile(u16::from_le_bytes([bytes[0], bytes[1]]));
+            destination = destination.add(2);
+            bytes = &bytes[2..];
+        }
+        // If any bytes remain beyond this point, `destination` is now word-aligned.
+        let mut words = bytes.chunks_exact(4);
+        for word in &mut words {
+            destination
+                .cast::<u32>()
+                .write_volatile(u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+            destination = destination.add(4);
+        }
+        let mut bytes = words.remainder();
+        if bytes.len() >= 2 {
+            destination
+                .cast::<u16>()
+                .write_volatile(u16::from_le_bytes([bytes[0], bytes[1]]));
+            destination = destination.add(2);
+            bytes = &bytes[2..];
+        }
+        if let [byte] = bytes {
+            destination.write_volatile(*byte);
+        }
     }
-
-    /// This is a no-op. Flushing of buffers is already done in `log()`.
-    fn flush(&self) {}
 }
 
-/// Logs a message at the fatal level.
-///
-/// `Fatal` is a level specific to mGBA, and is not present within the standard `log` ecosystem.
-/// This macro allows logging at this level specifically.
-///
-/// If [`init()`] has not been successfully run, this will have no effect.
+/// Lists of targets whose records are allowed or denied.
 ///
-/// Note that successfully logging at the `Fatal` level in mGBA will permanently halt execution and
-/// display the logged message to the user. As such, it is not possible to log more than 256 bytes,
-/// as the execution will be halted as soon as the first 256 bytes in the buffer are flushed.
-#[macro_export]
-macro_rules! fatal {
-    ($($arg:tt)+) => ($crate::__fatal(format_args!($($arg)+)));
+/// A filter matches a target if the target is equal to the filter or is a submodule of it. For
+/// example, the filter `agb::sound` matches the targets `agb::sound` and `agb::sound::mixer`, but
+/// not `agb::sound_effects`.
+#[derive(Clone, Copy, Debug)]
+struct TargetFilters {
+    /// Targets whose records are allowed.
+    ///
+    /// If any targets are allowed, records whose target matches none of them are not logged.
+    allow: [Option<&'static str>; TARGET_FILTER_CAPACITY],
+    /// Targets whose records are denied.
+    ///
+    /// Denying a target takes precedence over allowing it.
+    deny: [Option<&'static str>; TARGET_FILTER_CAPACITY],
 }
 
-/// Logs a message at the fatal level.
-///
-/// This is an implementation detail of the [`fatal!`] macro. It is not considered part of the
-/// public API and should not be used directly by external code.
-#[doc(hidden)]
-pub fn __fatal(args: fmt::Arguments) {
-    // Ensure mGBA is listening.
-    // SAFETY: This is guaranteed to be a valid read.
-    if unsafe { MGBA_LOG_ENABLE.read_volatile() } == 0x1DEA {
-        // Disable interrupts.
-        //
+impl TargetFilters {
+    /// Creates a new set of filters allowing all targets.
+    const fn new() -> Self {
+        Self {
+            allow: [None; TARGET_FILTER_CAPACITY],
+            deny: [None; TARGET_FILTER_CAPACITY],
+        }
+    }
+
+    /// Returns whether `filter` matches `target`.
+    fn matches(filter: &str, target: &str) -> bool {
+        matches!(target.strip_prefix(filter), Some(rest) if rest.is_empty() || rest.starts_with("::"))
+    }
+
+    /// Adds `target` to the first empty slot in `list`.
+    ///
+    /// Returns `false` if `list` is full.
+    fn push(list: &mut [Option<&'static str>], target: &'static str) -> bool {
+        list.iter_mut()
+            .find(|slot| slot.is_none())
+            .map(|slot| *slot = Some(target))
+            .is_some()
+    }
+
+    /// Returns whether records with the given `target` are allowed by these filters.
+    fn allows(&self, target: &str) -> bool {
+        let mut allowed = self.allow.iter().flatten().peekable();
+        (allowed.peek().is_none() || allowed.any(|filter| Self::matches(filter, target)))
+            && !self
+                .deny
+                .iter()
+                .flatten()
+                .any(|filter| Self::matches(filter, target))
+    }
+}
+
+/// Renders records.
+///
+/// This is implemented for all functions taking a writer and a record, such as
+/// `fn format(writer: &mut dyn Write, record: &Record) -> core::fmt::Result`. See
+/// [`LoggerBuilder::with_formatter()`] for details.
+pub trait FormatRecord: Sync {
+    /// Writes the complete message for `record` to `writer`.
+    ///
+    /// # Errors
+    /// Returns an error if formatting any part of the record fails.
+    fn format_record(&self, writer: &mut dyn Write, record: &Record) -> core::fmt::Result;
+}
+
+impl<F> FormatRecord for F
+where
+    F: Fn(&mut dyn Write, &Record) -> core::fmt::Result + Sync,
+{
+    fn format_record(&self, writer: &mut dyn Write, record: &Record) -> core::fmt::Result {
+        self(writer, record)
+    }
+}
+
+/// The renderer of records set through [`LoggerBuilder::with_formatter()`].
+#[derive(Clone, Copy)]
+struct RecordFormatter(&'static dyn FormatRecord);
+
+impl core::fmt::Debug for RecordFormatter {
+    fn fmt(&self, formatter: &mut Formatter) -> core::fmt::Result {
+        formatter.write_str("RecordFormatter")
+    }
+}
+
+/// The logger set through [`LoggerBuilder::with_secondary_logger()`].
+#[derive(Clone, Copy)]
+struct SecondaryLogger(&'static dyn Log);
+
+impl core::fmt::Debug for SecondaryLogger {
+    fn fmt(&self, formatter: &mut Formatter) -> core::fmt::Result {
+        formatter.write_str("SecondaryLogger")
+    }
+}
+
+/// Configuration of how records are rendered by the [`Logger`].
+///
+/// This is configured through a [`LoggerBuilder`].
+#[derive(Clone, Copy, Debug)]
+// Each of these options is independent of the others, so they are not better represented as a
+// state machine.
+#[allow(clippy::struct_excessive_bools)]
+struct Config {
+    /// The interface messages are sent through.
+    backend: Backend,
+    /// The region of SRAM that warnings and more severe messages are mirrored to, if any.
+    sram_log: Option<SramLog>,
+    /// Whether each message is prefixed with its record's target.
+    target: bool,
+    /// Whether each message is suffixed with its record's file and line.
+    file_line: bool,
+    /// Whether fatal messages are prefixed with the file and line of their caller.
+    fatal_location: bool,
+    /// The address and value written when an assertion fails, if any.
+    assertion_status: Option<(usize, u8)>,
+    /// Whether records are written without disabling interrupts, staging records logged by
+    /// interrupt handlers until the interrupted record is finished.
+    interrupt_staging: bool,
+    /// Whether records logged by interrupt handlers are deferred until they are flushed from the
+    /// main program.
+    defer_interrupt_logs: bool,
+    /// Whether records are formatted into RAM before interrupts are disabled.
+    buffered_formatting: bool,
+    /// Filters determining which targets are logged.
+    filters: TargetFilters,
+    /// The directives setting the maximum level logged for each target, if any.
+    directives: Option<&'static Directives>,
+    /// The function rendering each record, replacing the default rendering if set.
+    formatter: Option<RecordFormatter>,
+    /// The logger every record is also forwarded to, if any.
+    secondary: Option<SecondaryLogger>,
+    /// The marker written where a message is split across multiple sends.
+    continuation_marker: &'static str,
+    /// Whether messages that do not fit in mGBA's log buffer are truncated instead of split.
+    truncate: bool,
+    /// The character written in place of null characters.
+    null_substitute: char,
+    /// Whether control bytes other than newlines are escaped.
+    escape_control: bool,
+    /// Whether carriage returns are treated as line breaks.
+    carriage_return: bool,
+    /// The distance between the tab stops that tabs are expanded to, or `0` if they are not.
+    tab_width: u8,
+    /// The prefix written at the start of each line of a message after the first.
+    line_prefix: &'static str,
+    /// The function returning the frame count each message is prefixed with, if any.
+    frame_counter: Option<fn() -> u32>,
+    /// The function returning the current heap usage, if any.
+    heap_stats: Option<fn() -> HeapUsage>,
+    /// The bottom and top of the stack painted at initialization, if any.
+    stack: Option<(usize, usize)>,
+    /// The id of the build, included in crash dumps.
+    #[cfg(feature = "crash-dump")]
+    build_id: u32,
+    /// The offset within SRAM that crash dumps are written to, if any.
+    #[cfg(feature = "crash-dump")]
+    crash_dump_sram: Option<usize>,
+    /// Whether each message is prefixed with a sequence number.
+    sequence_numbers: bool,
+}
+
+impl Config {
+    /// The default configuration, used by [`init()`].
+    const fn new() -> Self {
+        Self {
+            backend: Backend::Mgba,
+            sram_log: None,
+            target: false,
+            file_line: false,
+            fatal_location: false,
+            assertion_status: None,
+            interrupt_staging: false,
+            defer_interrupt_logs: false,
+            buffered_formatting: false,
+            filters: TargetFilters::new(),
+            directives: None,
+            formatter: None,
+            secondary: None,
+            continuation_marker: "",
+            truncate: false,
+            null_substitute: '\x1a',
+            escape_control: false,
+            carriage_return: false,
+            tab_width: 0,
+            line_prefix: "",
+            frame_counter: None,
+            heap_stats: None,
+            stack: None,
+            #[cfg(feature = "crash-dump")]
+            build_id: 0,
+            #[cfg(feature = "crash-dump")]
+            crash_dump_sram: None,
+            sequence_numbers: false,
+        }
+    }
+
+    /// Returns how a [`Writer`] writes text.
+    const fn writer_config(&self) -> WriterConfig {
+        WriterConfig {
+            backend: self.backend,
+            sram_log: self.sram_log,
+            overflow: self.overflow(),
+            null_substitute: self.null_substitute,
+            escape_control: self.escape_control,
+            carriage_return: self.carriage_return,
+            tab_width: self.tab_width,
+            line_prefix: self.line_prefix,
+        }
+    }
+
+    /// Returns whether records at `level` with the given `target` pass the target filters and
+    /// directives.
+    fn allows(&self, level: log::Level, target: &str) -> bool {
+        self.filters.allows(target)
+            && match self.directives {
+                Some(directives) => directives.enabled(level, target),
+                None => true,
+            }
+    }
+
+    /// Writes the prefix written before every message, such as its sequence number, frame count,
+    /// timestamp, and indentation.
+    fn write_prefix<W>(&self, writer: &mut W) -> core::fmt::Result
+    where
+        W: Write,
+    {
+        if self.sequence_numbers {
+            write!(writer, "[#{}] ", dec_u32(next_sequence_number()))?;
+        }
+        if let Some(frame_counter) = self.frame_counter {
+            write!(writer, "[frame {}] ", dec_u32(frame_counter()))?;
+        }
+        #[cfg(feature = "timestamps")]
+        timer::write_timestamp(writer)?;
+        scope::write_indentation(writer)
+    }
+
+    /// Returns how messages that do not fit in mGBA's log buffer are handled.
+    const fn overflow(&self) -> Overflow {
+        if self.truncate {
+            Overflow::Truncate
+        } else {
+            Overflow::Split(self.continuation_marker)
+        }
+    }
+}
+
+/// Implements the logging interface for mGBA logging.
+///
+/// This struct implements `log::Log`, allowing it to be used as a logger with the `log` crate.
+/// Logging can be done using the standard log interface.
+///
+/// Note that this logger does not support `log::trace!`, since there are no trace logs available
+/// on mGBA, unless the `trace-as-debug` feature is enabled.
+#[derive(Debug)]
+struct Logger {
+    /// The logger's configuration.
+    ///
+    /// This must only be accessed while interrupts are disabled.
+    config: UnsafeCell<Config>,
+}
+
+impl Logger {
+    /// Creates a new logger with the default configuration.
+    const fn new() -> Self {
+        Self {
+            config: UnsafeCell::new(Config::new()),
+        }
+    }
+
+    /// Writes the `record` to mGBA, or stages it if another record is already being written.
+    ///
+    /// Another record is already being written if this is called while formatting that record,
+    /// such as by a `Display` implementation that logs, or, when interrupt staging is enabled, by
+    /// an interrupt handler that interrupted it. Writing the `record` directly would clobber the
+    /// other record, so it is instead staged to be sent once the other record is finished.
+    fn write_or_stage(config: &Config, level: Level, record: &Record) {
+        if staging::begin() {
+            // Any records deferred by interrupt handlers were logged before this one.
+            staging::STAGING.send();
+            // Note that the writer is dropped after this, causing the buffer to be flushed. Writing
+            // to the log buffer cannot fail, so an error can only come from formatting the record,
+            // in which case whatever was formatted is still sent.
+            let mut writer = Writer::new(level, config.writer_config());
+            if Self::write_record(config, &mut writer, record).is_err() {
+                count_format_error();
+            }
+            staging::STAGING.finish();
+        } else {
+            staging::STAGING.stage(level, |writer| Self::write_record(config, writer, record));
+        }
+    }
+
+    /// Writes already formatted `text` to mGBA, or stages it if another record is already being
+    /// written.
+    ///
+    /// See [`write_or_stage()`](Self::write_or_stage()) for details.
+    fn write_or_stage_str(config: &Config, level: Level, text: &str) {
+        if staging::begin() {
+            // Any records deferred by interrupt handlers were logged before this one.
+            staging::STAGING.send();
+            Writer::new(level, config.writer_config()).write_text(text.as_bytes());
+            staging::STAGING.finish();
+        } else {
+            staging::STAGING.stage(level, |writer| writer.write_str(text));
+        }
+    }
+
+    /// Returns the logger every record is also forwarded to, if any.
+    fn secondary(&self) -> Option<SecondaryLogger> {
+        // SAFETY: Interrupts are disabled, so the configuration cannot be accessed concurrently.
+        without_interrupts(|| unsafe { &*self.config.get() }.secondary)
+    }
+
+    /// Writes the `record` to the `writer`, decorated according to the given configuration.
+    fn write_record<W>(config: &Config, writer: &mut W, record: &Record) -> core::fmt::Result
+    where
+        W: Write,
+    {
+        if let Some(RecordFormatter(formatter)) = config.formatter {
+            return formatter.format_record(writer, record);
+        }
+        config.write_prefix(writer)?;
+        // Trace records are logged at the debug level, so they are prefixed to distinguish them
+        // from debug records.
+        #[cfg(feature = "trace-as-debug")]
+        if record.level() == log::Level::Trace {
+            writer.write_str("[TRACE] ")?;
+        }
+        if config.target {
+            write!(writer, "{}: ", record.target())?;
+        }
+        write(writer, *record.args())?;
+        if config.file_line {
+            if let (Some(file), Some(line)) = (record.file(), record.line()) {
+                write!(writer, " ({}:{line})", crate_relative_path(file))?;
+            }
+        }
+        // Key-value pairs are written last, so that they can be parsed from the end of the message.
+        #[cfg(feature = "kv")]
+        kv::write_key_values(writer, record)?;
+        Ok(())
+    }
+}
+
+/// Trims a source file path to be relative to the root of its crate.
+///
+/// Paths of files in dependencies are absolute, such as
+/// `/home/user/.cargo/registry/src/.../agb-0.15.0/src/sound/mod.rs`, and paths of files in
+/// workspace members are relative to the workspace root. Both are trimmed to start at the last
+/// `src` directory, as in `src/sound/mod.rs`, keeping messages short. Paths without a `src`
+/// directory are returned unchanged.
+fn crate_relative_path(path: &str) -> &str {
+    let bytes = path.as_bytes();
+    (0..bytes.len())
+        .rev()
+        .find(|&start| {
+            bytes[start..].starts_with(b"src")
+                && matches!(bytes.get(start + 3), Some(b'/' | b'\\'))
+                && (start == 0 || matches!(bytes[start - 1], b'/' | b'\\'))
+        })
+        .map_or(path, |start| &path[start..])
+}
+
+// SAFETY: The configuration is only ever accessed while interrupts are disabled. As the Game Boy
+// Advance has a single core, this means it is never accessed concurrently.
+unsafe impl Sync for Logger {}
+
+impl Log for Logger {
+    /// Logging is enabled for all log messages besides those whose level is `Trace` or whose
+    /// target is filtered out.
+    ///
+    /// This is because there is no analog for the `Trace` log level within mGBA. If the
+    /// `trace-as-debug` feature is enabled, logging is enabled for messages of all levels.
+    ///
+    /// Logging is disabled entirely while it is paused, and once a [`LoggerGuard`] is dropped.
+    /// Otherwise, records are also enabled if the secondary logger enables them.
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        if metadata.level() > MAX_LEVEL_FILTER || silenced() {
+            return false;
+        }
+        let (allowed, secondary) = without_interrupts(|| {
+            // SAFETY: Interrupts are disabled, so the configuration cannot be accessed
+            // concurrently.
+            let config = unsafe { &*self.config.get() };
+            (
+                config.allows(metadata.level(), metadata.target()),
+                config.secondary,
+            )
+        });
+        allowed
+            || match secondary {
+                Some(SecondaryLogger(secondary)) => secondary.enabled(metadata),
+                None => false,
+            }
+    }
+
+    /// Directly logs the `record` to mGBA's memory mapped IO registers for logging.
+    ///
+    /// Buffer flushing is handled automatically during logging.
+    fn log(&self, record: &Record) {
+        if silenced() {
+            return;
+        }
+        if let Ok(level) = Level::try_from(record.level()) {
+            // Interrupts are disabled to prevent synchronization issues when messages are logged
+            // in interrupt handling.
+            let staged_config = without_interrupts(|| {
+                // SAFETY: Interrupts are disabled, so the configuration cannot be accessed
+                // concurrently.
+                let config = unsafe { &*self.config.get() };
+                if !config.allows(record.level(), record.target()) {
+                    return None;
+                }
+                if config.defer_interrupt_logs && in_interrupt() {
+                    staging::STAGING
+                        .stage(level, |writer| Self::write_record(config, writer, record));
+                    return None;
+                }
+                if config.interrupt_staging || config.buffered_formatting {
+                    // The configuration is copied so that it can be used once interrupts are
+                    // enabled again.
+                    return Some(*config);
+                }
+
+                Self::write_or_stage(config, level, record);
+                None
+            });
+            if let Some(config) = staged_config {
+                if config.buffered_formatting {
+                    let mut buffer = FormatBuffer::new();
+                    // On truncation, as much of the record as possible is kept.
+                    if Self::write_record(&config, &mut buffer, record).is_err() {
+                        if buffer.is_truncated() {
+                            stats::count_truncated(level);
+                        } else {
+                            count_format_error();
+                        }
+                    }
+                    if config.interrupt_staging {
+                        Self::write_or_stage_str(&config, level, buffer.as_str());
+                    } else {
+                        without_interrupts(|| {
+                            Self::write_or_stage_str(&config, level, buffer.as_str());
+                        });
+                    }
+                } else {
+                    Self::write_or_stage(&config, level, record);
+                }
+            }
+        }
+        // The secondary logger is only called once interrupts are enabled again, as it may take
+        // arbitrarily long.
+        if let Some(SecondaryLogger(secondary)) = self.secondary() {
+            if secondary.enabled(record.metadata()) {
+                secondary.log(record);
+            }
+        }
+    }
+
+    /// Sends any records deferred by interrupt handlers, and flushes the secondary logger.
+    ///
+    /// See [`flush()`](crate::flush()) for details.
+    fn flush(&self) {
+        flush();
+        if let Some(SecondaryLogger(secondary)) = self.secondary() {
+            secondary.flush();
+        }
+    }
+}
+
+/// A logger that does nothing.
+///
+/// This is installed by [`try_init_or_noop()`] when mGBA does not acknowledge initialization, so
+/// that no other logger can be installed later and records are discarded as cheaply as possible.
+#[derive(Debug)]
+struct NopLogger;
+
+impl Log for NopLogger {
+    /// Logging is never enabled.
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        false
+    }
+
+    /// This is a no-op.
+    fn log(&self, _record: &Record) {}
+
+    /// This is a no-op.
+    fn flush(&self) {}
+}
+
+/// Logs a message at the fatal level.
+///
+/// `Fatal` is a level specific to mGBA, and is not present within the standard `log` ecosystem.
+/// This macro allows logging at this level specifically.
+///
+/// Note that successfully logging at the `Fatal` level in mGBA will permanently halt execution and
+/// display the logged message to the user. As such, it is not possible to log more than 256 bytes,
+/// as the execution will be halted as soon as the first 256 bytes in the buffer are flushed.
+///
+/// The location the message was logged from can be included in the message by enabling
+/// [`LoggerBuilder::with_fatal_location()`].
+///
+/// This macro never returns. If the message could not be logged (for example, if [`init()`] has
+/// not been successfully run or the program is not running in mGBA), execution loops forever
+/// instead. This allows it to be used wherever a value is expected:
+///
+/// ``` no_run
+/// let value: Option<u32> = None;
+/// let value = value.unwrap_or_else(|| mgba_log::fatal!("missing value"));
+/// ```
+#[macro_export]
+macro_rules! fatal {
+    ($($arg:tt)+) => ($crate::__fatal_halt(format_args!($($arg)+)));
+}
+
+/// Logs a message at the fatal level.
+///
+/// This is an implementation detail of the [`fatal!`] macro. It is not considered part of the
+/// public API and should not be used directly by external code.
+#[doc(hidden)]
+#[track_caller]
+pub fn __fatal(args: Arguments) {
+    log_fatal(args, false);
+}
+
+/// Logs a message at the fatal level.
+///
+/// The message is prefixed with the location of the caller if `locate` is `true` or if
+/// [`LoggerBuilder::with_fatal_location()`] was enabled.
+#[track_caller]
+fn log_fatal(args: Arguments, locate: bool) {
+    // Ensure the backend is listening.
+    if !cfg!(feature = "disabled") && without_interrupts(|| writer_config().backend).is_listening()
+    {
+        // Disable interrupts.
+        //
         // This prevents synchronization issues when messages are logged in interrupt handling.
-        unsafe { IME.write_volatile(false) };
+        // Interrupts are never reenabled, because writing with `Level::Fatal` will always cause
+        // mGBA to halt execution.
+        // The guard does nothing when the `no-ime` feature is enabled.
+        #[allow(clippy::forget_non_drop)]
+        core::mem::forget(InterruptGuard::new());
+
+        // Fatal logging is often used in panic handlers, so panicking on write failures would lead
+        // to recursive panicking. Instead, this fails silently.
+        #[allow(unused_must_use)]
+        {
+            // SAFETY: Interrupts are disabled, so the configuration is not accessed concurrently.
+            let config = unsafe { &*LOGGER.config.get() };
+            // mGBA halts once the fatal message is sent, so the backtrace is logged first.
+            #[cfg(feature = "backtrace")]
+            backtrace::write(&mut Writer::new(Level::Error, config.writer_config()));
+            // mGBA halts once the first fatal message is sent, so there is nothing to continue.
+            let mut writer_config = config.writer_config();
+            if let Overflow::Split(_) = writer_config.overflow {
+                writer_config.overflow = Overflow::Split("");
+            }
+            let mut writer = Writer::new(Level::Fatal, writer_config);
+            config.write_prefix(&mut writer);
+            if locate || config.fatal_location {
+                let location = Location::caller();
+                write!(
+                    writer,
+                    "{}:{}: ",
+                    crate_relative_path(location.file()),
+                    location.line()
+                );
+            }
+            write(&mut writer, args);
+        }
+    }
+}
+
+/// Logs a message at the fatal level and never returns.
+///
+/// This is an implementation detail of the [`fatal!`] macro. It is not considered part of the
+/// public API and should not be used directly by external code.
+#[doc(hidden)]
+#[track_caller]
+pub fn __fatal_halt(args: Arguments) -> ! {
+    __fatal(args);
+    // The mock captures fatal messages without halting, so tests observe them as panics instead.
+    #[cfg(feature = "mock")]
+    panic!("a message was logged at the fatal level");
+    // Logging at the fatal level halts mGBA. If execution continues, the message could not be
+    // logged, so execution is halted here instead.
+    #[allow(unreachable_code)]
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// Logs the value of an expression at the debug level and returns it.
+///
+/// This mirrors the standard library's `dbg!` macro. The file, line, expression, and the value's
+/// [`Debug`](core::fmt::Debug) representation are logged using [`log::debug!`], as in
+/// `src/main.rs:42: frames * 2 = 120`. The value is then returned, allowing this macro to be used
+/// in expression position. Multiple expressions can be provided, in which case a tuple of their
+/// values is returned.
+///
+/// ``` no_run
+/// let frames = 60;
+/// let doubled = mgba_log::mgba_dbg!(frames * 2);
+/// ```
+#[macro_export]
+macro_rules! mgba_dbg {
+    () => {
+        $crate::__log::debug!("{}:{}", file!(), line!())
+    };
+    ($val:expr $(,)?) => {
+        // `match` is used to extend the lifetimes of temporaries, as in `std::dbg!`.
+        match $val {
+            tmp => {
+                $crate::__log::debug!(
+                    "{}:{}: {} = {:?}",
+                    file!(),
+                    line!(),
+                    stringify!($val),
+                    &tmp
+                );
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::mgba_dbg!($val)),+,)
+    };
+}
+
+/// Logs a hex dump of a region of memory at the debug level.
+///
+/// Each line of the dump is logged as its own record using [`log::debug!`], displaying the
+/// address of the line followed by up to sixteen bytes in hexadecimal and as ASCII characters:
+///
+/// ``` text
+/// 07000000: 00 20 00 40 00 00 00 00 10 00 00 00 00 00 00 00  |. .@............|
+/// ```
+///
+/// The region to dump can be provided either as a `&[u8]` slice, or as a `*const u8` pointer and
+/// a length. Dumping from a pointer reads each byte using a volatile read, allowing memory mapped
+/// regions such as OAM or SRAM to be dumped. As the pointer must be valid for reads, dumping from
+/// a pointer must be done within an `unsafe` block.
+///
+/// ``` no_run
+/// let save_block = [0u8; 64];
+/// mgba_log::hexdump!(&save_block);
+///
+/// // Dump the first object attributes in OAM.
+/// unsafe {
+///     mgba_log::hexdump!(0x0700_0000 as *const u8, 32);
+/// }
+/// ```
+#[macro_export]
+macro_rules! hexdump {
+    ($data:expr $(,)?) => {
+        $crate::__hexdump(module_path!(), $data)
+    };
+    ($ptr:expr, $len:expr $(,)?) => {
+        $crate::__hexdump_raw(module_path!(), $ptr, $len)
+    };
+}
+
+/// Logs a message only the first time the call site is executed.
+///
+/// This accepts the same arguments as [`log::log!`], and is useful within code that runs every
+/// frame or every scanline, where logging the same message repeatedly would bury everything else.
+/// Every invocation of this macro has its own flag recording whether it has logged, so separate
+/// invocations log independently of each other.
+///
+/// ``` no_run
+/// use log::Level;
+///
+/// loop {
+///     mgba_log::log_once!(Level::Warn, "audio buffer underrun");
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_once {
+    ($($arg:tt)+) => {{
+        static FIRED: $crate::__RacyCell<bool> = $crate::__RacyCell::new(false);
+        if $crate::__once(&FIRED) {
+            $crate::__log::log!($($arg)+);
+        }
+    }};
+}
+
+/// Logs a message only every `n`th time the call site is executed.
+///
+/// The first argument is `n`, and the remaining arguments are the same as those accepted by
+/// [`log::log!`]. The first invocation always logs. This is useful within code such as interrupt
+/// handlers or tight loops, where logging at full rate would destroy frame timing.
+///
+/// When invocations have been skipped since the call site last logged, the number of skipped
+/// invocations is appended to the message, as in `vblank (59 skipped)`.
+///
+/// Every invocation of this macro has its own counter, so separate invocations are limited
+/// independently of each other. Updating the counter briefly disables interrupts.
+///
+/// ``` no_run
+/// use log::Level;
+///
+/// loop {
+///     // Logs roughly once per second.
+///     mgba_log::log_every_n!(60, Level::Debug, "vblank");
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_every_n {
+    ($n:expr, target: $target:expr, $lvl:expr, $($arg:tt)+) => {{
+        static COUNTER: $crate::__RacyCell<u32> = $crate::__RacyCell::new(0);
+        if let ::core::option::Option::Some(skipped) = $crate::__every_n(&COUNTER, $n) {
+            $crate::__log::log!(
+                target: $target,
+                $lvl,
+                "{}{}",
+                format_args!($($arg)+),
+                $crate::__Skipped(skipped)
+            );
+        }
+    }};
+    ($n:expr, $lvl:expr, $($arg:tt)+) => {
+        $crate::log_every_n!($n, target: module_path!(), $lvl, $($arg)+)
+    };
+}
+
+/// Logs the current heap usage, as reported by the function registered through
+/// [`LoggerBuilder::with_heap_stats()`].
+///
+/// The usage is logged at the given level, or at the debug level if none is given, as in
+/// `heap: used=768 free=256 largest_free=64 fragmentation=75%`. The largest free block and the
+/// fragmentation are only included if the registered function reports the largest free block.
+/// Logging the usage at the same point every frame or every level load makes memory leaks show up
+/// as a steadily growing `used` count. Nothing is logged if no function was registered.
+///
+/// ``` no_run
+/// use log::Level;
+///
+/// mgba_log::log_heap_stats!();
+/// mgba_log::log_heap_stats!(Level::Info);
+/// ```
+#[macro_export]
+macro_rules! log_heap_stats {
+    () => {
+        $crate::log_heap_stats!($crate::__log::Level::Debug)
+    };
+    ($lvl:expr $(,)?) => {
+        if let ::core::option::Option::Some(usage) = $crate::__heap_usage() {
+            $crate::__log::log!($lvl, "heap: {}", usage);
+        }
+    };
+}
+
+/// Logs the most stack ever used, as measured by painting the stack at initialization.
+///
+/// The usage is logged at the given level, or at the debug level if none is given, as in
+/// `stack: used=1024 free=3072`. If the stack was exhausted, `(overflowed)` is appended to the
+/// message, as the stack has then most likely grown into whatever is placed below it. Nothing is
+/// logged unless stack painting was configured through
+/// [`LoggerBuilder::with_stack_painting()`].
+///
+/// Measuring the usage reads every unused word of the stack, so it is best done once per frame or
+/// less often.
+///
+/// ``` no_run
+/// use log::Level;
+///
+/// mgba_log::log_stack_usage!();
+/// mgba_log::log_stack_usage!(Level::Info);
+/// ```
+#[macro_export]
+macro_rules! log_stack_usage {
+    () => {
+        $crate::log_stack_usage!($crate::__log::Level::Debug)
+    };
+    ($lvl:expr $(,)?) => {
+        if let ::core::option::Option::Some(usage) = $crate::__stack_usage() {
+            $crate::__log::log!($lvl, "stack: {}", usage);
+        }
+    };
+}
+
+/// Parses a filter string into [`Directives`] at compile time, for use with
+/// [`LoggerBuilder::with_directives()`].
+///
+/// The filter string uses the syntax of `env_logger`'s `RUST_LOG`: a comma separated list of
+/// directives, each of which is one of
+///
+/// - `level`, setting the maximum level of targets not named by any directive,
+/// - `target=level`, setting the maximum level of `target` and its submodules, or
+/// - `target`, logging all records of `target` and its submodules.
+///
+/// Levels are `off`, `error`, `warn`, `info`, `debug`, or `trace`, ignoring case. If the targets
+/// named by multiple directives match a record's target, the directive naming the longest target
+/// applies. Once any directive is given, records matched by none of them are not logged.
+///
+/// Without a filter string, the `MGBA_LOG` environment variable is read when the program is
+/// compiled, and an unset variable results in directives logging every record. An invalid filter
+/// string, or one with more than eight directives naming targets, fails to compile.
+///
+/// ``` no_run
+/// let directives = mgba_log::directives!("warn,game::ai=debug,agb=off");
+/// // Reads the filter string from `MGBA_LOG`, as in `MGBA_LOG=info cargo build`.
+/// let from_environment = mgba_log::directives!();
+/// ```
+#[macro_export]
+macro_rules! directives {
+    () => {
+        $crate::directives!(match ::core::option_env!("MGBA_LOG") {
+            ::core::option::Option::Some(filter) => filter,
+            ::core::option::Option::None => "",
+        })
+    };
+    ($filter:expr $(,)?) => {{
+        const DIRECTIVES: $crate::Directives = $crate::Directives::parse($filter);
+        &DIRECTIVES
+    }};
+}
+
+/// Logs the number of CPU cycles spent within the current scope.
+///
+/// This starts timing when invoked and logs the elapsed cycles at the debug level when the
+/// enclosing scope exits, as in `update: 1234 cycles`. The name must be a `&'static str`.
+///
+/// Cycles are counted using hardware timers 2 and 3, which are cascaded into a single 32-bit
+/// counter, so scopes of up to 2^32 cycles (a little over four minutes) are timed accurately. The
+/// timers are started the first time this macro is used, and are restarted if they have since been
+/// reconfigured. Programs using this macro should therefore not use timers 2 and 3 themselves.
+///
+/// ``` no_run
+/// fn update() {
+///     mgba_log::time_scope!("update");
+///     // ...
+/// }
+/// ```
+#[macro_export]
+macro_rules! time_scope {
+    ($name:expr) => {
+        let _time_scope = $crate::__TimeScope::new(module_path!(), $name);
+    };
+}
+
+/// Logs entering and exiting the current scope, indenting messages logged within it.
+///
+/// This logs `enter name` at the debug level when invoked and `exit name` when the enclosing scope
+/// exits. Every message logged in between, including those of nested scopes, is indented by two
+/// spaces, making the structure of calls visible in mGBA's flat log. The name must be a
+/// `&'static str`.
+///
+/// Indentation applies to all messages, including those logged by interrupt handlers that run
+/// while a scope is entered, but not to the lines of a multi-line message after the first. Scopes
+/// nested more than 16 deep are not indented any further.
+///
+/// ``` no_run
+/// fn update() {
+///     mgba_log::scope!("update");
+///     log::info!("updating");
+///     physics();
+/// }
+///
+/// fn physics() {
+///     mgba_log::scope!("physics");
+///     // ...
+/// }
+/// ```
+///
+/// Calling `update()` logs the following:
+///
+/// ``` text
+/// enter update
+///   updating
+///   enter physics
+///   exit physics
+/// exit update
+/// ```
+#[macro_export]
+macro_rules! scope {
+    ($name:expr) => {
+        let _scope = $crate::__Scope::new(module_path!(), $name);
+    };
+}
+
+/// Asserts that a boolean expression is `true`, logging a fatal message if it is not.
+///
+/// This behaves like the standard library's `assert!` macro, except that on failure the message
+/// is logged at the fatal level, causing mGBA to halt and display it. If the fatal message is not
+/// logged (for example, if the program is not running in mGBA), this panics with the same message
+/// instead.
+///
+/// A custom message can be provided using the same syntax as `assert!`. As mGBA halts as soon as
+/// the first line of a fatal message is sent, messages should not contain newlines and should be
+/// at most 256 bytes long.
+///
+/// A status value can also be written to an address watched by a test harness when an assertion
+/// fails, using [`LoggerBuilder::with_assertion_status()`].
+///
+/// ``` no_run
+/// let frames = 60;
+/// mgba_log::mgba_assert!(frames > 0);
+/// mgba_log::mgba_assert!(frames < 1000, "too many frames: {}", frames);
+/// ```
+#[macro_export]
+macro_rules! mgba_assert {
+    ($cond:expr $(,)?) => {
+        if !$cond {
+            $crate::__assert_failed(format_args!(
+                "assertion failed: {}",
+                stringify!($cond)
+            ));
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::__assert_failed(format_args!($($arg)+));
+        }
+    };
+}
+
+/// Asserts that two expressions are equal, logging a fatal message if they are not.
+///
+/// This behaves like the standard library's `assert_eq!` macro, except that on failure the values
+/// of both expressions are logged at the fatal level using their [`Debug`](core::fmt::Debug)
+/// implementations, causing mGBA to halt and display them. If the fatal message is not logged
+/// (for example, if the program is not running in mGBA), this panics with the same message
+/// instead.
+///
+/// A custom message can be provided using the same syntax as `assert_eq!`. As mGBA halts as soon
+/// as the first line of a fatal message is sent, messages should not contain newlines and should be
+/// at most 256 bytes long.
+///
+/// ``` no_run
+/// let lives = 3;
+/// mgba_log::mgba_assert_eq!(lives, 3);
+/// mgba_log::mgba_assert_eq!(lives, 3, "unexpected lives at start");
+/// ```
+#[macro_export]
+macro_rules! mgba_assert_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left == *right) {
+                    $crate::__assert_failed(format_args!(
+                        "assertion `left == right` failed (left: `{:?}`, right: `{:?}`)",
+                        left, right
+                    ));
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left == *right) {
+                    $crate::__assert_failed(format_args!(
+                        "assertion `left == right` failed: {} (left: `{:?}`, right: `{:?}`)",
+                        format_args!($($arg)+), left, right
+                    ));
+                }
+            }
+        }
+    };
+}
+
+/// Asserts that a boolean expression is `true` in debug builds, logging a fatal message if it is
+/// not.
+///
+/// This behaves the same as [`mgba_assert!`], except that it is only checked when debug
+/// assertions are enabled. In release builds, the expression is not evaluated.
+#[macro_export]
+macro_rules! debug_mgba_assert {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::mgba_assert!($($arg)*);
+        }
+    };
+}
+
+/// Asserts that two expressions are equal in debug builds, logging a fatal message if they are
+/// not.
+///
+/// This behaves the same as [`mgba_assert_eq!`], except that it is only checked when debug
+/// assertions are enabled. In release builds, the expressions are not evaluated.
+#[macro_export]
+macro_rules! debug_mgba_assert_eq {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::mgba_assert_eq!($($arg)*);
+        }
+    };
+}
+
+/// Reports a failed assertion.
+///
+/// This is an implementation detail of the [`mgba_assert!`] and [`mgba_assert_eq!`] macros. It is
+/// not considered part of the public API and should not be used directly by external code.
+#[doc(hidden)]
+#[cold]
+#[track_caller]
+pub fn __assert_failed(args: Arguments) -> ! {
+    // SAFETY: Interrupts are disabled, so the configuration cannot be accessed concurrently.
+    let status = without_interrupts(|| unsafe { (*LOGGER.config.get()).assertion_status });
+    if let Some((address, value)) = status {
+        // SAFETY: The address was guaranteed to be valid for this write when it was configured.
+        unsafe { (address as *mut u8).write_volatile(value) };
+    }
+    __fatal(args);
+    // Logging at the fatal level halts mGBA. If execution continues, the message could not be
+    // logged, so the failure is reported through the panic handler instead.
+    panic!("{}", args)
+}
+
+/// Indicates unreachable code, logging a fatal message if it is reached.
+///
+/// This behaves like the standard library's `unreachable!` macro, except that the message is
+/// logged at the fatal level, prefixed with the location of the macro invocation, causing mGBA to
+/// halt and display it. If the fatal message is not logged (for example, if the program is not
+/// running in mGBA), this panics with the same message instead.
+///
+/// ``` no_run
+/// let direction = 4;
+/// match direction % 4 {
+///     0 => {}
+///     1 => {}
+///     2 => {}
+///     3 => {}
+///     _ => mgba_log::mgba_unreachable!("invalid direction: {}", direction),
+/// }
+/// ```
+#[macro_export]
+macro_rules! mgba_unreachable {
+    () => {
+        $crate::__fatal_located(format_args!("internal error: entered unreachable code"))
+    };
+    ($($arg:tt)+) => {
+        $crate::__fatal_located(format_args!(
+            "internal error: entered unreachable code: {}",
+            format_args!($($arg)+)
+        ))
+    };
+}
+
+/// Indicates unfinished code, logging a fatal message if it is reached.
+///
+/// This behaves like the standard library's `todo!` macro, except that the message is logged at
+/// the fatal level, prefixed with the location of the macro invocation, causing mGBA to halt and
+/// display it. If the fatal message is not logged (for example, if the program is not running in
+/// mGBA), this panics with the same message instead.
+///
+/// ``` no_run
+/// fn save_game() {
+///     mgba_log::mgba_todo!("saving to flash");
+/// }
+/// ```
+#[macro_export]
+macro_rules! mgba_todo {
+    () => {
+        $crate::__fatal_located(format_args!("not yet implemented"))
+    };
+    ($($arg:tt)+) => {
+        $crate::__fatal_located(format_args!("not yet implemented: {}", format_args!($($arg)+)))
+    };
+}
+
+/// Logs a message at the fatal level, prefixed with the location of the caller, and panics.
+///
+/// This is an implementation detail of the [`mgba_unreachable!`] and [`mgba_todo!`] macros. It is
+/// not considered part of the public API and should not be used directly by external code.
+#[doc(hidden)]
+#[cold]
+#[track_caller]
+pub fn __fatal_located(args: Arguments) -> ! {
+    log_fatal(args, true);
+    // Logging at the fatal level halts mGBA. If execution continues, the message could not be
+    // logged, so the failure is reported through the panic handler instead.
+    panic!("{}", args)
+}
+
+/// Prints to mGBA's log at the info level.
+///
+/// This is equivalent to [`println!`]. As mGBA logs messages line by line, the output of each
+/// invocation is logged as its own message, and a trailing newline does not produce an additional
+/// empty message.
+///
+/// Unlike the [`log`] macros, this does not require the logger to be initialized. If the program
+/// is not running in mGBA, this will have no effect.
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => ($crate::__print($crate::Level::Info, format_args!($($arg)*)));
+}
+
+/// Prints to mGBA's log at the info level.
+///
+/// This allows easily porting code using the standard library's `println!` macro. The output of
+/// each invocation is logged as its own message, with any newlines in the output starting a new
+/// message.
+///
+/// Unlike the [`log`] macros, this does not require the logger to be initialized. If the program
+/// is not running in mGBA, this will have no effect.
+#[macro_export]
+macro_rules! println {
+    () => ($crate::__print($crate::Level::Info, format_args!("")));
+    ($($arg:tt)*) => ($crate::__print($crate::Level::Info, format_args!($($arg)*)));
+}
+
+/// Prints to mGBA's log at the error level.
+///
+/// This is equivalent to [`eprintln!`]. As mGBA logs messages line by line, the output of each
+/// invocation is logged as its own message, and a trailing newline does not produce an additional
+/// empty message.
+///
+/// Unlike the [`log`] macros, this does not require the logger to be initialized. If the program
+/// is not running in mGBA, this will have no effect.
+#[macro_export]
+macro_rules! eprint {
+    ($($arg:tt)*) => ($crate::__print($crate::Level::Error, format_args!($($arg)*)));
+}
+
+/// Prints to mGBA's log at the error level.
+///
+/// This allows easily porting code using the standard library's `eprintln!` macro to write
+/// diagnostics, logging them with the appropriate severity. The output of each invocation is
+/// logged as its own message, with any newlines in the output starting a new message.
+///
+/// Unlike the [`log`] macros, this does not require the logger to be initialized. If the program
+/// is not running in mGBA, this will have no effect.
+#[macro_export]
+macro_rules! eprintln {
+    () => ($crate::__print($crate::Level::Error, format_args!("")));
+    ($($arg:tt)*) => ($crate::__print($crate::Level::Error, format_args!($($arg)*)));
+}
+
+/// Logs a message using [`ufmt`] instead of [`core::fmt`].
+///
+/// This accepts the same arguments as [`log::log!`], except that the message is formatted using
+/// [`ufmt::uwrite!`], so arguments must implement [`ufmt::uDisplay`] or [`ufmt::uDebug`].
+/// Programs that format all of their messages this way avoid linking `core::fmt`'s formatting
+/// machinery, which can take up tens of kilobytes of ROM. The calling crate must depend on
+/// [`ufmt`] directly, as the code generated by [`ufmt::uwrite!`] refers to it by name.
+///
+/// Records are filtered by level and target like records logged through the [`log`] crate, and
+/// are prefixed with their target if [`LoggerBuilder::with_target()`] is enabled. They are not
+/// suffixed with their file and line, and are always written directly to mGBA with interrupts
+/// disabled, as with [`MgbaWriter`], rather than being staged or deferred.
+///
+/// ``` no_run
+/// use log::Level;
+///
+/// let frame: u32 = 42;
+/// mgba_log::ulog!(Level::Info, "frame {}", frame);
+/// mgba_log::ulog!(target: "audio", Level::Warn, "buffer underrun");
+/// ```
+///
+/// This macro is only available when the `ufmt` feature is enabled.
+#[cfg(feature = "ufmt")]
+#[macro_export]
+macro_rules! ulog {
+    (target: $target:expr, $lvl:expr, $($arg:tt)+) => {{
+        let level: $crate::__log::Level = $lvl;
+        if level <= $crate::__log::STATIC_MAX_LEVEL {
+            if let Some(mut writer) = $crate::__ulog(level, $target) {
+                // Writing to the log buffer cannot fail.
+                let _ = ::ufmt::uwrite!(writer, $($arg)+);
+            }
+        }
+    }};
+    ($lvl:expr, $($arg:tt)+) => ($crate::ulog!(target: ::core::module_path!(), $lvl, $($arg)+));
+}
+
+/// Logs a message at the debug level using [`ufmt`].
+///
+/// See [`ulog!`] for details. This macro is only available when the `ufmt` feature is enabled.
+#[cfg(feature = "ufmt")]
+#[macro_export]
+macro_rules! udebug {
+    (target: $target:expr, $($arg:tt)+) => ($crate::ulog!(target: $target, $crate::__log::Level::Debug, $($arg)+));
+    ($($arg:tt)+) => ($crate::ulog!($crate::__log::Level::Debug, $($arg)+));
+}
+
+/// Logs a message at the info level using [`ufmt`].
+///
+/// See [`ulog!`] for details. This macro is only available when the `ufmt` feature is enabled.
+#[cfg(feature = "ufmt")]
+#[macro_export]
+macro_rules! uinfo {
+    (target: $target:expr, $($arg:tt)+) => ($crate::ulog!(target: $target, $crate::__log::Level::Info, $($arg)+));
+    ($($arg:tt)+) => ($crate::ulog!($crate::__log::Level::Info, $($arg)+));
+}
+
+/// Logs a message at the warn level using [`ufmt`].
+///
+/// See [`ulog!`] for details. This macro is only available when the `ufmt` feature is enabled.
+#[cfg(feature = "ufmt")]
+#[macro_export]
+macro_rules! uwarn {
+    (target: $target:expr, $($arg:tt)+) => ($crate::ulog!(target: $target, $crate::__log::Level::Warn, $($arg)+));
+    ($($arg:tt)+) => ($crate::ulog!($crate::__log::Level::Warn, $($arg)+));
+}
+
+/// Logs a message at the error level using [`ufmt`].
+///
+/// See [`ulog!`] for details. This macro is only available when the `ufmt` feature is enabled.
+#[cfg(feature = "ufmt")]
+#[macro_export]
+macro_rules! uerror {
+    (target: $target:expr, $($arg:tt)+) => ($crate::ulog!(target: $target, $crate::__log::Level::Error, $($arg)+));
+    ($($arg:tt)+) => ($crate::ulog!($crate::__log::Level::Error, $($arg)+));
+}
+
+/// Prints a message to mGBA's log at the given level.
+///
+/// This is an implementation detail of the [`print!`], [`println!`], [`eprint!`], and
+/// [`eprintln!`] macros. It is not
+/// considered part of the public API and should not be used directly by external code.
+#[doc(hidden)]
+pub fn __print(level: Level, args: Arguments) {
+    let listening = match without_interrupts(|| writer_config().backend) {
+        Backend::Mgba => is_mgba(),
+        Backend::NoCashGba | Backend::AgbPrint | Backend::Uart(_) => true,
+    };
+    if listening {
+        // Writing to the log buffer cannot fail, so the result is ignored.
+        #[allow(unused_must_use)]
+        {
+            write(&mut MgbaWriter::new(level), args);
+        }
+    }
+}
+
+/// Begins a record logged through the [`ulog!`] family of macros.
+///
+/// Returns a writer that the message should be written to, or `None` if a record at the given
+/// `level` and `target` would not be logged. The writer has already been given any prefixes the
+/// logger is configured to write.
+///
+/// This is an implementation detail of the [`ulog!`] family of macros. It is not considered part
+/// of the public API and should not be used directly by external code.
+#[cfg(feature = "ufmt")]
+#[doc(hidden)]
+#[must_use]
+pub fn __ulog(level: log::Level, target: &str) -> Option<MgbaWriter> {
+    if cfg!(feature = "disabled")
+        || level > log::STATIC_MAX_LEVEL
+        || level > log::max_level()
+        || silenced()
+    {
+        return None;
+    }
+    let mgba_level = Level::try_from(level).ok()?;
+    let config = without_interrupts(|| {
+        // SAFETY: Interrupts are disabled, so the configuration cannot be accessed concurrently.
+        let config = unsafe { &*LOGGER.config.get() };
+        config.allows(level, target).then_some(*config)
+    })?;
+    let mut writer = MgbaWriter::new(mgba_level);
+    // Writing to the log buffer cannot fail.
+    let _ = config.write_prefix(&mut writer);
+    // Trace records are logged at the debug level, so they are prefixed to distinguish them from
+    // debug records.
+    #[cfg(feature = "trace-as-debug")]
+    if level == log::Level::Trace {
+        writer.write_text(b"[TRACE] ");
+    }
+    if config.target {
+        writer.write_text(target.as_bytes());
+        writer.write_text(b": ");
+    }
+    Some(writer)
+}
+
+/// An error occurring during initialization.
+///
+/// New variants may be added in future releases as more ways of configuring the logger are added,
+/// so matches on this type must include a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Enabling of logging was not acknowledged by MGBA.
+    ///
+    /// This likely indicates that the program is not being run in mGBA at all. In many cases, this
+    /// may be considered a recoverable error. However, if this error is returned by [`init()`],
+    /// then the logger was never actually set, meaning a different logger could potentially be set
+    /// instead.
+    NotAcknowledgedByMgba {
+        /// The value read back from mGBA's enable register after requesting that debug output be
+        /// enabled.
+        ///
+        /// mGBA acknowledges the request by reading as `0x1DEA`. Other emulators and real hardware
+        /// typically read back open bus or `0`. This is always `0` when the `disabled` feature is
+        /// enabled, as no handshake is performed at all.
+        handshake: u16,
+    },
+
+    /// Initialization of AGBPrint was not acknowledged.
+    ///
+    /// This is returned instead of [`NotAcknowledgedByMgba`](Self::NotAcknowledgedByMgba) when
+    /// logging through [`Backend::AgbPrint`], and likely indicates that the program is not being
+    /// run in an emulator supporting AGBPrint.
+    NotAcknowledgedByAgbPrint,
+
+    /// NO$GBA was not detected.
+    ///
+    /// This is returned instead of [`NotAcknowledgedByMgba`](Self::NotAcknowledgedByMgba) when
+    /// logging through [`Backend::NoCashGba`], and indicates that the program is not being run in
+    /// NO$GBA.
+    NotAcknowledgedByNoCashGba,
+
+    /// None of the supported emulators were detected by [`init_auto()`].
+    ///
+    /// This likely indicates that the program is running on real hardware or in an unsupported
+    /// emulator.
+    NoEmulatorDetected,
+
+    /// An error returned by `log::set_logger()`.
+    ///
+    /// This most often indicates that another logger has already been set by the program.
+    SetLoggerError(SetLoggerError),
+
+    /// More targets were allowed or denied than can be stored.
+    ///
+    /// At most eight targets can be allowed and eight targets can be denied at once.
+    TooManyTargetFilters,
+
+    /// The continuation marker is too long, or contains a newline or null character.
+    ///
+    /// See [`LoggerBuilder::with_continuation_marker()`] for the requirements on the marker.
+    InvalidContinuationMarker,
+
+    /// The substitute for null characters is itself a newline or null character.
+    InvalidNullSubstitute,
+
+    /// The continuation line prefix is too long, or contains a newline or null character.
+    ///
+    /// See [`LoggerBuilder::with_continuation_line_prefix()`] for the requirements on the prefix.
+    InvalidLinePrefix,
+
+    /// The region of SRAM set for mirroring messages does not fit in SRAM or is too short.
+    ///
+    /// See [`LoggerBuilder::with_sram_log()`] for the requirements on the region.
+    InvalidSramLog,
+
+    /// The offset within SRAM set for crash dumps leaves no room for a full crash dump.
+    ///
+    /// See [`LoggerBuilder::with_crash_dump_sram()`] for the requirements on the offset.
+    #[cfg(feature = "crash-dump")]
+    InvalidCrashDumpSram,
+}
+
+impl From<SetLoggerError> for Error {
+    fn from(error: SetLoggerError) -> Self {
+        Self::SetLoggerError(error)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, fmt: &mut Formatter) -> core::fmt::Result {
+        match self {
+            Self::NotAcknowledgedByMgba { handshake } => write!(
+                fmt,
+                "mGBA did not acknowledge initialization (read {handshake:#06X} instead of 0x1DEA)"
+            ),
+            Self::NotAcknowledgedByAgbPrint => {
+                fmt.write_str("AGBPrint did not acknowledge initialization")
+            }
+            Self::NotAcknowledgedByNoCashGba => fmt.write_str("NO$GBA was not detected"),
+            Self::NoEmulatorDetected => fmt.write_str("no supported emulator was detected"),
+            Self::SetLoggerError(error) => write!(fmt, "`log::set_logger()` error: {error}"),
+            Self::TooManyTargetFilters => fmt.write_str("too many target filters"),
+            Self::InvalidContinuationMarker => fmt.write_str("invalid continuation marker"),
+            Self::InvalidNullSubstitute => fmt.write_str("invalid null substitute"),
+            Self::InvalidLinePrefix => fmt.write_str("invalid continuation line prefix"),
+            Self::InvalidSramLog => fmt.write_str("invalid SRAM log region"),
+            #[cfg(feature = "crash-dump")]
+            Self::InvalidCrashDumpSram => fmt.write_str("invalid SRAM crash dump offset"),
+        }
+    }
+}
+
+/// Requires Rust 1.81 or later, where the `Error` trait was made available in `core`.
+#[cfg(core_error)]
+impl core::error::Error for Error {}
+
+/// Writes directly to mGBA's log buffer at a given level.
+///
+/// This allows streaming arbitrary [`fmt::Write`](core::fmt::Write) output into mGBA's log
+/// without going through the [`log`] crate. All output written to a single writer is logged as a
+/// single message, which is sent when the writer is dropped. Newlines within the output start new
+/// messages, and messages longer than mGBA's 256 byte buffer are split across multiple messages,
+/// without splitting any character across two messages. Split messages are marked using the
+/// continuation marker set through [`LoggerBuilder::with_continuation_marker()`], if any.
+///
+/// Interrupts are disabled for as long as the writer is alive, preventing messages logged within
+/// interrupt handlers from interfering with the output. Therefore, writers should be dropped as
+/// soon as writing is finished.
+///
+/// Output is only displayed by mGBA once logging has been enabled, either by initializing the
+/// logger or by calling [`is_mgba()`]. Writing at [`Level::Fatal`] will halt execution once the
+/// buffer is flushed.
+///
+/// ``` no_run
+/// use core::fmt::Write;
+/// use mgba_log::{Level, MgbaWriter};
+///
+/// let mut writer = MgbaWriter::new(Level::Info);
+/// write!(writer, "Hello, ").unwrap();
+/// write!(writer, "world!").unwrap();
+/// ```
+///
+/// When the `ufmt` feature is enabled, this also implements `ufmt::uWrite`, allowing output to be
+/// written using `ufmt::uwrite!` without linking `core::fmt`'s formatting machinery.
+///
+/// ``` ignore
+/// use mgba_log::{Level, MgbaWriter};
+///
+/// ufmt::uwrite!(MgbaWriter::new(Level::Info), "frame {}", 42u32).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct MgbaWriter {
+    /// The underlying writer.
+    ///
+    /// This must be declared before `_interrupts` so that it is dropped, flushing the buffer,
+    /// before interrupts are reenabled.
+    #[cfg(not(feature = "disabled"))]
+    writer: Writer,
+    /// Disables interrupts for the lifetime of the writer.
+    #[cfg(not(feature = "disabled"))]
+    _interrupts: InterruptGuard,
+    /// Prevents construction outside of this crate when the `disabled` feature is enabled.
+    #[cfg(feature = "disabled")]
+    _private: (),
+}
+
+impl MgbaWriter {
+    /// Creates a new writer for the given mGBA log level.
+    ///
+    /// Interrupts are disabled until the returned writer is dropped.
+    #[must_use]
+    #[cfg(not(feature = "disabled"))]
+    pub fn new(level: Level) -> Self {
+        // Fields are initialized in order, so the configuration is read with interrupts disabled.
+        Self {
+            _interrupts: InterruptGuard::new(),
+            writer: Writer::new(level, writer_config()),
+        }
+    }
+
+    /// Creates a new writer for the given mGBA log level.
+    ///
+    /// Interrupts are disabled until the returned writer is dropped.
+    #[must_use]
+    #[cfg(feature = "disabled")]
+    pub fn new(_level: Level) -> Self {
+        Self { _private: () }
+    }
+
+    /// Writes `text` to the log buffer.
+    #[cfg(not(feature = "disabled"))]
+    fn write_text(&mut self, text: &[u8]) {
+        self.writer.write_text(text);
+    }
+
+    /// Writes `text` to the log buffer.
+    #[cfg(feature = "disabled")]
+    #[allow(clippy::unused_self)]
+    fn write_text(&mut self, _text: &[u8]) {}
+}
+
+impl Write for MgbaWriter {
+    /// Write the given string to the log buffer.
+    ///
+    /// The buffer is flushed automatically when it becomes full.
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.write_text(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uWrite for MgbaWriter {
+    type Error = core::convert::Infallible;
+
+    /// Write the given string to the log buffer.
+    ///
+    /// The buffer is flushed automatically when it becomes full.
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.write_text(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ErrorType for MgbaWriter {
+    type Error = core::convert::Infallible;
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Write for MgbaWriter {
+    /// Write the given bytes to the log buffer.
+    ///
+    /// Every byte is always written, and the buffer is flushed automatically when it becomes full.
+    /// The bytes do not need to be valid UTF-8.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_text(buf);
+        Ok(buf.len())
+    }
+
+    /// Does nothing.
+    ///
+    /// The message written so far is sent when a newline is written or when the writer is
+    /// dropped. Sending it any earlier would split it across multiple messages.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// A static logger instance.
+///
+/// When initializing with [`log::set_logger()`], a static reference to a logger must be provided.
+/// This static logger can be used as the static reference.
+static LOGGER: Logger = Logger::new();
+/// A static no-op logger instance.
+///
+/// This is the static reference provided to [`log::set_logger()`] when a no-op logger is
+/// installed.
+static NOP_LOGGER: NopLogger = NopLogger;
+/// Whether logging is paused.
+///
+/// See [`pause()`] for details.
+static PAUSED: RacyCell<bool> = RacyCell::new(false);
+/// Whether the logger was deactivated by dropping a [`LoggerGuard`].
+static DEACTIVATED: RacyCell<bool> = RacyCell::new(false);
+
+/// Returns how a [`Writer`] writes text.
+///
+/// This must only be called while interrupts are disabled.
+fn writer_config() -> WriterConfig {
+    // SAFETY: Interrupts are disabled, so the configuration cannot be accessed concurrently.
+    unsafe { (*LOGGER.config.get()).writer_config() }
+}
+
+/// Returns whether records should currently be discarded.
+fn silenced() -> bool {
+    PAUSED.get() || DEACTIVATED.get()
+}
+
+/// The number of records that failed to format.
+///
+/// See [`format_errors()`] for details.
+static FORMAT_ERRORS: RacyCell<u32> = RacyCell::new(0);
+
+/// The number of frames counted through [`tick_frame()`].
+///
+/// See [`frame_count()`] for details.
+static FRAME_COUNT: RacyCell<u32> = RacyCell::new(0);
+
+/// The sequence number of the next message.
+///
+/// See [`LoggerBuilder::with_sequence_numbers()`] for details.
+static SEQUENCE_NUMBER: RacyCell<u32> = RacyCell::new(0);
+
+/// Returns the sequence number of a new message, advancing the sequence.
+fn next_sequence_number() -> u32 {
+    without_interrupts(|| {
+        let number = SEQUENCE_NUMBER.get();
+        SEQUENCE_NUMBER.set(number.wrapping_add(1));
+        number
+    })
+}
+
+/// Records that a record failed to format.
+fn count_format_error() {
+    without_interrupts(|| FORMAT_ERRORS.set(FORMAT_ERRORS.get().saturating_add(1)));
+}
+
+/// A builder for configuring and initializing mGBA logging.
+///
+/// A builder can be obtained using [`builder()`]. Once configured, the logger can be initialized
+/// with [`LoggerBuilder::init()`].
+///
+/// ``` no_run
+/// use log::LevelFilter;
+///
+/// mgba_log::builder()
+///     .with_target(true)
+///     .with_file_line(true)
+///     .with_level(LevelFilter::Info)
+///     .init()
+///     .expect("unable to initialize mGBA logger");
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct LoggerBuilder {
+    /// The configuration of the logger being built.
+    config: Config,
+    /// The maximum log level.
+    level: LevelFilter,
+    /// Whether more target filters were added than can be stored.
+    too_many_target_filters: bool,
+    /// Whether the region of SRAM set for mirroring messages is invalid.
+    invalid_sram_log: bool,
+    /// Whether the offset within SRAM set for crash dumps is invalid.
+    #[cfg(feature = "crash-dump")]
+    invalid_crash_dump_sram: bool,
+    /// The strategy used to prevent interrupts from disturbing logging.
+    interrupt_mask: &'static InterruptMask,
+}
+
+impl LoggerBuilder {
+    /// Creates a new builder with the default configuration.
+    ///
+    /// Initializing with the default configuration is equivalent to calling [`init()`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            config: Config::new(),
+            level: MAX_LEVEL_FILTER,
+            too_many_target_filters: false,
+            invalid_sram_log: false,
+            #[cfg(feature = "crash-dump")]
+            invalid_crash_dump_sram: false,
+            interrupt_mask: &InterruptMask::IME,
+        }
+    }
+
+    /// Sets the interface messages are sent through.
+    ///
+    /// Messages are sent through mGBA's debug registers by default. Setting [`Backend::NoCashGba`]
+    /// instead sends them to NO$GBA's debug message window, and setting [`Backend::AgbPrint`] sends
+    /// them through the AGBPrint protocol used by VisualBoyAdvance and its forks, such as VBA-M.
+    /// Setting [`Backend::Uart`] sends them over the serial port, for capturing through a USB link
+    /// cable adapter on real hardware.
+    ///
+    /// Initialization returns [`Error::NotAcknowledgedByNoCashGba`] if NO$GBA is not detected, and
+    /// [`Error::NotAcknowledgedByAgbPrint`] if AGBPrint does not acknowledge. Nothing acknowledges
+    /// on the other end of the serial port, so [`Backend::Uart`] never causes initialization to
+    /// fail: the port is switched to UART mode, and messages are sent whether or not anything is
+    /// listening. To detect the backend automatically, use [`init_auto()`](Self::init_auto())
+    /// instead.
+    ///
+    /// ``` no_run
+    /// use mgba_log::Backend;
+    ///
+    /// mgba_log::builder()
+    ///     .with_backend(Backend::AgbPrint)
+    ///     .init()
+    ///     .expect("unable to initialize AGBPrint logger");
+    /// ```
+    #[must_use]
+    pub const fn with_backend(mut self, backend: Backend) -> Self {
+        self.config.backend = backend;
+        self
+    }
+
+    /// Sets whether each message is prefixed with the target of its record.
+    ///
+    /// When enabled, a record logged with `log::info!(target: "physics", "Hello, world!")` is
+    /// rendered as `physics: Hello, world!`. Defaults to `false`.
+    #[must_use]
+    pub const fn with_target(mut self, enabled: bool) -> Self {
+        self.config.target = enabled;
+        self
+    }
+
+    /// Sets whether each message is suffixed with the file and line of its record.
+    ///
+    /// When enabled, a record logged on line 42 of `src/main.rs` is rendered as
+    /// `Hello, world! (src/main.rs:42)`. Paths are trimmed to be relative to the root of their
+    /// crate, so records logged by dependencies and workspace members are rendered the same way,
+    /// rather than with their full paths. Defaults to `false`.
+    #[must_use]
+    pub const fn with_file_line(mut self, enabled: bool) -> Self {
+        self.config.file_line = enabled;
+        self
+    }
+
+    /// Sets whether fatal messages are prefixed with the file and line they were logged from.
+    ///
+    /// When enabled, `fatal!("out of memory")` on line 42 of `src/main.rs` is rendered as
+    /// `src/main.rs:42: out of memory`. This also applies to the messages of failed assertions
+    /// made using [`mgba_assert!`] and related macros. The location is placed before the message,
+    /// rather than after it, so that it is not cut off when the message exceeds the 256 bytes
+    /// mGBA displays. Paths are trimmed in the same way as for
+    /// [`with_file_line()`](Self::with_file_line()). Defaults to `false`.
+    #[must_use]
+    pub const fn with_fatal_location(mut self, enabled: bool) -> Self {
+        self.config.fatal_location = enabled;
+        self
+    }
+
+    /// Sets a `value` to be written to the byte at `address` when an assertion made using
+    /// [`mgba_assert!`] or related macros fails.
+    ///
+    /// The value is written before the failure is logged, which halts mGBA. This lets a test
+    /// harness watching the address tell failed assertions apart from hangs, in the same way as it
+    /// detects that a test ROM finished. Defaults to writing nothing.
+    ///
+    /// ``` no_run
+    /// // Tell the harness watching 0x0203FFFF that an assertion failed.
+    /// // SAFETY: The byte is in EWRAM and is not used by the program otherwise.
+    /// let builder = unsafe { mgba_log::builder().with_assertion_status(0x0203_FFFF, 2) };
+    /// builder.init().expect("unable to initialize mGBA logger");
+    /// ```
+    ///
+    /// # Safety
+    /// `address` must be valid for a volatile byte write whenever an assertion can fail, and
+    /// writing `value` to it must not violate any invariant of the program.
+    #[must_use]
+    pub const unsafe fn with_assertion_status(mut self, address: usize, value: u8) -> Self {
+        self.config.assertion_status = Some((address, value));
+        self
+    }
+
+    /// Sets whether records are written without disabling interrupts.
+    ///
+    /// By default, interrupts are disabled while each record is formatted and written, so that
+    /// records logged by interrupt handlers cannot clobber a record that is only partially
+    /// written. Formatting can take a long time, which can disturb audio and video timing.
+    ///
+    /// When enabled, interrupts remain enabled while records are formatted and written. A record
+    /// logged by an interrupt handler while another record is being written is instead formatted
+    /// into a staging buffer in RAM, and is sent as soon as the interrupted record is finished.
+    /// Interrupts are then only disabled briefly, to check target filters and to copy staged
+    /// records. Up to 1024 bytes of records can be staged at once; records beyond that are
+    /// truncated or discarded.
+    ///
+    /// Only records logged through the [`log`] macros are staged. [`fatal!`], [`print!`], and
+    /// [`MgbaWriter`] still disable interrupts while writing, and should not be used by interrupt
+    /// handlers in this mode. Defaults to `false`.
+    #[must_use]
+    pub const fn with_interrupt_staging(mut self, enabled: bool) -> Self {
+        self.config.interrupt_staging = enabled;
+        self
+    }
+
+    /// Sets whether records are formatted into RAM before interrupts are disabled.
+    ///
+    /// By default, interrupts are disabled while each record is formatted and written. When
+    /// enabled, each record is instead first formatted into a 256 byte buffer on the stack while
+    /// interrupts are still enabled, and interrupts are only disabled while the formatted text is
+    /// copied to mGBA's log buffer. This shrinks the time spent with interrupts disabled,
+    /// especially for records with expensive formatting. Records longer than 256 bytes are
+    /// truncated.
+    ///
+    /// This can be combined with [`with_interrupt_staging()`](Self::with_interrupt_staging()), in
+    /// which case the formatted text is copied without disabling interrupts. Defaults to `false`.
+    #[must_use]
+    pub const fn with_buffered_formatting(mut self, enabled: bool) -> Self {
+        self.config.buffered_formatting = enabled;
+        self
+    }
+
+    /// Sets the strategy used to prevent interrupts from disturbing logging.
+    ///
+    /// By default, all interrupts are disabled through the `IME` register while records are
+    /// written, as in [`InterruptMask::IME`]. This allows masking only the interrupts whose
+    /// handlers log, or using [`InterruptMask::NONE`] if no interrupt handlers log. See
+    /// [`InterruptMask`] for details.
+    ///
+    /// This has no effect if the `critical-section` or `no-ime` features are enabled.
+    #[must_use]
+    pub const fn with_interrupt_mask(mut self, mask: &'static InterruptMask) -> Self {
+        self.interrupt_mask = mask;
+        self
+    }
+
+    /// Sets whether records logged by interrupt handlers are deferred.
+    ///
+    /// When enabled, records logged within interrupt handlers are formatted into a buffer in RAM
+    /// instead of being written to mGBA. This bounds the time spent logging within interrupt
+    /// handlers. Deferred records are sent the next time a record is logged outside of an
+    /// interrupt handler, or when [`flush()`] is called, such as once per iteration of the main
+    /// loop. Up to 1024 bytes of records can be deferred at once; records beyond that are truncated
+    /// or discarded.
+    ///
+    /// Interrupt handlers are detected by the CPU being in IRQ mode or having IRQs disabled in the
+    /// CPSR. Handlers that switch to another mode and reenable IRQs are not detected, and their
+    /// records are logged immediately. Defaults to `false`.
+    #[must_use]
+    pub const fn with_deferred_interrupt_logs(mut self, enabled: bool) -> Self {
+        self.config.defer_interrupt_logs = enabled;
+        self
+    }
+
+    /// Sets the marker written where a message is split across multiple sends.
+    ///
+    /// mGBA's log buffer holds 256 bytes, so longer messages are split into multiple messages.
+    /// When a marker such as `"…"` is set, each split message ends with the marker and the message
+    /// continuing it starts with the marker, so that readers and tools can tell which messages
+    /// belong together and reassemble them. Messages are split without a marker by default.
+    ///
+    /// The marker must be at most 16 bytes long and must not contain newlines or null characters.
+    /// Otherwise, [`init()`](Self::init()) returns [`Error::InvalidContinuationMarker`].
+    #[must_use]
+    pub const fn with_continuation_marker(mut self, marker: &'static str) -> Self {
+        self.config.continuation_marker = marker;
+        self
+    }
+
+    /// Sets whether messages that do not fit in mGBA's log buffer are truncated instead of split.
+    ///
+    /// By default, messages longer than mGBA's 256 byte log buffer are split across multiple
+    /// messages. When enabled, such messages are instead truncated to 255 bytes, ending with `…`,
+    /// and the rest of the message is discarded. Each line of a message is truncated separately.
+    /// This is especially useful for fatal messages, as mGBA halts once the first part of a split
+    /// fatal message is sent. The continuation marker is unused when enabled. Defaults to `false`.
+    #[must_use]
+    pub const fn with_truncation(mut self, enabled: bool) -> Self {
+        self.config.truncate = enabled;
+        self
+    }
+
+    /// Sets the character written in place of null characters.
+    ///
+    /// mGBA interprets a null character as the end of a message, so null characters within
+    /// messages are replaced. The substitute must not be a newline or null character. Otherwise,
+    /// [`init()`](Self::init()) returns [`Error::InvalidNullSubstitute`]. Defaults to `'\x1a'`,
+    /// the ASCII substitute character.
+    #[must_use]
+    pub const fn with_null_substitute(mut self, substitute: char) -> Self {
+        self.config.null_substitute = substitute;
+        self
+    }
+
+    /// Sets whether control bytes are escaped.
+    ///
+    /// When enabled, every control byte other than a newline, including null characters, is
+    /// written as `\xNN`, where `NN` is the byte in hexadecimal, and backslashes are written as
+    /// `\\`. This keeps binary data readable and unambiguous in captured logs. The null substitute
+    /// is unused when enabled. Defaults to `false`.
+    #[must_use]
+    pub const fn with_escaped_control_bytes(mut self, enabled: bool) -> Self {
+        self.config.escape_control = enabled;
+        self
+    }
+
+    /// Sets whether carriage returns are treated as line breaks.
+    ///
+    /// When enabled, both `\r\n` and a lone `\r` start a new message, just as `\n` does. This
+    /// avoids logging stray `\r` bytes when echoing text that uses Windows or classic Mac OS line
+    /// endings. When disabled, carriage returns are written as-is, or escaped if
+    /// [`with_escaped_control_bytes()`](Self::with_escaped_control_bytes()) is enabled. Defaults to
+    /// `false`.
+    #[must_use]
+    pub const fn with_carriage_return_line_breaks(mut self, enabled: bool) -> Self {
+        self.config.carriage_return = enabled;
+        self
+    }
+
+    /// Sets the distance between the tab stops that tabs are expanded to.
+    ///
+    /// mGBA's log window renders tabs inconsistently. When `width` is not `0`, each `\t` is instead
+    /// replaced by the spaces needed to reach the next column that is a multiple of `width`, so
+    /// that table-style output lines up. Columns are counted in bytes from the start of each
+    /// message. When `0`, tabs are written as-is, or escaped if
+    /// [`with_escaped_control_bytes()`](Self::with_escaped_control_bytes()) is enabled. Defaults to
+    /// `0`.
+    #[must_use]
+    pub const fn with_tab_width(mut self, width: u8) -> Self {
+        self.config.tab_width = width;
+        self
+    }
+
+    /// Sets the prefix written at the start of each line of a message after the first.
+    ///
+    /// Each line of a message containing newlines is logged as a separate message. When a prefix
+    /// such as `"  | "` is set, every line after the first starts with the prefix, so that the
+    /// lines of a multi-line message are visually grouped. Lines start without a prefix by
+    /// default.
+    ///
+    /// The prefix must be at most 16 bytes long and must not contain newlines or null characters.
+    /// Otherwise, [`init()`](Self::init()) returns [`Error::InvalidLinePrefix`].
+    #[must_use]
+    pub const fn with_continuation_line_prefix(mut self, prefix: &'static str) -> Self {
+        self.config.line_prefix = prefix;
+        self
+    }
+
+    /// Sets the function rendering each record.
+    ///
+    /// By default, each record is rendered as its message, decorated as configured through
+    /// [`with_target()`](Self::with_target()) and [`with_file_line()`](Self::with_file_line()).
+    /// When a formatter is set, it instead writes the complete message for each record, allowing
+    /// full control over prefixes, field order, and separators. The written text is handled in the
+    /// same way as any other message, so newlines still start new messages.
+    ///
+    /// Messages logged through the `ulog!` family of macros are not records, and are therefore not
+    /// rendered by the formatter.
+    ///
+    /// ``` no_run
+    /// use core::fmt::Write;
+    /// use log::Record;
+    ///
+    /// fn format(writer: &mut dyn Write, record: &Record) -> core::fmt::Result {
+    ///     write!(writer, "[{}] {}", record.target(), record.args())
+    /// }
+    ///
+    /// mgba_log::builder()
+    ///     .with_formatter(&format)
+    ///     .init()
+    ///     .expect("unable to initialize mGBA logger");
+    /// ```
+    #[must_use]
+    pub const fn with_formatter(mut self, formatter: &'static dyn FormatRecord) -> Self {
+        self.config.formatter = Some(RecordFormatter(formatter));
+        self
+    }
+
+    /// Sets a logger that every record is also forwarded to.
+    ///
+    /// This allows records to be logged both to mGBA and to, for example, an on-screen console or
+    /// a buffer in RAM, without having to wrap this logger. Each record is forwarded after it is
+    /// sent to mGBA, if the secondary logger's [`enabled()`](Log::enabled()) accepts it. The
+    /// secondary logger's own filtering applies instead of this logger's target filters, but
+    /// records more verbose than the level set through [`with_level()`](Self::with_level()) are
+    /// never logged at all. Calling [`flush()`](crate::flush()) flushes the secondary logger as
+    /// well.
+    ///
+    /// The secondary logger is called with interrupts enabled, unless the record is logged from
+    /// an interrupt handler. It must therefore be able to handle records logged by interrupt
+    /// handlers while it is logging another record, if the program logs from interrupt handlers.
+    ///
+    /// ``` no_run
+    /// use log::{Log, Metadata, Record};
+    ///
+    /// struct Console;
+    ///
+    /// impl Log for Console {
+    ///     fn enabled(&self, metadata: &Metadata) -> bool {
+    ///         metadata.level() <= log::Level::Warn
+    ///     }
+    ///
+    ///     fn log(&self, record: &Record) {
+    ///         // Draw the record on screen.
+    ///     }
+    ///
+    ///     fn flush(&self) {}
+    /// }
+    ///
+    /// mgba_log::builder()
+    ///     .with_secondary_logger(&Console)
+    ///     .init()
+    ///     .expect("unable to initialize mGBA logger");
+    /// ```
+    #[must_use]
+    pub const fn with_secondary_logger(mut self, logger: &'static dyn Log) -> Self {
+        self.config.secondary = Some(SecondaryLogger(logger));
+        self
+    }
 
-        // Fatal logging is often used in panic handlers, so panicking on write failures would lead
-        // to recursive panicking. Instead, this fails silently.
-        #[allow(unused_must_use)]
-        {
-            write(&mut Writer::new(Level::Fatal), args);
+    /// Sets whether each message is prefixed with the current frame count, as in
+    /// `[frame 42] Hello, world!`.
+    ///
+    /// The frame count is advanced by calling [`tick_frame()`] once per frame, typically from the
+    /// program's vblank interrupt handler. Programs that already count frames can instead provide
+    /// their own count through [`with_frame_counter()`](Self::with_frame_counter()).
+    ///
+    /// The prefix is written before any other decoration, but not by a formatter set through
+    /// [`with_formatter()`](Self::with_formatter()), which can call [`frame_count()`] itself.
+    /// Defaults to `false`.
+    ///
+    /// ``` no_run
+    /// mgba_log::builder()
+    ///     .with_frame_timestamps(true)
+    ///     .init()
+    ///     .expect("unable to initialize mGBA logger");
+    ///
+    /// extern "C" fn vblank_handler() {
+    ///     mgba_log::tick_frame();
+    /// }
+    /// ```
+    #[must_use]
+    pub const fn with_frame_timestamps(mut self, enabled: bool) -> Self {
+        self.config.frame_counter = if enabled { Some(frame_count) } else { None };
+        self
+    }
+
+    /// Prefixes each message with the frame count returned by `frame_counter`.
+    ///
+    /// This behaves the same as [`with_frame_timestamps()`](Self::with_frame_timestamps()), but
+    /// reads the frame count from the program instead of counting calls to [`tick_frame()`].
+    /// `frame_counter` is called once per message, possibly from within interrupt handlers and
+    /// while interrupts are disabled, so it should do little more than read a value.
+    ///
+    /// ``` no_run
+    /// /// The frame count, incremented by the program's vblank interrupt handler.
+    /// const FRAMES: *const u32 = 0x0300_7ff0 as *const u32;
+    ///
+    /// fn frames() -> u32 {
+    ///     unsafe { FRAMES.read_volatile() }
+    /// }
+    ///
+    /// mgba_log::builder()
+    ///     .with_frame_counter(frames)
+    ///     .init()
+    ///     .expect("unable to initialize mGBA logger");
+    /// ```
+    #[must_use]
+    pub const fn with_frame_counter(mut self, frame_counter: fn() -> u32) -> Self {
+        self.config.frame_counter = Some(frame_counter);
+        self
+    }
+
+    /// Registers `heap_stats` as the source of the heap usage logged by [`log_heap_stats!`].
+    ///
+    /// This is the integration point for whichever allocator the program uses, such as `agb`'s or
+    /// its own. `heap_stats` is called each time [`log_heap_stats!`] is invoked, with interrupts
+    /// enabled, so it may disable them itself while reading the allocator's state. Without a
+    /// registered source, [`log_heap_stats!`] logs nothing.
+    ///
+    /// ``` no_run
+    /// use mgba_log::HeapUsage;
+    ///
+    /// /// The number of bytes the program's allocator has handed out.
+    /// const ALLOCATED: *const usize = 0x0300_7ff0 as *const usize;
+    /// /// The size of the program's heap.
+    /// const HEAP_SIZE: usize = 0x1_0000;
+    ///
+    /// fn heap_stats() -> HeapUsage {
+    ///     let used = unsafe { ALLOCATED.read_volatile() };
+    ///     HeapUsage {
+    ///         used,
+    ///         free: HEAP_SIZE - used,
+    ///         largest_free: None,
+    ///     }
+    /// }
+    ///
+    /// mgba_log::builder()
+    ///     .with_heap_stats(heap_stats)
+    ///     .init()
+    ///     .expect("unable to initialize mGBA logger");
+    /// ```
+    #[must_use]
+    pub const fn with_heap_stats(mut self, heap_stats: fn() -> HeapUsage) -> Self {
+        self.config.heap_stats = Some(heap_stats);
+        self
+    }
+
+    /// Paints the stack between `bottom` and `top` at initialization, allowing the most stack ever
+    /// used to be logged by [`log_stack_usage!`].
+    ///
+    /// When the logger is initialized, every unused word of the stack, from `bottom` up to the
+    /// current stack pointer, is filled with a known pattern. [`log_stack_usage!`] then finds the
+    /// deepest point the stack has reached by looking for the lowest word that no longer holds
+    /// it. A stack that grows into the IWRAM data placed below it silently corrupts that data,
+    /// so checking the stack usage regularly catches overflows before they cause mysterious bugs.
+    ///
+    /// `bottom` is usually the end of the program's static data, and `top` the initial stack
+    /// pointer, which is `0x0300_7F00` when using the standard crt0.
+    ///
+    /// ``` no_run
+    /// // SAFETY: The program's static data ends before 0x0300_6000, and nothing else is placed
+    /// // between it and the top of the stack.
+    /// let builder = unsafe { mgba_log::builder().with_stack_painting(0x0300_6000, 0x0300_7F00) };
+    /// builder.init().expect("unable to initialize mGBA logger");
+    /// ```
+    ///
+    /// # Safety
+    /// `bottom` up to `top` must be the stack that the logger is initialized on, and must not be
+    /// used for anything else, including the stacks of other CPU modes.
+    #[must_use]
+    pub const unsafe fn with_stack_painting(mut self, bottom: usize, top: usize) -> Self {
+        self.config.stack = Some((bottom, top));
+        self
+    }
+
+    /// Sets whether each message is prefixed with a sequence number, as in `[#17] Hello, world!`.
+    ///
+    /// Sequence numbers start at `0` and increase by one for every message, in the order messages
+    /// are logged rather than the order they are sent. This allows messages that were discarded
+    /// or reordered, such as those deferred from interrupt handlers, to be detected by whatever
+    /// reads the log. The sequence number is written before any other decoration, but not by a
+    /// formatter set through [`with_formatter()`](Self::with_formatter()). Defaults to `false`.
+    #[must_use]
+    pub const fn with_sequence_numbers(mut self, enabled: bool) -> Self {
+        self.config.sequence_numbers = enabled;
+        self
+    }
+
+    /// Mirrors messages at the [`Warning`](Level::Warning) level and above into a ring buffer in
+    /// the `len` bytes of cartridge SRAM starting at `offset`.
+    ///
+    /// This allows the most recent warnings and errors to be recovered from the save file after a
+    /// crash, even on real hardware. The region starts with a header identifying the log and
+    /// checked by a CRC, and each message is written as a line prefixed with its level, as in
+    /// `[WARN] Hello, world!`. A log left in the region by a previous session is appended to,
+    /// while anything else in the region is cleared on initialization. The
+    /// `mgba_log_reporter` test crate's `decode_sram_log()` recovers the messages from a save
+    /// file.
+    ///
+    /// The region must fit within the 32 KiB of SRAM, must not overlap any save data, and must be
+    /// at least 522 bytes long. Otherwise, initialization returns [`Error::InvalidSramLog`]. The
+    /// cartridge, or the emulator's save type, must use SRAM.
+    ///
+    /// ``` no_run
+    /// mgba_log::builder()
+    ///     .with_sram_log(0x7000, 0x1000)
+    ///     .init()
+    ///     .expect("unable to initialize mGBA logger");
+    /// ```
+    #[must_use]
+    pub const fn with_sram_log(mut self, offset: usize, len: usize) -> Self {
+        self.config.sram_log = SramLog::new(offset, len);
+        self.invalid_sram_log = self.config.sram_log.is_none();
+        self
+    }
+
+    /// Sets the id of the build, which is included in crash dumps.
+    ///
+    /// This allows matching a crash dump with the ELF file of the build that wrote it, such as by
+    /// setting it to part of the commit hash the ROM was built from. Defaults to `0`.
+    ///
+    /// ``` no_run
+    /// mgba_log::builder()
+    ///     .with_build_id(0x1a2b_3c4d)
+    ///     .init()
+    ///     .expect("unable to initialize mGBA logger");
+    /// ```
+    #[cfg(feature = "crash-dump")]
+    #[must_use]
+    pub const fn with_build_id(mut self, build_id: u32) -> Self {
+        self.config.build_id = build_id;
+        self
+    }
+
+    /// Writes crash dumps to cartridge SRAM, starting at `offset`.
+    ///
+    /// Crash dumps are always logged when running in mGBA. Writing them to SRAM as well allows them
+    /// to be recovered from the save file after a crash on real hardware. The `mgba_log_reporter`
+    /// test crate's `decode_crash_dump` binary decodes them from a save file.
+    ///
+    /// A crash dump takes up to 143 bytes, which must fit within the 32 KiB of SRAM and must not
+    /// overlap any save data. Otherwise, initialization returns [`Error::InvalidCrashDumpSram`].
+    ///
+    /// ``` no_run
+    /// mgba_log::builder()
+    ///     .with_crash_dump_sram(0x7f00)
+    ///     .init()
+    ///     .expect("unable to initialize mGBA logger");
+    /// ```
+    #[cfg(feature = "crash-dump")]
+    #[must_use]
+    pub const fn with_crash_dump_sram(mut self, offset: usize) -> Self {
+        self.invalid_crash_dump_sram = offset > sram::SRAM_LEN - crash_dump::MAX_LEN;
+        self.config.crash_dump_sram = Some(offset);
+        self
+    }
+
+    /// Sets the maximum log level.
+    ///
+    /// Records more verbose than `level` will not be logged. As mGBA has no analog for the `Trace`
+    /// log level, `level` is clamped to `LevelFilter::Debug` unless the `trace-as-debug` feature
+    /// is enabled. `level` is also clamped to the maximum level set through the `max_level_*` and
+    /// `release_max_level_*` features. Defaults to the most verbose level supported.
+    #[must_use]
+    pub const fn with_level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Allows records whose target is `target` or a submodule of `target`.
+    ///
+    /// Once any target is allowed, records whose target is not allowed will not be logged. Targets
+    /// can also be allowed after initialization using [`allow_target()`].
+    ///
+    /// At most eight targets can be allowed. Allowing more will cause [`init()`](Self::init()) to
+    /// return [`Error::TooManyTargetFilters`].
+    #[must_use]
+    pub fn allow_target(mut self, target: &'static str) -> Self {
+        self.too_many_target_filters |=
+            !TargetFilters::push(&mut self.config.filters.allow, target);
+        self
+    }
+
+    /// Denies records whose target is `target` or a submodule of `target`.
+    ///
+    /// Denying a target takes precedence over allowing it. Targets can also be denied after
+    /// initialization using [`deny_target()`].
+    ///
+    /// At most eight targets can be denied. Denying more will cause [`init()`](Self::init()) to
+    /// return [`Error::TooManyTargetFilters`].
+    #[must_use]
+    pub fn deny_target(mut self, target: &'static str) -> Self {
+        self.too_many_target_filters |= !TargetFilters::push(&mut self.config.filters.deny, target);
+        self
+    }
+
+    /// Sets the maximum level logged for each target, using directives parsed at compile time by
+    /// [`directives!`].
+    ///
+    /// Records are only logged if both the directives and the targets allowed and denied through
+    /// [`allow_target()`](Self::allow_target()) and [`deny_target()`](Self::deny_target()) allow
+    /// them. Directives can not raise the maximum level set through
+    /// [`with_level()`](Self::with_level()). As the filter string is parsed at compile time, all
+    /// that is left to do when logging is comparing the record's target against the targets named
+    /// by the directives.
+    ///
+    /// ``` no_run
+    /// mgba_log::builder()
+    ///     .with_directives(mgba_log::directives!("warn,game::ai=debug,agb=off"))
+    ///     .init()
+    ///     .expect("unable to initialize mGBA logger");
+    /// ```
+    #[must_use]
+    pub const fn with_directives(mut self, directives: &'static Directives) -> Self {
+        self.config.directives = Some(directives);
+        self
+    }
+
+    /// Initialize mGBA logging using this configuration.
+    ///
+    /// This function takes control of mGBA's [memory mapped debug IO registers](
+    /// https://github.com/mgba-emu/mgba/blob/17a549baf2c8100f2c7e7c244996d9ac85d23198/opt/libgba/mgba.c#L31-L33).
+    /// Any data previously stored in the debug buffer will be completely overwritten by calls to
+    /// the [`log`] macros.
+    ///
+    /// # Errors
+    /// This function returns `Ok(())` if the logger was enabled. If the logger was not enabled for
+    /// any reason, it instead returns an [`Error`]. See the documentation for [`Error`] for what
+    /// errors can occur.
+    pub fn init(self) -> Result<(), Error> {
+        if cfg!(feature = "disabled") {
+            return Ok(());
+        }
+        if self.too_many_target_filters {
+            return Err(Error::TooManyTargetFilters);
+        }
+        if self.invalid_sram_log {
+            return Err(Error::InvalidSramLog);
+        }
+        #[cfg(feature = "crash-dump")]
+        if self.invalid_crash_dump_sram {
+            return Err(Error::InvalidCrashDumpSram);
+        }
+        let marker = self.config.continuation_marker;
+        if marker.len() > MAX_CONTINUATION_MARKER_LEN || marker.contains(['\n', '\0']) {
+            return Err(Error::InvalidContinuationMarker);
+        }
+        if matches!(self.config.null_substitute, '\n' | '\0') {
+            return Err(Error::InvalidNullSubstitute);
+        }
+        let prefix = self.config.line_prefix;
+        if prefix.len() > MAX_LINE_PREFIX_LEN || prefix.contains(['\n', '\0']) {
+            return Err(Error::InvalidLinePrefix);
+        }
+
+        match self.config.backend {
+            Backend::Mgba => {
+                let handshake = mgba_handshake();
+                if handshake != 0x1DEA {
+                    return Err(Error::NotAcknowledgedByMgba { handshake });
+                }
+            }
+            Backend::NoCashGba => {
+                if !nocash::is_nocash() {
+                    return Err(Error::NotAcknowledgedByNoCashGba);
+                }
+            }
+            Backend::AgbPrint => {
+                if !without_interrupts(agbprint::init) {
+                    return Err(Error::NotAcknowledgedByAgbPrint);
+                }
+            }
+            // Nothing acknowledges on the other end of the serial port, so this never fails.
+            Backend::Uart(baud_rate) => without_interrupts(|| uart::init(baud_rate)),
         }
 
-        // `IME` is not reenabled, because writing with `Level::Fatal` will always cause mGBA to
-        // halt execution.
+        // Interrupts are disabled to prevent an interrupt handler from attempting to set a
+        // different logger while `log::set_logger()` is running.
+        without_interrupts(|| {
+            // SAFETY: Interrupts are disabled, therefore this call is safe.
+            unsafe { log::set_logger_racy(&LOGGER) }
+                .map(|()| {
+                    // SAFETY: Interrupts are disabled, so the configuration cannot be accessed
+                    // concurrently.
+                    unsafe {
+                        *LOGGER.config.get() = self.config;
+                    }
+                    // The section entered above is still exited using the previous strategy.
+                    mask::set(self.interrupt_mask);
+                    if let Some(sram_log) = self.config.sram_log {
+                        sram_log.init();
+                    }
+                    if let Some((bottom, top)) = self.config.stack {
+                        // SAFETY: The stack bounds were guaranteed to be valid when they were
+                        // configured, and interrupts are disabled.
+                        unsafe { stack::paint(bottom, top) };
+                    }
+                    #[cfg(feature = "post-mortem")]
+                    post_mortem::start_session();
+                    #[cfg(feature = "timestamps")]
+                    timer::start_timestamps();
+                    // The `TRACE` log level is not used by mGBA, unless `trace-as-debug` is
+                    // enabled, and levels compiled out of the `log` macros are never enabled.
+                    // SAFETY: Interrupts are disabled, therefore this call is safe.
+                    unsafe { log::set_max_level_racy(self.level.min(MAX_LEVEL_FILTER)) };
+                })
+                .map_err(Into::into)
+        })
     }
-}
 
-/// An error occurring during initialization.
-#[derive(Debug)]
-pub enum Error {
-    /// Enabling of logging was not acknowledged by MGBA.
+    /// Initialize mGBA logging using this configuration, falling back to a no-op logger if mGBA is
+    /// not present.
     ///
-    /// This likely indicates that the program is not being run in mGBA at all. In many cases, this
-    /// may be considered a recoverable error. However, if this error is returned by [`init()`],
-    /// then the logger was never actually set, meaning a different logger could potentially be set
-    /// instead.
-    NotAcknowledgedByMgba,
+    /// This behaves the same as [`init()`](Self::init()), except that if mGBA does not
+    /// acknowledge initialization (for example, when running on real hardware or a different
+    /// emulator), a logger that discards all records is installed instead of returning an error.
+    /// This allows calling a single function unconditionally while keeping all calls to the
+    /// [`log`] macros harmless.
+    ///
+    /// Returns `Ok(true)` if mGBA logging was enabled, or `Ok(false)` if the no-op logger was
+    /// installed.
+    ///
+    /// # Errors
+    /// This function never returns [`Error::NotAcknowledgedByMgba`],
+    /// [`Error::NotAcknowledgedByNoCashGba`], or [`Error::NotAcknowledgedByAgbPrint`]. Any other
+    /// [`Error`] is returned in the same cases as [`init()`](Self::init()).
+    pub fn try_init_or_noop(self) -> Result<bool, Error> {
+        match self.init() {
+            Ok(()) => Ok(true),
+            Err(
+                Error::NotAcknowledgedByMgba { .. }
+                | Error::NotAcknowledgedByNoCashGba
+                | Error::NotAcknowledgedByAgbPrint,
+            ) => {
+                without_interrupts(|| {
+                    // SAFETY: Interrupts are disabled, therefore this call is safe.
+                    unsafe { log::set_logger_racy(&NOP_LOGGER) }
+                        .map(|()| {
+                            // Disabling all levels allows the `log` macros to discard records
+                            // without calling the logger at all.
+                            // SAFETY: Interrupts are disabled, therefore this call is safe.
+                            unsafe { log::set_max_level_racy(LevelFilter::Off) };
+                            false
+                        })
+                        .map_err(Into::into)
+                })
+            }
+            Err(error) => Err(error),
+        }
+    }
 
-    /// An error returned by `log::set_logger()`.
+    /// Initialize logging using this configuration, sending messages through whichever supported
+    /// emulator is detected.
     ///
-    /// This most often indicates that another logger has already been set by the program.
-    SetLoggerError(SetLoggerError),
+    /// mGBA and NO$GBA are probed in turn, and the first to acknowledge is used as the [`Backend`],
+    /// replacing any backend set through [`with_backend()`](Self::with_backend()). This allows a
+    /// single program to log correctly across these emulators. AGBPrint is never probed, as doing
+    /// so writes to the cartridge address space, so it must be selected through
+    /// [`with_backend()`](Self::with_backend()).
+    ///
+    /// Returns the detected backend. When the `disabled` feature is enabled, nothing is probed and
+    /// the configured backend is returned.
+    ///
+    /// # Errors
+    /// Returns [`Error::NoEmulatorDetected`] if none of the supported emulators were detected.
+    /// Otherwise, an [`Error`] is returned in the same cases as [`init()`](Self::init()).
+    pub fn init_auto(mut self) -> Result<Backend, Error> {
+        if !cfg!(feature = "disabled") {
+            self.config.backend =
+                without_interrupts(backend::detect).ok_or(Error::NoEmulatorDetected)?;
+        }
+        let backend = self.config.backend;
+        self.init().map(|()| backend)
+    }
+
+    /// Initialize mGBA logging using this configuration for as long as the returned guard is
+    /// alive.
+    ///
+    /// This behaves the same as [`init()`](Self::init()), except that logging is deactivated when
+    /// the returned [`LoggerGuard`] is dropped. See [`LoggerGuard`] for details.
+    ///
+    /// # Errors
+    /// This function returns an [`Error`] in the same cases as [`init()`](Self::init()).
+    pub fn init_scoped(self) -> Result<LoggerGuard, Error> {
+        let previously_enabled = cfg!(feature = "disabled") || mgba_log_enabled();
+        self.init().map(|()| LoggerGuard { previously_enabled })
+    }
 }
 
-impl From<SetLoggerError> for Error {
-    fn from(error: SetLoggerError) -> Self {
-        Self::SetLoggerError(error)
+impl Default for LoggerBuilder {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl Display for Error {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Self::NotAcknowledgedByMgba => fmt.write_str("mGBA did not acknowledge initialization"),
-            Self::SetLoggerError(error) => write!(fmt, "`log::set_logger()` error: {error}"),
+/// Keeps mGBA logging active for as long as it is alive.
+///
+/// This is returned by [`init_scoped()`] and [`LoggerBuilder::init_scoped()`]. When it is dropped,
+/// any records deferred by interrupt handlers are sent, the logger is deactivated, discarding all
+/// records logged afterwards, and mGBA's debug output is disabled again if it was not enabled
+/// before initialization. This is useful for test ROMs and tools that only want logging during a
+/// bounded phase of execution.
+///
+/// As the [`log`] crate does not allow replacing a logger once it is set, logging cannot be
+/// initialized again after the guard is dropped.
+///
+/// ``` no_run
+/// {
+///     let _logger = mgba_log::init_scoped().expect("unable to initialize mGBA logger");
+///     log::info!("This is logged.");
+/// }
+/// log::info!("This is not logged.");
+/// ```
+#[derive(Debug)]
+#[must_use = "logging is deactivated as soon as the guard is dropped"]
+pub struct LoggerGuard {
+    /// Whether mGBA's debug output was enabled before initialization.
+    previously_enabled: bool,
+}
+
+impl Drop for LoggerGuard {
+    fn drop(&mut self) {
+        if cfg!(feature = "disabled") {
+            return;
         }
+        flush();
+        without_interrupts(|| {
+            DEACTIVATED.set(true);
+            // Disabling all levels allows the `log` macros to discard records without calling the
+            // logger at all.
+            // SAFETY: Interrupts are disabled, therefore this call is safe.
+            unsafe { log::set_max_level_racy(LevelFilter::Off) };
+            if !self.previously_enabled {
+                // Any value other than `0xC0DE` disables mGBA's debug output.
+                mgba_log_enable(0);
+            }
+        });
     }
 }
 
-/// A static logger instance.
+/// Returns whether the program is running in mGBA.
 ///
-/// When initializing with [`log::set_logger()`], a static reference to a logger must be provided.
-/// This static logger can be used as the static reference.
-static LOGGER: Logger = Logger;
+/// This performs the same handshake with mGBA's debug registers that is used during
+/// initialization, without initializing the logger. This allows branching on the presence of
+/// mGBA, for example to skip expensive debug-only code paths when running on real hardware.
+///
+/// Note that performing the handshake enables mGBA's debug output. This has no effect beyond
+/// allowing messages to be written to mGBA's log.
+#[must_use]
+pub fn is_mgba() -> bool {
+    mgba_handshake() == 0x1DEA
+}
+
+/// Requests that mGBA's debug output be enabled, returning the value read back from the enable
+/// register.
+///
+/// mGBA acknowledges the request by reading as `0x1DEA`. With the `disabled` feature enabled, no
+/// handshake is performed and `0` is returned.
+fn mgba_handshake() -> u16 {
+    if cfg!(feature = "disabled") {
+        return 0;
+    }
+    mgba_log_enable(0xC0DE);
+    mgba_log_read_enable()
+}
+
+/// Re-emits the messages sent during the previous session, returning how many were re-emitted.
+///
+/// With the `post-mortem` feature enabled, the most recent messages of each session are kept in
+/// the `.noinit` section, which survives a soft reset. After the program crashes and is reset,
+/// calling this once logging has been initialized again sends the messages of the session before
+/// the crash, oldest first, at the levels they were originally sent at. This allows finding out
+/// what was logged before a crash without having been capturing the log at the time.
+///
+/// Only about the last kilobyte of messages is kept. Messages that were originally sent at the
+/// [`Fatal`](Level::Fatal) level are re-emitted at the [`Error`](Level::Error) level, so that
+/// mGBA does not halt execution. Nothing is re-emitted if logging is not initialized, or if no
+/// previous session was found, such as after powering on.
+///
+/// ``` no_run
+/// mgba_log::init().expect("unable to initialize mGBA logger");
+/// mgba_log::recover_previous_session();
+/// ```
+#[cfg(feature = "post-mortem")]
+// The count is informational, so it is fine to ignore it.
+#[allow(clippy::must_use_candidate)]
+pub fn recover_previous_session() -> usize {
+    if cfg!(feature = "disabled") {
+        return 0;
+    }
+    without_interrupts(|| {
+        let backend = writer_config().backend;
+        if !backend.is_listening() {
+            return 0;
+        }
+        let mut count = 0;
+        post_mortem::for_each_previous(|level, message| {
+            let level = match level {
+                Level::Fatal => Level::Error,
+                level => level,
+            };
+            let len = message.len().min(MGBA_LOG_BUFFER_LEN);
+            // SAFETY: Interrupts are disabled, so no other message is being written to the
+            // buffer, and the message is truncated to fit within it.
+            unsafe {
+                copy_to_buffer(backend.buffer(), 0, &message[..len]);
+                if len < MGBA_LOG_BUFFER_LEN {
+                    backend.buffer().add(len).write_volatile(b'\x00');
+                }
+                backend.send(level, len);
+            }
+            count += 1;
+        });
+        count
+    })
+}
+
+/// Returns a [`LoggerBuilder`] for configuring mGBA logging.
+#[must_use]
+pub const fn builder() -> LoggerBuilder {
+    LoggerBuilder::new()
+}
 
 /// Initialize mGBA logging.
 ///
@@ -307,45 +3674,391 @@ static LOGGER: Logger = Logger;
 /// Any data previously stored in the debug buffer will be completely overwritten by calls to the
 /// [`log`] macros.
 ///
+/// The maximum log level is set to the most verbose level supported by mGBA. To log at a less
+/// verbose level, use [`init_with_level()`] instead. For further configuration, use [`builder()`].
+///
 /// # Errors
 /// This function returns `Ok(())` if the logger was enabled. If the logger was not enabled for any
 /// reason, it instead returns an [`Error`]. See the documentation for [`Error`] for what errors
 /// can occur.
 pub fn init() -> Result<(), Error> {
-    // SAFETY: This is guaranteed to be a valid write.
-    unsafe {
-        MGBA_LOG_ENABLE.write(0xC0DE);
+    builder().init()
+}
+
+/// Initialize mGBA logging, falling back to a no-op logger if mGBA is not present.
+///
+/// This behaves the same as [`init()`], except that if mGBA does not acknowledge initialization
+/// (for example, when running on real hardware or a different emulator), a logger that discards
+/// all records is installed instead of returning an error. For further configuration, use
+/// [`LoggerBuilder::try_init_or_noop()`].
+///
+/// Returns `Ok(true)` if mGBA logging was enabled, or `Ok(false)` if the no-op logger was
+/// installed.
+///
+/// # Errors
+/// This function never returns [`Error::NotAcknowledgedByMgba`],
+/// [`Error::NotAcknowledgedByNoCashGba`], or [`Error::NotAcknowledgedByAgbPrint`]. Any other
+/// [`Error`] is returned in the same cases as [`init()`].
+pub fn try_init_or_noop() -> Result<bool, Error> {
+    builder().try_init_or_noop()
+}
+
+/// Initializes logging at the start of a function annotated with [`#[entry]`](entry), logging
+/// `banner` at the info level if mGBA acknowledged initialization.
+///
+/// This is an implementation detail of [`#[entry]`](entry). It is not considered part of the
+/// public API and should not be used directly by external code.
+#[cfg(feature = "entry")]
+#[doc(hidden)]
+pub fn __entry(banner: Option<&str>) {
+    match try_init_or_noop() {
+        Ok(true) => {
+            if let Some(banner) = banner {
+                log::info!("{banner}");
+            }
+        }
+        Ok(false) => {}
+        Err(error) => panic!("unable to initialize mGBA logger: {error}"),
     }
-    // SAFETY: This is guaranteed to be a valid read.
-    if unsafe { MGBA_LOG_ENABLE.read_volatile() } != 0x1DEA {
-        return Err(Error::NotAcknowledgedByMgba);
-    }
-
-    // Disable interrupts, storing the previous value.
-    //
-    // This prevents an interrupt handler from attempting to set a different logger while
-    // `log::set_logger()` is running.
-    //
-    // Compiler fences are used to prevent these function calls from being reordered during
-    // compilation.
-    let previous_ime = unsafe { IME.read_volatile() };
-    // SAFETY: This is guaranteed to be a valid write.
-    unsafe { IME.write_volatile(false) };
-    compiler_fence(atomic::Ordering::Acquire);
-
-    // SAFETY: Interrupts are disabled, therefore this call is safe.
-    let result = unsafe { log::set_logger_racy(&LOGGER) }
-        // The `TRACE` log level is not used by mGBA.
-        // SAFETY: Interrupts are disabled, therefore this call is safe.
-        .map(|()| unsafe { log::set_max_level_racy(LevelFilter::Debug) })
-        .map_err(Into::into);
-
-    compiler_fence(atomic::Ordering::Release);
-    // Restore previous interrupt enable value.
-    // SAFETY: This is guaranteed to be a valid write.
-    unsafe {
-        IME.write_volatile(previous_ime);
+}
+
+/// Initialize logging through whichever supported emulator is detected.
+///
+/// This behaves the same as [`init()`], except that mGBA and NO$GBA are probed in turn, and
+/// messages are sent through the first to acknowledge. The detected [`Backend`] is returned. For
+/// further configuration, use [`LoggerBuilder::init_auto()`].
+///
+/// ``` no_run
+/// let backend = mgba_log::init_auto().expect("no supported emulator detected");
+/// log::info!("Logging through {backend:?}.");
+/// ```
+///
+/// # Errors
+/// This function returns an [`Error`] in the same cases as [`LoggerBuilder::init_auto()`].
+pub fn init_auto() -> Result<Backend, Error> {
+    builder().init_auto()
+}
+
+/// Initialize mGBA logging for as long as the returned guard is alive.
+///
+/// This behaves the same as [`init()`], except that logging is deactivated when the returned
+/// [`LoggerGuard`] is dropped. For further configuration, use [`LoggerBuilder::init_scoped()`].
+///
+/// # Errors
+/// This function returns an [`Error`] in the same cases as [`init()`].
+pub fn init_scoped() -> Result<LoggerGuard, Error> {
+    builder().init_scoped()
+}
+
+/// Initialize mGBA logging with the given maximum log level.
+///
+/// This behaves the same as [`init()`], except that records more verbose than `level` will not be
+/// logged. For example, passing `LevelFilter::Info` will cause `log::debug!` records to be
+/// ignored, which can be useful for release builds.
+///
+/// As mGBA has no analog for the `Trace` log level, `level` is clamped to `LevelFilter::Debug`
+/// unless the `trace-as-debug` feature is enabled. `level` is also clamped to the maximum level
+/// set through the `max_level_*` and `release_max_level_*` features.
+///
+/// # Errors
+/// This function returns `Ok(())` if the logger was enabled. If the logger was not enabled for any
+/// reason, it instead returns an [`Error`]. See the documentation for [`Error`] for what errors
+/// can occur.
+pub fn init_with_level(level: LevelFilter) -> Result<(), Error> {
+    builder().with_level(level).init()
+}
+
+/// Allows records whose target is `target` or a submodule of `target`.
+///
+/// Once any target is allowed, records whose target is not allowed will not be logged. This can be
+/// called at any time, including before initialization.
+///
+/// Returns `false` if the target could not be allowed because eight targets are already allowed.
+#[must_use]
+pub fn allow_target(target: &'static str) -> bool {
+    without_interrupts(|| {
+        // SAFETY: Interrupts are disabled, so the configuration cannot be accessed concurrently.
+        TargetFilters::push(
+            &mut unsafe { &mut *LOGGER.config.get() }.filters.allow,
+            target,
+        )
+    })
+}
+
+/// Denies records whose target is `target` or a submodule of `target`.
+///
+/// Denying a target takes precedence over allowing it. This can be called at any time, including
+/// before initialization.
+///
+/// Returns `false` if the target could not be denied because eight targets are already denied.
+#[must_use]
+pub fn deny_target(target: &'static str) -> bool {
+    without_interrupts(|| {
+        // SAFETY: Interrupts are disabled, so the configuration cannot be accessed concurrently.
+        TargetFilters::push(
+            &mut unsafe { &mut *LOGGER.config.get() }.filters.deny,
+            target,
+        )
+    })
+}
+
+/// Removes all allowed and denied targets, causing records of all targets to be logged.
+pub fn clear_target_filters() {
+    without_interrupts(|| {
+        // SAFETY: Interrupts are disabled, so the configuration cannot be accessed concurrently.
+        unsafe { &mut *LOGGER.config.get() }.filters = TargetFilters::new();
+    });
+}
+
+/// Pauses logging until [`resume()`] is called.
+///
+/// While logging is paused, all records are discarded without being formatted. This is useful for
+/// temporarily silencing logging during time-sensitive sections, such as audio mixing, without
+/// changing the logger's configuration or the maximum log level. Output from [`print!`],
+/// [`MgbaWriter`], and fatal messages logged through macros like [`fatal!`] is not affected.
+///
+/// This can be called at any time, including before initialization and from within interrupt
+/// handlers. Calls do not nest: a single call to [`resume()`] resumes logging regardless of how
+/// many times this was called.
+///
+/// ``` no_run
+/// fn mix_audio() {
+///     mgba_log::pause();
+///     // ...
+///     mgba_log::resume();
+/// }
+/// ```
+pub fn pause() {
+    PAUSED.set(true);
+}
+
+/// Resumes logging after it was paused by [`pause()`].
+///
+/// Records logged while logging was paused are not logged retroactively.
+pub fn resume() {
+    PAUSED.set(false);
+}
+
+/// Returns the number of records that failed to format.
+///
+/// Formatting a record fails when an implementation of a trait like [`Display`] used by its
+/// message returns an error. Such records are not discarded: whatever was formatted before the
+/// error is still logged. Records truncated because they did not fit in a buffer are not counted.
+///
+/// The count saturates at [`u32::MAX`].
+///
+/// ``` no_run
+/// mgba_log::init().expect("unable to initialize mGBA logger");
+///
+/// // ...
+///
+/// if mgba_log::format_errors() > 0 {
+///     mgba_log::eprintln!("some records failed to format");
+/// }
+/// ```
+#[must_use]
+pub fn format_errors() -> u32 {
+    FORMAT_ERRORS.get()
+}
+
+/// Advances the frame count by one.
+///
+/// This should be called exactly once per frame, typically from the program's vblank interrupt
+/// handler. The frame count is used to prefix messages when enabled through
+/// [`LoggerBuilder::with_frame_timestamps()`], allowing logs to be correlated with gameplay.
+///
+/// ``` no_run
+/// extern "C" fn vblank_handler() {
+///     mgba_log::tick_frame();
+/// }
+/// ```
+pub fn tick_frame() {
+    FRAME_COUNT.set(FRAME_COUNT.get().wrapping_add(1));
+}
+
+/// Returns the number of times [`tick_frame()`] has been called.
+///
+/// The count wraps around after 2^32 frames, which takes over two years at 60 frames per second.
+#[must_use]
+pub fn frame_count() -> u32 {
+    FRAME_COUNT.get()
+}
+
+/// Returns statistics about the messages sent to mGBA so far.
+///
+/// For each level, this counts the messages and bytes sent, as well as the messages that were cut
+/// short or discarded. This allows test ROMs and in-game debug overlays to report on the health of
+/// logging, such as whether messages are being lost.
+///
+/// ``` no_run
+/// use mgba_log::Level;
+///
+/// let stats = mgba_log::stats();
+/// if stats.level(Level::Warning).messages > 0 {
+///     // ...
+/// }
+/// let total = stats.total();
+/// mgba_log::println!("{} messages, {} bytes", total.messages, total.bytes);
+/// ```
+#[must_use]
+pub fn stats() -> Stats {
+    stats::get()
+}
+
+/// Logs the current values of the selected groups of I/O registers at the debug level.
+///
+/// Each group is logged as its own record, listing each register's name and value, as in
+/// `DISPCNT=0x0403 DISPSTAT=0x0008 VCOUNT=0x00a0`. This allows capturing the state of the hardware
+/// at the moment something goes wrong, such as when a background is not displayed as expected.
+///
+/// ``` no_run
+/// use mgba_log::IoRegisters;
+///
+/// mgba_log::dump_io_registers(IoRegisters::DISPLAY | IoRegisters::BACKGROUNDS);
+/// ```
+pub fn dump_io_registers(registers: IoRegisters) {
+    io::dump(registers);
+}
+
+/// Logs a message from within an interrupt handler.
+///
+/// Unlike the [`log`] macros, this never writes to mGBA's log registers. Instead, the message is
+/// formatted into a buffer in RAM, and is sent the next time a record is logged outside of an
+/// interrupt handler or [`flush()`] is called. This guarantees that:
+///
+/// - Logging from an interrupt handler can never clobber a record, [`print!`] output, or
+///   [`MgbaWriter`] output that the interrupted code was in the middle of writing, regardless of
+///   how the logger was configured.
+/// - The time spent within the interrupt handler is bounded by formatting the message into RAM.
+/// - Messages are sent in the order they were logged, before any records logged afterwards outside
+///   of interrupt handlers.
+///
+/// Messages more verbose than the maximum log level are discarded, as are all messages if the
+/// logger has not been initialized or logging is [paused](pause()). Target filters do not apply.
+/// Up to 1024 bytes of messages can be waiting to be sent at once; messages beyond that are
+/// truncated or discarded.
+///
+/// This must not be used with the `no-ime` feature enabled.
+///
+/// ``` no_run
+/// use log::Level;
+///
+/// extern "C" fn vblank_handler() {
+///     mgba_log::log_from_irq(Level::Debug, format_args!("vblank"));
+/// }
+/// ```
+pub fn log_from_irq(level: log::Level, args: Arguments) {
+    if cfg!(feature = "disabled")
+        || level > log::STATIC_MAX_LEVEL
+        || level > log::max_level()
+        || silenced()
+    {
+        return;
+    }
+    if let Ok(mgba_level) = Level::try_from(level) {
+        staging::STAGING.stage(mgba_level, |writer| {
+            // SAFETY: Messages are staged while interrupts are disabled, so the configuration
+            // cannot be accessed concurrently.
+            unsafe { &*LOGGER.config.get() }.write_prefix(writer)?;
+            // Trace messages are prefixed in the same way as trace records.
+            #[cfg(feature = "trace-as-debug")]
+            if level == log::Level::Trace {
+                writer.write_str("[TRACE] ")?;
+            }
+            write(writer, args)
+        });
+    }
+}
+
+/// Logs raw bytes as a message.
+///
+/// Unlike the [`log`] macros, this does not require the message to be a `str`, allowing data such
+/// as save data, link cable payloads, or decompressed output to be logged without first validating
+/// it as UTF-8. The bytes are written as-is, with newlines, null characters, and other control
+/// bytes handled as configured through the [`LoggerBuilder`]. Enabling
+/// [`LoggerBuilder::with_escaped_control_bytes()`] is recommended for binary data.
+///
+/// Messages more verbose than the maximum log level are discarded, as are all messages if the
+/// logger has not been initialized or logging is [paused](pause()). Target filters do not apply.
+///
+/// ``` no_run
+/// use log::Level;
+///
+/// mgba_log::builder()
+///     .with_escaped_control_bytes(true)
+///     .init()
+///     .expect("unable to initialize mGBA logger");
+///
+/// let payload = [0x00, 0x01, b'O', b'K', 0xff];
+/// mgba_log::log_bytes(Level::Debug, &payload);
+/// ```
+pub fn log_bytes(level: log::Level, bytes: &[u8]) {
+    if cfg!(feature = "disabled")
+        || level > log::STATIC_MAX_LEVEL
+        || level > log::max_level()
+        || silenced()
+    {
+        return;
     }
+    let Ok(mgba_level) = Level::try_from(level) else {
+        return;
+    };
+    let stage = |writer: &mut staging::StagingWriter| {
+        // SAFETY: Messages are staged while interrupts are disabled, so the configuration cannot
+        // be accessed concurrently.
+        unsafe { &*LOGGER.config.get() }.write_prefix(writer)?;
+        // Trace messages are prefixed in the same way as trace records.
+        #[cfg(feature = "trace-as-debug")]
+        if level == log::Level::Trace {
+            writer.write_bytes(b"[TRACE] ")?;
+        }
+        writer.write_bytes(bytes)
+    };
+    without_interrupts(|| {
+        // SAFETY: Interrupts are disabled, so the configuration cannot be accessed concurrently.
+        let config = unsafe { &*LOGGER.config.get() };
+        if (config.defer_interrupt_logs && in_interrupt()) || !staging::begin() {
+            staging::STAGING.stage(mgba_level, stage);
+            return;
+        }
+        // Any records deferred by interrupt handlers were logged before this one.
+        staging::STAGING.send();
+        {
+            let mut writer = Writer::new(mgba_level, config.writer_config());
+            // Writing to the log buffer cannot fail.
+            let _ = config.write_prefix(&mut writer);
+            #[cfg(feature = "trace-as-debug")]
+            if level == log::Level::Trace {
+                writer.write_text(b"[TRACE] ");
+            }
+            writer.write_text(bytes);
+        }
+        staging::STAGING.finish();
+    });
+}
 
-    result
+/// Sends any records logged by interrupt handlers that are waiting to be sent.
+///
+/// Records are only deferred when logged using [`log_from_irq()`], or when enabled through
+/// [`LoggerBuilder::with_deferred_interrupt_logs()`]. Deferred records are also sent whenever a
+/// record is logged outside of an interrupt handler, so this only needs to be called if the main
+/// program may not log for a while, such as once per iteration of the main loop.
+///
+/// This has no effect when called from within an interrupt handler.
+///
+/// ``` no_run
+/// mgba_log::builder()
+///     .with_deferred_interrupt_logs(true)
+///     .init()
+///     .expect("unable to initialize mGBA logger");
+///
+/// loop {
+///     // ...
+///     mgba_log::flush();
+/// }
+/// ```
+pub fn flush() {
+    if !cfg!(feature = "disabled") && !in_interrupt() && staging::begin() {
+        staging::STAGING.finish();
+    }
 }
@@ -0,0 +1,146 @@
+//! Strategies for preventing interrupts from disturbing logging.
+
+use crate::{RacyCell, IME};
+
+/// The strategy used to prevent interrupts from disturbing logging.
+///
+/// `None` indicates that no strategy has been set, in which case [`InterruptMask::IME`] is used.
+static INTERRUPT_MASK: RacyCell<Option<&'static InterruptMask>> = RacyCell::new(None);
+
+/// Returns the strategy currently used to prevent interrupts from disturbing logging.
+///
+/// The strategy is unused if the `critical-section` or `no-ime` features are enabled.
+#[cfg_attr(
+    any(feature = "critical-section", feature = "no-ime"),
+    allow(dead_code)
+)]
+pub(crate) fn current() -> &'static InterruptMask {
+    INTERRUPT_MASK.get().unwrap_or(&InterruptMask::IME)
+}
+
+/// Sets the strategy used to prevent interrupts from disturbing logging.
+///
+/// Sections that were entered using the previous strategy are still exited using that strategy.
+pub(crate) fn set(mask: &'static InterruptMask) {
+    INTERRUPT_MASK.set(Some(mask));
+}
+
+/// A strategy for preventing interrupts from disturbing logging.
+///
+/// While records are being written and while the logger's state is being accessed, the logger
+/// enters a section in which interrupt handlers that log must not run. By default, this is done
+/// by disabling all interrupts through the `IME` register, as in [`InterruptMask::IME`]. A
+/// different strategy can be set using [`LoggerBuilder::with_interrupt_mask()`], such as masking
+/// only the interrupts whose handlers log, or not masking interrupts at all if no interrupt
+/// handlers log.
+///
+/// The strategy is ignored if the `critical-section` or `no-ime` features are enabled.
+///
+/// [`LoggerBuilder::with_interrupt_mask()`]: crate::LoggerBuilder::with_interrupt_mask()
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    any(feature = "critical-section", feature = "no-ime"),
+    allow(dead_code)
+)]
+pub struct InterruptMask {
+    /// Enters the section, returning the state to be restored when it is exited.
+    acquire: fn() -> u16,
+    /// Exits the section, given the state returned by `acquire`.
+    release: fn(u16),
+}
+
+impl InterruptMask {
+    /// Disables all interrupts using the `IME` register.
+    ///
+    /// This is the default strategy. If interrupts are already disabled, `IME` is left untouched.
+    pub const IME: Self = Self::new(ime_acquire, ime_release);
+
+    /// Does not mask interrupts at all.
+    ///
+    /// This is only correct if no interrupt handler ever logs, prints, or changes the logger's
+    /// configuration.
+    pub const NONE: Self = Self::new(none_acquire, none_release);
+
+    /// Creates a custom strategy.
+    ///
+    /// `acquire` is called when entering a section in which interrupt handlers that log must not
+    /// run, and returns a state that is passed to `release` when the section is exited. Sections
+    /// may be nested, and are always exited in the reverse order in which they were entered.
+    ///
+    /// The following strategy masks only the vblank interrupt, by clearing its bit in the `IE`
+    /// register:
+    ///
+    /// ``` no_run
+    /// use mgba_log::InterruptMask;
+    ///
+    /// /// Interrupt Enable.
+    /// const IE: *mut u16 = 0x0400_0200 as *mut u16;
+    /// const VBLANK: u16 = 0x0001;
+    ///
+    /// fn acquire() -> u16 {
+    ///     let previous = unsafe { IE.read_volatile() };
+    ///     unsafe { IE.write_volatile(previous & !VBLANK) };
+    ///     previous
+    /// }
+    ///
+    /// fn release(previous: u16) {
+    ///     unsafe { IE.write_volatile(previous) };
+    /// }
+    ///
+    /// static VBLANK_MASK: InterruptMask = InterruptMask::new(acquire, release);
+    ///
+    /// mgba_log::builder()
+    ///     .with_interrupt_mask(&VBLANK_MASK)
+    ///     .init()
+    ///     .expect("unable to initialize mGBA logger");
+    /// ```
+    #[must_use]
+    pub const fn new(acquire: fn() -> u16, release: fn(u16)) -> Self {
+        Self { acquire, release }
+    }
+
+    /// Enters a section in which interrupt handlers that log must not run.
+    #[cfg_attr(
+        any(feature = "critical-section", feature = "no-ime"),
+        allow(dead_code)
+    )]
+    pub(crate) fn acquire(&self) -> u16 {
+        (self.acquire)()
+    }
+
+    /// Returns the function exiting a section entered through [`acquire()`](Self::acquire()).
+    #[cfg_attr(
+        any(feature = "critical-section", feature = "no-ime"),
+        allow(dead_code)
+    )]
+    pub(crate) fn release(&self) -> fn(u16) {
+        self.release
+    }
+}
+
+/// Disables interrupts, returning whether they were previously enabled.
+fn ime_acquire() -> u16 {
+    // SAFETY: This is guaranteed to be a valid read.
+    let previous_ime = unsafe { IME.read_volatile() };
+    if previous_ime {
+        // SAFETY: This is guaranteed to be a valid write.
+        unsafe { IME.write_volatile(false) };
+    }
+    u16::from(previous_ime)
+}
+
+/// Reenables interrupts if they were previously enabled.
+fn ime_release(previous_ime: u16) {
+    if previous_ime != 0 {
+        // SAFETY: This is guaranteed to be a valid write.
+        unsafe { IME.write_volatile(true) };
+    }
+}
+
+/// Does nothing.
+fn none_acquire() -> u16 {
+    0
+}
+
+/// Does nothing.
+fn none_release(_: u16) {}
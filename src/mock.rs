@@ -0,0 +1,140 @@
+//! An in-memory replacement for mGBA's debug registers.
+//!
+//! With the `mock` feature enabled, messages sent to mGBA are captured in memory instead of being
+//! written to mGBA's debug registers. This allows testing logging code on the host, including
+//! under Miri, without booting an emulator.
+//!
+//! Only mGBA's debug registers are replaced. The other backends, the SRAM log, the `timestamps`
+//! and `post-mortem` features, and everything else accessing hardware still access their
+//! addresses directly, so they must not be used with this feature. Interrupts are never disabled,
+//! as the `mock` feature enables the `no-ime` feature.
+//!
+//! As with mGBA, messages are only captured once debug output has been enabled, either by
+//! initializing the logger or by calling [`is_mgba()`](crate::is_mgba()), which always succeeds.
+//! Logging at [`Level::Fatal`] panics rather than halting execution.
+//!
+//! ```
+//! use core::fmt::Write;
+//! use mgba_log::{mock, Level, MgbaWriter};
+//!
+//! let messages = mock::capture(|| {
+//!     assert!(mgba_log::is_mgba());
+//!     write!(MgbaWriter::new(Level::Info), "foo\nbar").unwrap();
+//! });
+//!
+//! assert_eq!(
+//!     messages,
+//!     [
+//!         mock::Message::new(Level::Info, "foo"),
+//!         mock::Message::new(Level::Info, "bar"),
+//!     ]
+//! );
+//! ```
+
+extern crate std;
+
+use crate::{Level, MGBA_LOG_BUFFER_LEN};
+use core::cell::UnsafeCell;
+use std::{
+    string::String,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, PoisonError,
+    },
+    vec::Vec,
+};
+
+/// Stands in for mGBA's log buffer.
+static BUFFER: Buffer = Buffer(UnsafeCell::new([0; MGBA_LOG_BUFFER_LEN]));
+/// Whether debug output is enabled.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// The messages sent since they were last taken.
+static MESSAGES: Mutex<Vec<Message>> = Mutex::new(Vec::new());
+/// Serializes calls to [`capture()`].
+static CAPTURE: Mutex<()> = Mutex::new(());
+
+/// A buffer of the same size as mGBA's log buffer.
+struct Buffer(UnsafeCell<[u8; MGBA_LOG_BUFFER_LEN]>);
+
+// SAFETY: The buffer is only accessed by the writer currently writing a message. Logging is only
+// done from a single thread at a time, as documented on `capture()`.
+unsafe impl Sync for Buffer {}
+
+/// A message sent to mGBA.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Message {
+    /// The level the message was sent at.
+    pub level: Level,
+    /// The text of the message.
+    ///
+    /// Invalid UTF-8 is replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    pub text: String,
+}
+
+impl Message {
+    /// Creates a message with the given `level` and `text`.
+    #[must_use]
+    pub fn new(level: Level, text: &str) -> Self {
+        Self {
+            level,
+            text: text.into(),
+        }
+    }
+}
+
+/// Returns the buffer standing in for mGBA's log buffer.
+pub(crate) fn buffer() -> *mut u8 {
+    BUFFER.0.get().cast()
+}
+
+/// Captures the contents of the buffer as a message at the given `level`.
+///
+/// As with mGBA, the message ends at the first null byte, or at the end of the buffer. Nothing is
+/// captured while debug output is disabled.
+pub(crate) fn send(level: Level) {
+    if !is_enabled() {
+        return;
+    }
+    // SAFETY: The buffer is not written to while a message is being sent.
+    let buffer = unsafe { &*BUFFER.0.get() };
+    let len = buffer
+        .iter()
+        .position(|&byte| byte == b'\x00')
+        .unwrap_or(MGBA_LOG_BUFFER_LEN);
+    let text = String::from_utf8_lossy(&buffer[..len]).into_owned();
+    MESSAGES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Message { level, text });
+}
+
+/// Enables debug output if `value` is `0xC0DE`, and disables it otherwise.
+pub(crate) fn enable(value: u16) {
+    ENABLED.store(value == 0xC0DE, Ordering::Relaxed);
+}
+
+/// Returns whether debug output is enabled.
+pub(crate) fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Returns the messages sent since they were last taken, in the order they were sent.
+#[must_use]
+pub fn take_messages() -> Vec<Message> {
+    core::mem::take(&mut *MESSAGES.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// Calls `f`, returning the messages it sent.
+///
+/// Calls to this function never overlap, even across threads, which allows tests using it to be
+/// run in parallel. Logging outside of `f` while another thread is within this function is not
+/// allowed, as the log buffer is shared.
+pub fn capture<F>(f: F) -> Vec<Message>
+where
+    F: FnOnce(),
+{
+    let _guard = CAPTURE.lock().unwrap_or_else(PoisonError::into_inner);
+    drop(take_messages());
+    f();
+    take_messages()
+}
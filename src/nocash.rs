@@ -0,0 +1,29 @@
+//! Output through NO$GBA's debug registers.
+//!
+//! NO$GBA identifies itself through a string readable from its emulation ID register, and prints
+//! each character written to its character output register to its debug message window.
+
+use crate::{backend::level_prefix, Level};
+
+/// The emulation ID register, which reads as a string identifying the emulator.
+const EMULATION_ID: *const u8 = 0x04FF_FA00 as *const u8;
+/// The start of the string read from [`EMULATION_ID`] when running in NO$GBA.
+const NOCASH_ID: &[u8] = b"no$gba";
+/// The character output register.
+const CHAR_OUT: *mut u8 = 0x04FF_FA1C as *mut u8;
+
+/// Returns whether the program is running in NO$GBA.
+pub(crate) fn is_nocash() -> bool {
+    NOCASH_ID.iter().enumerate().all(|(offset, &byte)| {
+        // SAFETY: This is guaranteed to be a valid read.
+        unsafe { EMULATION_ID.add(offset).read_volatile() == byte }
+    })
+}
+
+/// Prints `text` at the given `level`, followed by a newline.
+pub(crate) fn print(level: Level, text: &[u8]) {
+    for &byte in level_prefix(level).iter().chain(text).chain(b"\n") {
+        // SAFETY: This is guaranteed to be a valid write.
+        unsafe { CHAR_OUT.write_volatile(byte) };
+    }
+}
@@ -0,0 +1,132 @@
+//! A panic handler logging the CPU's registers and the top of the stack before halting.
+//!
+//! This is enabled by the `panic-handler` feature.
+
+use crate::{__fatal_halt, is_mgba};
+#[cfg(not(feature = "crash-dump"))]
+use crate::{fmt::hex_u32, stack_region_end, Level, MgbaWriter};
+#[cfg(not(feature = "crash-dump"))]
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+
+/// The number of words at the top of the stack that are logged.
+#[cfg(not(feature = "crash-dump"))]
+const STACK_WORDS: usize = 16;
+/// The number of values logged on each line.
+#[cfg(not(feature = "crash-dump"))]
+const VALUES_PER_LINE: usize = 4;
+/// The names of the registers, in the order they are captured.
+#[cfg(not(feature = "crash-dump"))]
+const REGISTER_NAMES: [&str; 17] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "sp", "lr",
+    "pc", "cpsr",
+];
+
+/// Captures `r0` through `r15`, followed by the CPSR.
+///
+/// The registers are captured upon entering this function from the panic handler, so the program
+/// counter points within it, and `r0` through `r3` hold scratch values. The stack pointer, the
+/// link register, and the callee-saved registers still reflect the panicking code closely, and the
+/// stack holds the return addresses of the calls that led to the panic.
+#[cfg(target_arch = "arm")]
+#[instruction_set(arm::a32)]
+#[inline(never)]
+fn capture_registers() -> [u32; 17] {
+    let mut registers = [0; 17];
+    // SAFETY: `registers` has room for every register stored, and storing them has no other
+    // effects.
+    unsafe {
+        core::arch::asm!(
+            "stmia {registers}, {{r0-r14}}",
+            // Storing the program counter through `stmia` is deprecated, so it is moved first.
+            "mov {scratch}, pc",
+            "str {scratch}, [{registers}, #60]",
+            "mrs {scratch}, cpsr",
+            "str {scratch}, [{registers}, #64]",
+            registers = in(reg) registers.as_mut_ptr(),
+            scratch = out(reg) _,
+            options(nostack, preserves_flags),
+        );
+    }
+    registers
+}
+
+/// Captures `r0` through `r15`, followed by the CPSR.
+///
+/// Registers can only be captured on the Game Boy Advance's ARM CPU, so these are all zero.
+#[cfg(not(target_arch = "arm"))]
+fn capture_registers() -> [u32; 17] {
+    [0; 17]
+}
+
+/// Writes each register's name and value, four to a line.
+#[cfg(not(feature = "crash-dump"))]
+fn write_registers<W>(writer: &mut W, registers: &[u32; 17]) -> fmt::Result
+where
+    W: Write,
+{
+    for (index, (name, value)) in REGISTER_NAMES.iter().zip(registers).enumerate() {
+        let separator = match index % VALUES_PER_LINE {
+            0 if index == 0 => "",
+            0 => "\n",
+            _ => " ",
+        };
+        write!(writer, "{separator}{name}={}", hex_u32(*value))?;
+    }
+    Ok(())
+}
+
+/// Writes the words at the top of the stack, starting at `sp`, four to a line.
+///
+/// Each line is prefixed with the address of its first word. Nothing is written if `sp` is not
+/// within a memory region the stack may be located in, and words past the end of that region are
+/// not read.
+#[cfg(not(feature = "crash-dump"))]
+fn write_stack<W>(writer: &mut W, sp: usize) -> fmt::Result
+where
+    W: Write,
+{
+    let Some(end) = stack_region_end(sp) else {
+        return Ok(());
+    };
+    let stack = sp as *const u32;
+    for index in 0..((end - sp) / 4).min(STACK_WORDS) {
+        // SAFETY: The address is within the memory region containing the stack, and is word
+        // aligned as the stack pointer always is.
+        let address = unsafe { stack.add(index) };
+        if index % VALUES_PER_LINE == 0 {
+            // Addresses on the Game Boy Advance always fit in 32 bits.
+            #[allow(clippy::cast_possible_truncation)]
+            let line_address = address as u32;
+            write!(writer, "\n{}:", hex_u32(line_address))?;
+        }
+        // SAFETY: Same as above.
+        write!(writer, " {}", hex_u32(unsafe { address.read_volatile() }))?;
+    }
+    Ok(())
+}
+
+/// Logs the CPU's registers and the top of the stack at the error level, followed by the panic
+/// message at the fatal level.
+///
+/// When the `test-runner` feature is enabled and a test is running, the failure of the test is
+/// logged first.
+///
+/// When the `crash-dump` feature is enabled, the registers and the stack are written as a crash
+/// dump instead.
+#[panic_handler]
+fn panic_handler(info: &PanicInfo) -> ! {
+    let registers = capture_registers();
+    #[cfg(feature = "test-runner")]
+    crate::test_runner::fail();
+    #[cfg(feature = "crash-dump")]
+    crate::crash_dump::write(&registers, is_mgba());
+    #[cfg(not(feature = "crash-dump"))]
+    if is_mgba() {
+        let mut writer = MgbaWriter::new(Level::Error);
+        // Write failures can not be reported from within the panic handler, so they are ignored.
+        let _ = write_registers(&mut writer, &registers);
+        let _ = write_stack(&mut writer, registers[13] as usize);
+    }
+    __fatal_halt(format_args!("{info}"))
+}
@@ -0,0 +1,207 @@
+//! Recent messages kept in EWRAM across soft resets.
+//!
+//! See [`recover_previous_session()`](crate::recover_previous_session()) for details.
+//!
+//! Two ring buffers are kept, one for the current session and one for the previous session. Each
+//! time logging is initialized, the ring buffer of the current session becomes that of the
+//! previous session, and the other ring buffer is cleared. Messages are written to a ring buffer
+//! in the same format as in SRAM, as lines prefixed with their level and followed by a null byte.
+
+use crate::{backend::level_prefix, Level, RacyCell, MGBA_LOG_BUFFER_LEN};
+use core::{cell::UnsafeCell, ptr::addr_of_mut};
+
+/// The value identifying [`Sessions`] left by a previous session.
+const MAGIC: u32 = u32::from_le_bytes(*b"MLOG");
+/// The length of each ring buffer.
+const RING_LEN: usize = 1024;
+/// The longest line written to a ring buffer, including its level prefix.
+const MAX_LINE_LEN: usize = MGBA_LOG_BUFFER_LEN + 8;
+
+/// The messages of the current and previous sessions.
+#[repr(C)]
+struct Sessions {
+    /// Equal to [`MAGIC`] once a session has been started.
+    magic: u32,
+    /// The index of the ring buffer of the current session.
+    current: u32,
+    /// Whether the other ring buffer holds the messages of a previous session.
+    has_previous: u32,
+    /// The offset within each ring buffer to write to next.
+    heads: [u32; 2],
+    /// The ring buffers.
+    rings: [[u8; RING_LEN]; 2],
+}
+
+/// Sessions that can be shared with interrupt handlers.
+struct SessionsCell(UnsafeCell<Sessions>);
+
+// SAFETY: The sessions are only accessed by the writer currently sending a message, or while
+// interrupts are disabled. As the Game Boy Advance has a single core, this means they are never
+// accessed concurrently.
+unsafe impl Sync for SessionsCell {}
+
+/// The messages of the current and previous sessions.
+///
+/// The initial value is never loaded, as the `.noinit` section is not initialized by the runtime.
+/// Its actual contents are whatever was left by the previous session, or arbitrary values after a
+/// power cycle, so every access is volatile and every value is checked before being used.
+#[link_section = ".noinit"]
+static SESSIONS: SessionsCell = SessionsCell(UnsafeCell::new(Sessions {
+    magic: 0,
+    current: 0,
+    has_previous: 0,
+    heads: [0; 2],
+    rings: [[0; RING_LEN]; 2],
+}));
+
+/// Whether a session has been started since the program was last reset.
+static STARTED: RacyCell<bool> = RacyCell::new(false);
+
+/// Returns a pointer to the sessions.
+fn sessions() -> *mut Sessions {
+    SESSIONS.0.get()
+}
+
+/// Returns the index of the current ring buffer and whether the other holds a previous session,
+/// or `None` if no valid sessions are stored.
+fn state() -> Option<(usize, bool)> {
+    let sessions = sessions();
+    // SAFETY: These are all valid reads within the sessions.
+    unsafe {
+        let current = addr_of_mut!((*sessions).current).read_volatile();
+        let valid = addr_of_mut!((*sessions).magic).read_volatile() == MAGIC
+            && current < 2
+            && (0..2).all(|index| {
+                (addr_of_mut!((*sessions).heads[index]).read_volatile() as usize) < RING_LEN
+            });
+        valid.then(|| {
+            (
+                current as usize,
+                addr_of_mut!((*sessions).has_previous).read_volatile() != 0,
+            )
+        })
+    }
+}
+
+/// Returns a pointer to the ring buffer at `index`, along with a pointer to its head.
+fn ring(index: usize) -> (*mut u8, *mut u32) {
+    let sessions = sessions();
+    // SAFETY: `index` is always either `0` or `1`.
+    unsafe {
+        (
+            addr_of_mut!((*sessions).rings[index]).cast(),
+            addr_of_mut!((*sessions).heads[index]),
+        )
+    }
+}
+
+/// Starts a new session, keeping the messages of the current session as the previous session.
+///
+/// This must be called while interrupts are disabled.
+pub(crate) fn start_session() {
+    let (current, has_previous) = match state() {
+        Some((current, _)) => (1 - current, true),
+        None => (0, false),
+    };
+    let (_, previous_head) = ring(1 - current);
+    let (ring, head) = ring(current);
+    let sessions = sessions();
+    // SAFETY: These are all valid writes within the sessions.
+    unsafe {
+        for offset in 0..RING_LEN {
+            ring.add(offset).write_volatile(0);
+        }
+        head.write_volatile(0);
+        // Both heads must be valid before the sessions are marked as valid.
+        if !has_previous {
+            previous_head.write_volatile(0);
+        }
+        // `current` is always either `0` or `1`, so it always fits in a `u32`.
+        #[allow(clippy::cast_possible_truncation)]
+        addr_of_mut!((*sessions).current).write_volatile(current as u32);
+        addr_of_mut!((*sessions).has_previous).write_volatile(u32::from(has_previous));
+        addr_of_mut!((*sessions).magic).write_volatile(MAGIC);
+    }
+    STARTED.set(true);
+}
+
+/// Appends `message` at the given `level` to the current session.
+///
+/// Nothing is written until a session has been started.
+pub(crate) fn append(level: Level, message: &[u8]) {
+    if !STARTED.get() {
+        return;
+    }
+    let Some((current, _)) = state() else {
+        return;
+    };
+    let (ring, head) = ring(current);
+    // SAFETY: The head is checked to be within the ring buffer, and every offset written to is
+    // wrapped around to stay within it.
+    unsafe {
+        let mut offset = head.read_volatile() as usize;
+        for &byte in level_prefix(level).iter().chain(message).chain(b"\n") {
+            ring.add(offset).write_volatile(byte);
+            offset = (offset + 1) % RING_LEN;
+        }
+        ring.add(offset).write_volatile(0);
+        // `offset` is always less than `RING_LEN`, so it always fits in a `u32`.
+        #[allow(clippy::cast_possible_truncation)]
+        head.write_volatile(offset as u32);
+    }
+}
+
+/// Calls `f` with the level and text of each message of the previous session, oldest first.
+///
+/// Once the previous session's ring buffer wrapped around, its oldest message was partially
+/// overwritten, so it is skipped.
+pub(crate) fn for_each_previous<F>(mut f: F)
+where
+    F: FnMut(Level, &[u8]),
+{
+    let Some((current, true)) = state() else {
+        return;
+    };
+    let (ring, head) = ring(1 - current);
+    let mut line = [0; MAX_LINE_LEN];
+    let mut len = 0;
+    // Whether the start of the next line is known. Null bytes are only found in the part of the
+    // ring buffer that was never written to, which precedes the oldest message. Otherwise, the
+    // oldest message is skipped up to the end of its line.
+    let mut aligned = false;
+    // SAFETY: The head is checked to be within the ring buffer, and every offset read from is
+    // wrapped around to stay within it.
+    unsafe {
+        let head = head.read_volatile() as usize;
+        for offset in (head + 1..RING_LEN).chain(0..head) {
+            let byte = ring.add(offset).read_volatile();
+            if byte == 0 {
+                aligned = true;
+                continue;
+            }
+            if !aligned {
+                aligned = byte == b'\n';
+                continue;
+            }
+            if byte == b'\n' {
+                let line = &line[..len];
+                if let Some(level) = [
+                    Level::Fatal,
+                    Level::Error,
+                    Level::Warning,
+                    Level::Info,
+                    Level::Debug,
+                ]
+                .into_iter()
+                .find(|&level| line.starts_with(level_prefix(level)))
+                {
+                    f(level, &line[level_prefix(level).len()..]);
+                }
+                len = 0;
+            } else if len < line.len() {
+                line[len] = byte;
+                len += 1;
+            }
+        }
+    }
+}
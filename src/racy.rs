@@ -0,0 +1,211 @@
+//! Values shared with interrupt handlers without using atomic types.
+//!
+//! The Game Boy Advance's ARM7TDMI has no atomic instructions beyond `swp`, so the compiler lowers
+//! operations on types like [`AtomicBool`](core::sync::atomic::AtomicBool) to `__sync_*` library
+//! calls, which are not provided on the Game Boy Advance. As the Game Boy Advance has a single
+//! core, a single load or store of a value no larger than a word can never be observed half
+//! completed, so such values are instead accessed using plain volatile loads and stores.
+//!
+//! On other targets, such as the host running tests with the `mock` feature, values may be
+//! accessed from multiple threads at once, so each access is additionally serialized by a lock.
+
+use crate::{without_interrupts, InterruptMask};
+use core::cell::UnsafeCell;
+#[cfg(not(all(target_arch = "arm", target_os = "none")))]
+use core::{
+    hint,
+    sync::{atomic, atomic::Ordering},
+};
+
+/// A type that can be loaded and stored using a single access.
+///
+/// This is only implemented for types no larger than a word. It cannot be implemented outside of
+/// this crate.
+pub trait Word: Copy + Send {}
+
+impl Word for bool {}
+impl Word for u8 {}
+impl Word for u16 {}
+impl Word for u32 {}
+impl Word for Option<&'static InterruptMask> {}
+
+/// A value that can be read and written by both the main program and interrupt handlers.
+///
+/// Every read and write is a single volatile access, which an interrupt cannot split. Sequences of
+/// accesses, such as read-modify-write operations, must be performed while interrupts are disabled
+/// if an interrupt handler may access the same value in between.
+///
+/// This is an implementation detail of the [`log_once!`](crate::log_once!) and
+/// [`log_every_n!`](crate::log_every_n!) macros. It is not considered part of the public API and
+/// should not be used directly by external code.
+#[derive(Debug)]
+pub struct RacyCell<T> {
+    /// The contained value.
+    value: UnsafeCell<T>,
+    /// Serializes accesses between threads.
+    lock: Lock,
+}
+
+impl<T> RacyCell<T>
+where
+    T: Word,
+{
+    /// Creates a new cell containing `value`.
+    #[must_use]
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            lock: Lock::new(),
+        }
+    }
+
+    /// Returns the contained value.
+    #[must_use]
+    pub fn get(&self) -> T {
+        // SAFETY: The pointer is valid, and the access is serialized by the lock.
+        self.lock
+            .with(|| unsafe { self.value.get().read_volatile() })
+    }
+
+    /// Replaces the contained value.
+    pub fn set(&self, value: T) {
+        // SAFETY: The pointer is valid, and the access is serialized by the lock.
+        self.lock
+            .with(|| unsafe { self.value.get().write_volatile(value) });
+    }
+}
+
+// SAFETY: The value is only ever accessed using single volatile loads and stores of a `Word`,
+// serialized by the lock. On the Game Boy Advance, which has a single core, these can never
+// overlap with each other. On other targets, the lock prevents them from overlapping.
+unsafe impl<T> Sync for RacyCell<T> where T: Word {}
+
+/// Serializes accesses to a cell between threads.
+///
+/// The Game Boy Advance has a single core, so this does nothing there.
+#[cfg(all(target_arch = "arm", target_os = "none"))]
+#[derive(Debug)]
+struct Lock;
+
+#[cfg(all(target_arch = "arm", target_os = "none"))]
+impl Lock {
+    /// Creates a new lock.
+    const fn new() -> Self {
+        Self
+    }
+
+    /// Runs `f`.
+    fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        f()
+    }
+}
+
+/// Serializes accesses to a cell between threads.
+///
+/// This is a spin lock, as accesses only ever hold it for a few instructions.
+#[cfg(not(all(target_arch = "arm", target_os = "none")))]
+#[derive(Debug)]
+struct Lock(atomic::AtomicBool);
+
+#[cfg(not(all(target_arch = "arm", target_os = "none")))]
+impl Lock {
+    /// Creates a new lock.
+    const fn new() -> Self {
+        Self(atomic::AtomicBool::new(false))
+    }
+
+    /// Runs `f` while holding the lock.
+    ///
+    /// `f` must not access the same cell, or it will never acquire the lock.
+    fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        /// Releases the lock when dropped, including when `f` panics.
+        struct Release<'a>(&'a atomic::AtomicBool);
+
+        impl Drop for Release<'_> {
+            fn drop(&mut self) {
+                self.0.store(false, Ordering::Release);
+            }
+        }
+
+        while self
+            .0
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            hint::spin_loop();
+        }
+        let _release = Release(&self.0);
+        f()
+    }
+}
+
+/// A value that is shared with interrupt handlers, and is only accessed while interrupts are
+/// disabled.
+///
+/// Unlike [`RacyCell`], this can hold values of any size, and can be updated in place.
+#[derive(Debug)]
+pub(crate) struct GuardedCell<T> {
+    /// The contained value.
+    value: UnsafeCell<T>,
+    /// Serializes accesses between threads.
+    lock: Lock,
+}
+
+impl<T> GuardedCell<T>
+where
+    T: Copy,
+{
+    /// Creates a new cell containing `value`.
+    pub(crate) const fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            lock: Lock::new(),
+        }
+    }
+
+    /// Returns a copy of the contained value.
+    pub(crate) fn get(&self) -> T {
+        self.update(|value| *value)
+    }
+
+    /// Replaces the contained value.
+    #[cfg_attr(not(feature = "test-runner"), allow(dead_code))]
+    pub(crate) fn set(&self, value: T) {
+        self.update(|contained| *contained = value);
+    }
+
+    /// Calls `f` on the contained value, returning its result.
+    ///
+    /// `f` is given a copy of the value, which is written back once it returns. `f` must not access
+    /// the same cell, as its changes would be overwritten, and on targets other than the Game Boy
+    /// Advance it would never acquire the lock.
+    pub(crate) fn update<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        without_interrupts(|| {
+            self.lock.with(|| {
+                // SAFETY: The pointer is valid, and the access is serialized as described below.
+                let mut value = unsafe { *self.value.get() };
+                let result = f(&mut value);
+                // SAFETY: As above.
+                unsafe { *self.value.get() = value };
+                result
+            })
+        })
+    }
+}
+
+// SAFETY: The value is only ever accessed while interrupts are disabled, and while holding the
+// lock, and no reference to it outlives an access. On the Game Boy Advance, which has a single
+// core, nothing else can run while interrupts are disabled, so accesses can never overlap with
+// each other. With the `no-ime` feature, interrupts are left enabled, but the program guarantees
+// that its interrupt handlers do not log. On other targets, the lock prevents accesses from
+// overlapping.
+unsafe impl<T> Sync for GuardedCell<T> where T: Copy + Send {}
@@ -0,0 +1,119 @@
+//! Indentation of messages logged within nested scopes.
+//!
+//! This is the implementation of the [`scope!`](crate::scope!) macro and of the `#[instrument]`
+//! attribute.
+
+use crate::RacyCell;
+use core::fmt::{self, Write};
+
+/// The number of scopes currently entered.
+///
+/// An interrupt handler entering a scope always exits it before returning, so this is never left
+/// changed by an interrupt, even though it is updated without disabling interrupts.
+static DEPTH: RacyCell<u8> = RacyCell::new(0);
+
+/// The indentation written for each entered scope.
+const INDENT: &str = "  ";
+/// The deepest nesting of scopes that is indented.
+///
+/// Messages in scopes nested more deeply are indented the same as at this depth, so that the
+/// indentation does not take up a large part of the log buffer.
+const MAX_INDENTED_DEPTH: u8 = 16;
+
+/// Writes the indentation of messages logged within the currently entered scopes.
+pub(crate) fn write_indentation<W>(writer: &mut W) -> fmt::Result
+where
+    W: Write,
+{
+    for _ in 0..DEPTH.get().min(MAX_INDENTED_DEPTH) {
+        writer.write_str(INDENT)?;
+    }
+    Ok(())
+}
+
+/// Logs entering a scope, and exiting it when dropped.
+///
+/// This is an implementation detail of the [`scope!`](crate::scope!) macro. It is not considered
+/// part of the public API and should not be used directly by external code.
+#[doc(hidden)]
+pub struct __Scope {
+    /// The target entering and exiting the scope are logged to.
+    target: &'static str,
+    /// The name of the scope.
+    name: &'static str,
+}
+
+impl __Scope {
+    /// Enters a scope with the given `name`.
+    #[must_use]
+    pub fn new(target: &'static str, name: &'static str) -> Self {
+        log::debug!(target: target, "enter {name}");
+        DEPTH.set(DEPTH.get().saturating_add(1));
+        Self { target, name }
+    }
+}
+
+impl Drop for __Scope {
+    fn drop(&mut self) {
+        DEPTH.set(DEPTH.get().saturating_sub(1));
+        log::debug!(target: self.target, "exit {}", self.name);
+    }
+}
+
+/// Logs entering a function, along with the values of some of its arguments, and exiting it when
+/// dropped.
+///
+/// This is an implementation detail of the [`#[instrument]`](crate::instrument) attribute. It is
+/// not considered part of the public API and should not be used directly by external code.
+#[cfg(feature = "instrument")]
+#[doc(hidden)]
+pub struct __Instrument {
+    /// The target entering and exiting the function are logged to.
+    target: &'static str,
+    /// The name of the function.
+    name: &'static str,
+    /// The value of the cycle counter when the function was entered.
+    #[cfg(feature = "timestamps")]
+    start: u32,
+}
+
+#[cfg(feature = "instrument")]
+impl __Instrument {
+    /// Enters a function with the given `name`, logging the values of the given `arguments`.
+    #[must_use]
+    pub fn new(
+        target: &'static str,
+        name: &'static str,
+        arguments: Option<fmt::Arguments>,
+    ) -> Self {
+        match arguments {
+            Some(arguments) => log::debug!(target: target, "enter {name}({arguments})"),
+            None => log::debug!(target: target, "enter {name}"),
+        }
+        DEPTH.set(DEPTH.get().saturating_add(1));
+        Self {
+            target,
+            name,
+            // The cycle counter is started during initialization when the `timestamps` feature is
+            // enabled.
+            #[cfg(feature = "timestamps")]
+            start: crate::timer::cycles(),
+        }
+    }
+}
+
+#[cfg(feature = "instrument")]
+impl Drop for __Instrument {
+    fn drop(&mut self) {
+        DEPTH.set(DEPTH.get().saturating_sub(1));
+        #[cfg(feature = "timestamps")]
+        log::debug!(
+            target: self.target,
+            "exit {}: {} cycles",
+            self.name,
+            crate::timer::cycles().wrapping_sub(self.start)
+        );
+        #[cfg(not(feature = "timestamps"))]
+        log::debug!(target: self.target, "exit {}", self.name);
+    }
+}
@@ -0,0 +1,159 @@
+//! Mirroring of messages into a ring buffer in cartridge SRAM.
+//!
+//! The region of SRAM used starts with a header, followed by the ring buffer itself:
+//!
+//! | Offset | Length | Contents                                                           |
+//! |--------|--------|--------------------------------------------------------------------|
+//! | 0      | 4      | The magic bytes `MLOG`.                                            |
+//! | 4      | 2      | The length of the ring buffer, little endian.                      |
+//! | 6      | 2      | The offset within the ring buffer to write to next, little endian. |
+//! | 8      | 2      | The CRC-16/CCITT-FALSE of the preceding bytes, little endian.      |
+//!
+//! Each message is written to the ring buffer as a line prefixed with its level, as in
+//! `[WARN] Hello, world!\n`, and is followed by a null byte marking the end of the log. Before
+//! the ring buffer first wraps around, the bytes after the end of the log are all null as well.
+
+use crate::{backend::level_prefix, Level};
+
+/// The start of cartridge SRAM, which only supports byte accesses.
+const SRAM: *mut u8 = 0x0E00_0000 as *mut u8;
+/// The length of cartridge SRAM.
+pub(crate) const SRAM_LEN: usize = 0x8000;
+/// The bytes identifying the start of the header.
+const MAGIC: [u8; 4] = *b"MLOG";
+/// The length of the header.
+const HEADER_LEN: usize = 10;
+/// The shortest ring buffer allowed, which fits at least one full message.
+const MIN_RING_LEN: usize = 512;
+
+/// A region of SRAM holding a log.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SramLog {
+    /// The offset of the region within SRAM.
+    offset: usize,
+    /// The length of the ring buffer, following the header.
+    ring_len: usize,
+}
+
+impl SramLog {
+    /// Creates a log occupying the `len` bytes of SRAM starting at `offset`.
+    ///
+    /// Returns `None` if the region does not fit in SRAM, or if it is too short to hold a useful
+    /// log.
+    pub(crate) const fn new(offset: usize, len: usize) -> Option<Self> {
+        if offset > SRAM_LEN || len > SRAM_LEN - offset || len < HEADER_LEN + MIN_RING_LEN {
+            None
+        } else {
+            Some(Self {
+                offset,
+                ring_len: len - HEADER_LEN,
+            })
+        }
+    }
+
+    /// Reads the byte at `index` within the region.
+    fn read(self, index: usize) -> u8 {
+        // SAFETY: Every index used is within the region, which is within SRAM.
+        unsafe { SRAM.add(self.offset + index).read_volatile() }
+    }
+
+    /// Writes the byte at `index` within the region.
+    fn write(self, index: usize, byte: u8) {
+        // SAFETY: Every index used is within the region, which is within SRAM.
+        unsafe { SRAM.add(self.offset + index).write_volatile(byte) }
+    }
+
+    /// Reads the little endian halfword at `index` within the region.
+    fn read_u16(self, index: usize) -> u16 {
+        u16::from_le_bytes([self.read(index), self.read(index + 1)])
+    }
+
+    /// Writes the little endian halfword at `index` within the region.
+    fn write_u16(self, index: usize, value: u16) {
+        let [low, high] = value.to_le_bytes();
+        self.write(index, low);
+        self.write(index + 1, high);
+    }
+
+    /// Returns the offset within the ring buffer to write to next, or `None` if the header is not
+    /// valid for this region.
+    fn head(self) -> Option<usize> {
+        let valid = (0..MAGIC.len()).all(|index| self.read(index) == MAGIC[index])
+            && usize::from(self.read_u16(4)) == self.ring_len
+            && self.read_u16(8) == crc16((0..8).map(|index| self.read(index)));
+        let head = usize::from(self.read_u16(6));
+        (valid && head < self.ring_len).then_some(head)
+    }
+
+    /// Writes the header, with `head` as the offset within the ring buffer to write to next.
+    fn set_head(self, head: usize) {
+        for (index, &byte) in MAGIC.iter().enumerate() {
+            self.write(index, byte);
+        }
+        // Both lengths are less than the length of SRAM, so they always fit in a `u16`.
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            self.write_u16(4, self.ring_len as u16);
+            self.write_u16(6, head as u16);
+        }
+        self.write_u16(8, crc16((0..8).map(|index| self.read(index))));
+    }
+
+    /// Prepares the region for logging.
+    ///
+    /// A log left by a previous session is kept and appended to. Otherwise, the region is
+    /// cleared and a new header is written.
+    pub(crate) fn init(self) {
+        if self.head().is_none() {
+            for index in 0..self.ring_len {
+                self.write(HEADER_LEN + index, 0);
+            }
+            self.set_head(0);
+        }
+    }
+
+    /// Appends `message` at the given `level` to the log.
+    ///
+    /// Nothing is written if the header has been corrupted.
+    pub(crate) fn append(self, level: Level, message: &[u8]) {
+        let Some(mut head) = self.head() else {
+            return;
+        };
+        for &byte in level_prefix(level).iter().chain(message).chain(b"\n") {
+            self.write(HEADER_LEN + head, byte);
+            head = (head + 1) % self.ring_len;
+        }
+        self.write(HEADER_LEN + head, 0);
+        self.set_head(head);
+    }
+}
+
+/// Writes `bytes` to SRAM, starting at `offset`.
+///
+/// Bytes that would be written past the end of SRAM are not written.
+#[cfg(feature = "crash-dump")]
+pub(crate) fn write_bytes(offset: usize, bytes: &[u8]) {
+    for (index, &byte) in bytes[..SRAM_LEN.saturating_sub(offset).min(bytes.len())]
+        .iter()
+        .enumerate()
+    {
+        // SAFETY: The index is within SRAM, as bytes past its end are not written.
+        unsafe { SRAM.add(offset + index).write_volatile(byte) };
+    }
+}
+
+/// Computes the CRC-16/CCITT-FALSE of `bytes`.
+pub(crate) fn crc16<I>(bytes: I) -> u16
+where
+    I: IntoIterator<Item = u8>,
+{
+    bytes.into_iter().fold(0xFFFF, |crc, byte| {
+        (0..8).fold(crc ^ u16::from(byte) << 8, |crc, _| {
+            if crc & 0x8000 == 0 {
+                crc << 1
+            } else {
+                crc << 1 ^ 0x1021
+            }
+        })
+    })
+}
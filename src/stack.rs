@@ -0,0 +1,116 @@
+//! Measurement of the most stack ever used.
+//!
+//! See [`log_stack_usage!`](crate::log_stack_usage!) for details.
+
+use crate::{without_interrupts, LOGGER};
+use core::fmt::{self, Display};
+
+/// The word every unused word of the stack is filled with when it is painted.
+const PAINT: u32 = 0x5AFE_57AC;
+
+/// The most stack ever used, as measured by [`log_stack_usage!`](crate::log_stack_usage!).
+///
+/// This is logged as in `stack: used=1024 free=3072`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StackUsage {
+    /// The largest number of bytes of the stack that were ever in use at once.
+    pub used: usize,
+    /// The number of bytes below the deepest point the stack ever reached.
+    ///
+    /// If this is `0`, the stack was exhausted and has most likely overflowed into whatever is
+    /// placed below it.
+    pub free: usize,
+}
+
+impl Display for StackUsage {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "used={} free={}", self.used, self.free)?;
+        if self.free == 0 {
+            formatter.write_str(" (overflowed)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the given stack bounds, narrowed to the words entirely within them.
+fn word_bounds(bottom: usize, top: usize) -> (usize, usize) {
+    ((bottom + 3) & !3, top & !3)
+}
+
+/// Fills every word from `start` up to, but not including, `end` or the stack pointer, whichever
+/// comes first, with [`PAINT`].
+///
+/// Only the words below the stack pointer are painted, as those above it are in use. The loop is
+/// written in assembly so that the stack pointer is compared against while painting, rather than
+/// read beforehand, as the frame of this very function is placed below the caller's stack pointer.
+///
+/// # Safety
+/// `start` and `end` must be word aligned, and every word in between must be valid for writes.
+/// Words below the stack pointer must not be in use, so this must be called while interrupts are
+/// disabled.
+#[cfg(target_arch = "arm")]
+unsafe fn fill(start: usize, end: usize) {
+    // SAFETY: The caller guarantees that every word written is valid for writes, and that those
+    // below the stack pointer are not in use.
+    unsafe {
+        core::arch::asm!(
+            "2:",
+            "cmp {ptr}, {end}",
+            "bhs 3f",
+            "cmp {ptr}, sp",
+            "bhs 3f",
+            "stmia {ptr}!, {{{paint}}}",
+            "b 2b",
+            "3:",
+            ptr = inout(reg) start => _,
+            end = in(reg) end,
+            paint = in(reg) PAINT,
+            options(nostack),
+        );
+    }
+}
+
+/// Paints the unused part of the stack between `bottom` and `top`.
+///
+/// This must be called while interrupts are disabled, as an interrupt handler could otherwise
+/// place its own frame below the stack pointer while it is being painted.
+///
+/// # Safety
+/// `bottom` up to `top` must be the stack, or part of the stack, that the program is running on,
+/// and must not be used for anything else.
+#[cfg(target_arch = "arm")]
+pub(crate) unsafe fn paint(bottom: usize, top: usize) {
+    let (bottom, top) = word_bounds(bottom, top);
+    // SAFETY: The caller guarantees that the stack is valid for writes, and interrupts are
+    // disabled, so words below the stack pointer are not in use.
+    unsafe { fill(bottom, top) };
+}
+
+/// Paints the unused part of the stack between `bottom` and `top`.
+///
+/// The stack pointer can only be read on the Game Boy Advance's ARM CPU, so this paints nothing.
+#[cfg(not(target_arch = "arm"))]
+pub(crate) unsafe fn paint(_bottom: usize, _top: usize) {}
+
+/// Returns the most stack ever used, measured by finding the lowest word of the stack painted at
+/// initialization that no longer holds the paint.
+///
+/// Returns `None` if stack painting was not configured through
+/// [`LoggerBuilder::with_stack_painting()`](crate::LoggerBuilder::with_stack_painting()), or if no
+/// logger was initialized.
+#[doc(hidden)]
+#[must_use]
+pub fn __stack_usage() -> Option<StackUsage> {
+    // SAFETY: Interrupts are disabled, so the configuration cannot be accessed concurrently.
+    let (bottom, top) = without_interrupts(|| unsafe { (*LOGGER.config.get()).stack })?;
+    let (bottom, top) = word_bounds(bottom, top);
+    let mut deepest = bottom;
+    // SAFETY: The stack was guaranteed to be valid for reads when painting was configured.
+    while deepest < top && unsafe { (deepest as *const u32).read_volatile() } == PAINT {
+        deepest += 4;
+    }
+    Some(StackUsage {
+        used: top.saturating_sub(deepest),
+        free: deepest - bottom,
+    })
+}
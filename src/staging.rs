@@ -0,0 +1,236 @@
+//! Staging of records that cannot be written to mGBA immediately.
+//!
+//! A record logged while another record is being formatted, such as by a `Display` implementation
+//! that logs, would clobber the partially written record. Such records are instead formatted into
+//! a staging buffer in RAM, and are sent once the other record is finished.
+//!
+//! When interrupt staging is enabled through [`LoggerBuilder::with_interrupt_staging()`], records
+//! are written to mGBA's log buffer without disabling interrupts. Records logged by an interrupt
+//! handler that interrupts the writing of another record are staged in the same way.
+//!
+//! When deferred interrupt logging is enabled through
+//! [`LoggerBuilder::with_deferred_interrupt_logs()`], all records logged by interrupt handlers are
+//! staged, and are sent later from outside of the interrupt handler.
+//!
+//! Messages logged using [`log_from_irq()`](crate::log_from_irq()) are always staged in the same
+//! way.
+//!
+//! [`LoggerBuilder::with_interrupt_staging()`]: crate::LoggerBuilder::with_interrupt_staging()
+//! [`LoggerBuilder::with_deferred_interrupt_logs()`]: crate::LoggerBuilder::with_deferred_interrupt_logs()
+
+use crate::{
+    count_format_error, stats, without_interrupts, writer_config, Level, RacyCell, Writer,
+};
+use core::{cell::UnsafeCell, fmt, fmt::Write};
+
+/// The number of bytes available for staged records.
+///
+/// This is documented on [`LoggerBuilder::with_interrupt_staging()`] and
+/// [`LoggerBuilder::with_deferred_interrupt_logs()`], which must be updated if it changes.
+///
+/// [`LoggerBuilder::with_interrupt_staging()`]: crate::LoggerBuilder::with_interrupt_staging()
+/// [`LoggerBuilder::with_deferred_interrupt_logs()`]: crate::LoggerBuilder::with_deferred_interrupt_logs()
+const CAPACITY: usize = 1024;
+/// The number of bytes preceding the text of each staged record.
+///
+/// These contain the record's level and the length of its text, both as little-endian `u16`s.
+const HEADER_LEN: usize = 4;
+
+/// Whether a record is currently being written to mGBA's log buffer.
+static BUSY: RacyCell<bool> = RacyCell::new(false);
+
+/// Records waiting to be sent.
+pub(crate) static STAGING: Staging = Staging::new();
+
+/// Attempts to begin writing a record directly to mGBA's log buffer.
+///
+/// Returns `false` if another record is already being written, in which case the record should be
+/// staged instead. If this returns `true`, [`Staging::finish()`] must be called once the record
+/// has been written.
+///
+/// No read-modify-write is needed here: an interrupt handler that preempts this function always
+/// runs to completion, including finishing any record it writes, before this function resumes.
+pub(crate) fn begin() -> bool {
+    if BUSY.get() {
+        false
+    } else {
+        BUSY.set(true);
+        true
+    }
+}
+
+/// Converts a level's raw value back into a [`Level`].
+fn level_from_raw(raw: u16) -> Level {
+    match raw {
+        0x100 => Level::Fatal,
+        0x101 => Level::Error,
+        0x102 => Level::Warning,
+        0x103 => Level::Info,
+        _ => Level::Debug,
+    }
+}
+
+/// A buffer of records waiting to be sent to mGBA.
+///
+/// Each record is stored as a header of [`HEADER_LEN`] bytes followed by the record's text.
+pub(crate) struct Staging {
+    /// The staged records.
+    ///
+    /// This must only be accessed while interrupts are disabled.
+    buffer: UnsafeCell<[u8; CAPACITY]>,
+    /// The number of bytes of `buffer` in use.
+    ///
+    /// This must only be accessed while interrupts are disabled.
+    len: UnsafeCell<usize>,
+    /// Whether a record is currently being staged.
+    ///
+    /// This must only be accessed while interrupts are disabled.
+    in_progress: UnsafeCell<bool>,
+}
+
+impl Staging {
+    /// Creates a new, empty staging buffer.
+    const fn new() -> Self {
+        Self {
+            buffer: UnsafeCell::new([0; CAPACITY]),
+            len: UnsafeCell::new(0),
+            in_progress: UnsafeCell::new(false),
+        }
+    }
+
+    /// Stages a record at the given `level`, whose text is written by `write`.
+    ///
+    /// If the buffer fills up, the text is truncated. If there is no room for the record at all,
+    /// it is discarded. Records staged while `write` is formatting another staged record are also
+    /// discarded, as there is nowhere left to put them.
+    pub(crate) fn stage<F>(&self, level: Level, write: F)
+    where
+        F: FnOnce(&mut StagingWriter) -> fmt::Result,
+    {
+        without_interrupts(|| {
+            // SAFETY: Interrupts are disabled, so this flag cannot be accessed concurrently. It is
+            // checked before the buffer is borrowed, so that a nested call never aliases it.
+            if unsafe { self.in_progress.get().replace(true) } {
+                stats::count_dropped(level);
+                return;
+            }
+            // SAFETY: Interrupts are disabled and no other record is being staged, so the buffer
+            // cannot be accessed concurrently.
+            let (buffer, len) = unsafe { (&mut *self.buffer.get(), &mut *self.len.get()) };
+            let start = *len;
+            if CAPACITY - start <= HEADER_LEN {
+                // SAFETY: Interrupts are disabled, so this flag cannot be accessed concurrently.
+                unsafe { self.in_progress.get().write(false) };
+                stats::count_dropped(level);
+                return;
+            }
+            let mut writer = StagingWriter {
+                buffer: &mut buffer[start + HEADER_LEN..],
+                len: 0,
+            };
+            // On truncation, as much of the record as possible is kept. The writer only fails
+            // once the buffer is full, so any other error came from formatting the record.
+            if write(&mut writer).is_err() {
+                if writer.len < writer.buffer.len() {
+                    count_format_error();
+                } else {
+                    stats::count_truncated(level);
+                }
+            }
+            let text_len = writer.len;
+            buffer[start..start + 2].copy_from_slice(&(level as u16).to_le_bytes());
+            // The text is never longer than `CAPACITY`, so it always fits in a `u16`.
+            #[allow(clippy::cast_possible_truncation)]
+            buffer[start + 2..start + HEADER_LEN].copy_from_slice(&(text_len as u16).to_le_bytes());
+            *len = start + HEADER_LEN + text_len;
+            // SAFETY: Interrupts are disabled, so this flag cannot be accessed concurrently.
+            unsafe { self.in_progress.get().write(false) };
+        });
+    }
+
+    /// Sends all staged records.
+    ///
+    /// This must only be called outside of interrupt handlers, either after [`begin()`] returned
+    /// `true` or while interrupts are disabled.
+    pub(crate) fn send(&self) {
+        self.drain(false);
+    }
+
+    /// Sends all staged records, and then marks the logger as no longer writing a record.
+    ///
+    /// This must only be called after [`begin()`] returned `true`.
+    pub(crate) fn finish(&self) {
+        self.drain(true);
+    }
+
+    /// Sends all staged records, marking the logger as no longer writing a record if `release` is
+    /// `true`.
+    ///
+    /// Checking that no records remain and releasing the logger are done without interrupts in
+    /// between, so that no record can be staged without being sent.
+    fn drain(&self, release: bool) {
+        let mut offset = 0;
+        loop {
+            // Interrupts are only disabled while copying a single, already formatted record, and
+            // are allowed in between records.
+            let finished = without_interrupts(|| {
+                // SAFETY: Interrupts are disabled, so the buffer cannot be accessed concurrently.
+                let (buffer, len) = unsafe { (&*self.buffer.get(), &mut *self.len.get()) };
+                if offset >= *len {
+                    // Records staged after this point will be written directly by their loggers.
+                    *len = 0;
+                    if release {
+                        BUSY.set(false);
+                    }
+                    return true;
+                }
+                let level =
+                    level_from_raw(u16::from_le_bytes([buffer[offset], buffer[offset + 1]]));
+                let text_len =
+                    usize::from(u16::from_le_bytes([buffer[offset + 2], buffer[offset + 3]]));
+                let text = &buffer[offset + HEADER_LEN..offset + HEADER_LEN + text_len];
+                Writer::new(level, writer_config()).write_text(text);
+                offset += HEADER_LEN + text_len;
+                false
+            });
+            if finished {
+                break;
+            }
+        }
+    }
+}
+
+// SAFETY: The buffer is only ever accessed while interrupts are disabled. As the Game Boy Advance
+// has a single core, this means it is never accessed concurrently.
+unsafe impl Sync for Staging {}
+
+/// Writes the text of a staged record into the staging buffer.
+///
+/// Text is stored as it was formatted. Newlines and null characters are handled once the record is
+/// sent, so text truncated in the middle of a multi-byte character is still sent as-is.
+pub(crate) struct StagingWriter<'a> {
+    /// The remaining space in the staging buffer.
+    buffer: &'a mut [u8],
+    /// The number of bytes written.
+    len: usize,
+}
+
+impl StagingWriter<'_> {
+    /// Writes `bytes` as-is, which need not be valid UTF-8.
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) -> fmt::Result {
+        for &byte in bytes {
+            let Some(slot) = self.buffer.get_mut(self.len) else {
+                return Err(fmt::Error);
+            };
+            *slot = byte;
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
+impl Write for StagingWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_bytes(s.as_bytes())
+    }
+}
@@ -0,0 +1,150 @@
+//! Counting of the messages sent to mGBA.
+//!
+//! See [`stats()`](crate::stats()) for details.
+
+use crate::{racy::GuardedCell, Level};
+
+/// The statistics collected so far.
+static STATS: GuardedCell<Stats> = GuardedCell::new(Stats::new());
+
+/// Statistics about the messages logged at a single level.
+///
+/// Every counter saturates at `u32::MAX`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LevelStats {
+    /// The number of messages sent to mGBA.
+    ///
+    /// A record whose message is split across multiple sends or spans multiple lines counts once
+    /// for each message sent.
+    pub messages: u32,
+    /// The number of bytes of text sent to mGBA, not including the null byte ending each message.
+    pub bytes: u32,
+    /// The number of messages that were cut short.
+    ///
+    /// A message is cut short when it is truncated as configured through
+    /// [`LoggerBuilder::with_truncation()`](crate::LoggerBuilder::with_truncation()), or when it
+    /// does not fit in the RAM it is staged or formatted into.
+    pub truncated: u32,
+    /// The number of messages that were discarded because there was no room to stage them.
+    pub dropped: u32,
+}
+
+impl LevelStats {
+    /// Creates statistics with every counter set to zero.
+    const fn new() -> Self {
+        Self {
+            messages: 0,
+            bytes: 0,
+            truncated: 0,
+            dropped: 0,
+        }
+    }
+}
+
+/// Statistics about the messages logged at each level.
+///
+/// This is returned by [`stats()`](crate::stats()).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// Statistics about messages logged at the [`Fatal`](Level::Fatal) level.
+    pub fatal: LevelStats,
+    /// Statistics about messages logged at the [`Error`](Level::Error) level.
+    pub error: LevelStats,
+    /// Statistics about messages logged at the [`Warning`](Level::Warning) level.
+    pub warning: LevelStats,
+    /// Statistics about messages logged at the [`Info`](Level::Info) level.
+    pub info: LevelStats,
+    /// Statistics about messages logged at the [`Debug`](Level::Debug) level.
+    pub debug: LevelStats,
+}
+
+impl Stats {
+    /// Creates statistics with every counter set to zero.
+    const fn new() -> Self {
+        Self {
+            fatal: LevelStats::new(),
+            error: LevelStats::new(),
+            warning: LevelStats::new(),
+            info: LevelStats::new(),
+            debug: LevelStats::new(),
+        }
+    }
+
+    /// Returns the statistics about messages logged at `level`.
+    #[must_use]
+    pub const fn level(&self, level: Level) -> &LevelStats {
+        match level {
+            Level::Fatal => &self.fatal,
+            Level::Error => &self.error,
+            Level::Warning => &self.warning,
+            Level::Info => &self.info,
+            Level::Debug => &self.debug,
+        }
+    }
+
+    /// Returns the statistics about messages logged at all levels combined.
+    #[must_use]
+    pub fn total(&self) -> LevelStats {
+        [self.fatal, self.error, self.warning, self.info, self.debug]
+            .into_iter()
+            .fold(LevelStats::new(), |total, stats| LevelStats {
+                messages: total.messages.saturating_add(stats.messages),
+                bytes: total.bytes.saturating_add(stats.bytes),
+                truncated: total.truncated.saturating_add(stats.truncated),
+                dropped: total.dropped.saturating_add(stats.dropped),
+            })
+    }
+
+    /// Returns the statistics about messages logged at `level`, for updating.
+    fn level_mut(&mut self, level: Level) -> &mut LevelStats {
+        match level {
+            Level::Fatal => &mut self.fatal,
+            Level::Error => &mut self.error,
+            Level::Warning => &mut self.warning,
+            Level::Info => &mut self.info,
+            Level::Debug => &mut self.debug,
+        }
+    }
+}
+
+/// Updates the statistics about messages logged at `level`.
+fn update<F>(level: Level, f: F)
+where
+    F: FnOnce(&mut LevelStats),
+{
+    STATS.update(|stats| f(stats.level_mut(level)));
+}
+
+/// Returns a copy of the statistics collected so far.
+pub(crate) fn get() -> Stats {
+    STATS.get()
+}
+
+/// Counts a message of `len` bytes sent at `level`, which was cut short if `truncated` is `true`.
+pub(crate) fn count_sent(level: Level, len: usize, truncated: bool) {
+    update(level, |stats| {
+        stats.messages = stats.messages.saturating_add(1);
+        // A message is never longer than the log buffer, so its length always fits in a `u32`.
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            stats.bytes = stats.bytes.saturating_add(len as u32);
+        }
+        if truncated {
+            stats.truncated = stats.truncated.saturating_add(1);
+        }
+    });
+}
+
+/// Counts a message at `level` that was cut short before being sent.
+pub(crate) fn count_truncated(level: Level) {
+    update(level, |stats| {
+        stats.truncated = stats.truncated.saturating_add(1);
+    });
+}
+
+/// Counts a message at `level` that was discarded.
+pub(crate) fn count_dropped(level: Level) {
+    update(level, |stats| {
+        stats.dropped = stats.dropped.saturating_add(1);
+    });
+}
@@ -0,0 +1,131 @@
+//! A test runner for the unstable `custom_test_frameworks` feature, running tests on the Game Boy
+//! Advance and reporting their results through mGBA's log.
+//!
+//! This is enabled by the `test-runner` feature. See [`test_runner()`] for details.
+
+use crate::{__fatal_halt, __print, racy::GuardedCell, Level};
+
+/// A test that can be run by [`test_runner()`].
+///
+/// This is implemented for all functions taking no arguments, which includes every function
+/// annotated with `#[test_case]`.
+pub trait Testable {
+    /// Returns the name the test is reported with.
+    fn name(&self) -> &'static str;
+
+    /// Runs the test, panicking if it fails.
+    fn run(&self);
+}
+
+impl<T> Testable for T
+where
+    T: Fn(),
+{
+    fn name(&self) -> &'static str {
+        core::any::type_name::<T>()
+    }
+
+    fn run(&self) {
+        self();
+    }
+}
+
+/// The progress of the test run in progress.
+#[derive(Clone, Copy)]
+struct Progress {
+    /// The name of the test currently running.
+    name: &'static str,
+    /// The number of tests that have passed so far.
+    passed: usize,
+    /// The number of tests in the run.
+    total: usize,
+}
+
+/// The progress of the test run in progress, if any.
+static PROGRESS: GuardedCell<Option<Progress>> = GuardedCell::new(None);
+
+/// Records the progress of the test run in progress.
+fn set_progress(progress: Option<Progress>) {
+    PROGRESS.set(progress);
+}
+
+/// Runs every test in `tests`, logging the result of each, followed by a summary.
+///
+/// Each passing test is logged at the info level, as in `TEST game::tests::physics ... ok`. Once
+/// every test has passed, a summary such as `test result: ok. 3 passed; 0 failed` is logged at the
+/// fatal level, halting mGBA. The tests are run whether or not the logger is initialized, so
+/// programs only need to initialize it if the tests themselves use the [`log`] macros.
+///
+/// As panics abort on the Game Boy Advance, the first failing test ends the run. The panic handler
+/// of the `panic-handler` feature, which the `test-runner` feature enables, then logs
+/// `TEST game::tests::physics ... FAILED` and a summary such as
+/// `test result: FAILED. 1 passed; 1 failed; 1 not run` at the error level, before logging the
+/// panic itself at the fatal level. The `mgba_log_reporter` test crate's `TestReport` parses the
+/// results from the log.
+///
+/// This is meant to be used as the runner of the unstable `custom_test_frameworks` feature, with
+/// the generated test harness called from the program's entry point:
+///
+/// ``` ignore
+/// #![no_std]
+/// #![no_main]
+/// #![feature(custom_test_frameworks)]
+/// #![test_runner(mgba_log::test_runner)]
+/// #![reexport_test_harness_main = "test_main"]
+///
+/// #[no_mangle]
+/// pub fn main() {
+///     #[cfg(test)]
+///     test_main();
+/// }
+///
+/// #[test_case]
+/// fn addition() {
+///     assert_eq!(1 + 1, 2);
+/// }
+/// ```
+pub fn test_runner(tests: &[&dyn Testable]) -> ! {
+    __print(
+        Level::Info,
+        format_args!(
+            "running {} test{}",
+            tests.len(),
+            if tests.len() == 1 { "" } else { "s" }
+        ),
+    );
+    for (passed, test) in tests.iter().enumerate() {
+        set_progress(Some(Progress {
+            name: test.name(),
+            passed,
+            total: tests.len(),
+        }));
+        test.run();
+        __print(Level::Info, format_args!("TEST {} ... ok", test.name()));
+    }
+    set_progress(None);
+    __fatal_halt(format_args!(
+        "test result: ok. {} passed; 0 failed",
+        tests.len()
+    ))
+}
+
+/// Logs that the test currently running failed, along with a summary of the test run.
+///
+/// This is called by the panic handler. Nothing is logged if no test is running.
+pub(crate) fn fail() {
+    let Some(progress) = PROGRESS.get() else {
+        return;
+    };
+    __print(
+        Level::Error,
+        format_args!("TEST {} ... FAILED", progress.name),
+    );
+    __print(
+        Level::Error,
+        format_args!(
+            "test result: FAILED. {} passed; 1 failed; {} not run",
+            progress.passed,
+            progress.total - progress.passed - 1
+        ),
+    );
+}
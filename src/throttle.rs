@@ -0,0 +1,69 @@
+//! Limiting how often a call site logs.
+//!
+//! This is the implementation of the [`log_once!`](crate::log_once!) and
+//! [`log_every_n!`](crate::log_every_n!) macros.
+
+use crate::{without_interrupts, RacyCell};
+use core::{fmt, fmt::Display};
+
+/// Returns `true` the first time it is called with the given `flag`, and `false` every time after.
+///
+/// This is an implementation detail of the [`log_once!`](crate::log_once!) macro. It is not
+/// considered part of the public API and should not be used directly by external code.
+#[doc(hidden)]
+pub fn __once(flag: &RacyCell<bool>) -> bool {
+    // Avoid disabling interrupts once the flag has been set.
+    if flag.get() {
+        return false;
+    }
+    // The Game Boy Advance has no atomic swap, so interrupts are disabled to prevent an interrupt
+    // handler from observing the flag between it being read and being set.
+    without_interrupts(|| {
+        let first = !flag.get();
+        flag.set(true);
+        first
+    })
+}
+
+/// Returns the number of skipped invocations if a call site with the given `counter` should log,
+/// logging every `n`th invocation.
+///
+/// `counter` stores the number of invocations since the call site last logged, or `0` if it has
+/// never logged.
+///
+/// This is an implementation detail of the [`log_every_n!`](crate::log_every_n!) macro. It is not
+/// considered part of the public API and should not be used directly by external code.
+#[doc(hidden)]
+pub fn __every_n(counter: &RacyCell<u32>, n: u32) -> Option<u32> {
+    // The Game Boy Advance has no atomic read-modify-write operations, so interrupts are disabled
+    // to prevent an interrupt handler from modifying the counter while it is being updated.
+    without_interrupts(|| {
+        let invocations = counter.get();
+        if invocations == 0 || invocations >= n {
+            counter.set(1);
+            Some(invocations.saturating_sub(1))
+        } else {
+            counter.set(invocations + 1);
+            None
+        }
+    })
+}
+
+/// Displays the number of invocations skipped by [`log_every_n!`](crate::log_every_n!).
+///
+/// Nothing is displayed if no invocations were skipped.
+///
+/// This is an implementation detail of the [`log_every_n!`](crate::log_every_n!) macro. It is not
+/// considered part of the public API and should not be used directly by external code.
+#[doc(hidden)]
+pub struct __Skipped(pub u32);
+
+impl Display for __Skipped {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 == 0 {
+            Ok(())
+        } else {
+            write!(formatter, " ({} skipped)", self.0)
+        }
+    }
+}
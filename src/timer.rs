@@ -0,0 +1,157 @@
+//! Cycle counting using the Game Boy Advance's hardware timers.
+//!
+//! Timers 2 and 3 are cascaded to form a single free-running 32-bit counter that increments once
+//! per CPU cycle. This is the implementation of the [`time_scope!`](crate::time_scope!) macro and
+//! of the timestamps written when the `timestamps` feature is enabled.
+
+#[cfg(feature = "timestamps")]
+use crate::{fmt::dec_u32, RacyCell};
+#[cfg(feature = "timestamps")]
+use core::fmt::{self, Write};
+
+/// Counter register for timer 2.
+///
+/// Reading this address returns the lower 16 bits of the cycle counter. Writing to it sets the
+/// timer's reload value.
+const TM2CNT_L: *mut u16 = 0x0400_0108 as *mut u16;
+/// Control register for timer 2.
+const TM2CNT_H: *mut u16 = 0x0400_010A as *mut u16;
+/// Counter register for timer 3.
+///
+/// Reading this address returns the upper 16 bits of the cycle counter. Writing to it sets the
+/// timer's reload value.
+const TM3CNT_L: *mut u16 = 0x0400_010C as *mut u16;
+/// Control register for timer 3.
+const TM3CNT_H: *mut u16 = 0x0400_010E as *mut u16;
+
+/// Timer control value enabling a timer that increments every cycle.
+const ENABLE: u16 = 0x0080;
+/// Timer control value enabling a timer that increments when the previous timer overflows.
+const ENABLE_CASCADE: u16 = 0x0084;
+
+/// The value of the cycle counter when the logger was initialized.
+#[cfg(feature = "timestamps")]
+static EPOCH: RacyCell<u32> = RacyCell::new(0);
+
+/// Starts the cycle counter, if it is not already running.
+///
+/// The counter is considered running if timers 2 and 3 are configured exactly as this module
+/// configures them. Otherwise, both timers are reset and restarted.
+fn start() {
+    // SAFETY: These are all valid timer registers.
+    unsafe {
+        if TM2CNT_H.read_volatile() == ENABLE && TM3CNT_H.read_volatile() == ENABLE_CASCADE {
+            return;
+        }
+        TM2CNT_H.write_volatile(0);
+        TM3CNT_H.write_volatile(0);
+        TM2CNT_L.write_volatile(0);
+        TM3CNT_L.write_volatile(0);
+        // Timer 3 must be enabled first so that it observes the first overflow of timer 2.
+        TM3CNT_H.write_volatile(ENABLE_CASCADE);
+        TM2CNT_H.write_volatile(ENABLE);
+    }
+}
+
+/// Returns the current value of the cycle counter.
+///
+/// The counter wraps around after 2^32 cycles, so differences between values should be computed
+/// using wrapping arithmetic.
+pub(crate) fn cycles() -> u32 {
+    loop {
+        // SAFETY: These are both valid timer registers.
+        let (high, low, high_again) = unsafe {
+            (
+                TM3CNT_L.read_volatile(),
+                TM2CNT_L.read_volatile(),
+                TM3CNT_L.read_volatile(),
+            )
+        };
+        // If the upper half changed between reads, the lower half overflowed in between and the
+        // read must be retried.
+        if high == high_again {
+            return u32::from(high) << 16 | u32::from(low);
+        }
+    }
+}
+
+/// Starts the cycle counter, and measures timestamps from this point onward.
+#[cfg(feature = "timestamps")]
+pub(crate) fn start_timestamps() {
+    start();
+    EPOCH.set(cycles());
+}
+
+/// Writes the time elapsed since [`start_timestamps()`] was called, as in `[12.345ms] `.
+///
+/// The cycle counter wraps around after 2^32 cycles, so timestamps wrap around after about 256
+/// seconds.
+#[cfg(feature = "timestamps")]
+pub(crate) fn write_timestamp<W>(writer: &mut W) -> fmt::Result
+where
+    W: Write,
+{
+    let elapsed = u64::from(cycles().wrapping_sub(EPOCH.get()));
+    // The CPU runs at exactly 2^24 cycles per second, so converting to milliseconds and
+    // microseconds only requires multiplication and shifting. Both results fit in a `u32`, as the
+    // elapsed cycles do.
+    #[allow(clippy::cast_possible_truncation)]
+    let (millis, micros) = (
+        ((elapsed * 1_000) >> 24) as u32,
+        ((elapsed * 1_000_000) >> 24) as u32,
+    );
+    let fraction = micros - millis * 1_000;
+    write!(writer, "[{}.", dec_u32(millis))?;
+    if fraction < 100 {
+        writer.write_char('0')?;
+    }
+    if fraction < 10 {
+        writer.write_char('0')?;
+    }
+    write!(writer, "{}ms] ", dec_u32(fraction))
+}
+
+/// Logs the number of cycles elapsed between its creation and when it is dropped.
+///
+/// This is an implementation detail of the [`time_scope!`](crate::time_scope!) macro. It is not
+/// considered part of the public API and should not be used directly by external code.
+#[doc(hidden)]
+pub struct __TimeScope {
+    /// The target the elapsed cycles are logged to.
+    target: &'static str,
+    /// The name of the timed scope.
+    name: &'static str,
+    /// The value of the cycle counter when the scope was entered.
+    start: u32,
+}
+
+impl __TimeScope {
+    /// Starts timing a scope with the given `name`.
+    #[must_use]
+    pub fn new(target: &'static str, name: &'static str) -> Self {
+        // The timers are left untouched if the elapsed cycles could never be logged.
+        if log::Level::Debug > log::STATIC_MAX_LEVEL {
+            return Self {
+                target,
+                name,
+                start: 0,
+            };
+        }
+        start();
+        Self {
+            target,
+            name,
+            start: cycles(),
+        }
+    }
+}
+
+impl Drop for __TimeScope {
+    fn drop(&mut self) {
+        if log::Level::Debug > log::STATIC_MAX_LEVEL {
+            return;
+        }
+        let elapsed = cycles().wrapping_sub(self.start);
+        log::debug!(target: self.target, "{}: {} cycles", self.name, elapsed);
+    }
+}
@@ -0,0 +1,77 @@
+//! Output through the serial port in UART mode.
+//!
+//! In UART mode, each byte written to the serial data register is sent over the link port's SO
+//! line, allowing messages to be captured from real hardware through a USB link cable adapter.
+//! Flow control is not used, so the receiving end must keep up with the configured baud rate.
+
+use crate::{backend::level_prefix, Level};
+
+/// The general purpose control register, which selects between serial and general purpose modes.
+const RCNT: *mut u16 = 0x0400_0134 as *mut u16;
+/// The serial control register.
+const SIOCNT: *mut u16 = 0x0400_0128 as *mut u16;
+/// The serial data register used for 8-bit transfers.
+const SIODATA8: *mut u8 = 0x0400_012A as *mut u8;
+
+/// Set in [`SIOCNT`] while the send FIFO is full.
+const SEND_FULL: u16 = 0x0010;
+/// Selects 8-bit data in [`SIOCNT`].
+const DATA_8_BIT: u16 = 0x0080;
+/// Enables the FIFOs in [`SIOCNT`]. Setting this resets them.
+const FIFO_ENABLE: u16 = 0x0100;
+/// Enables sending in [`SIOCNT`].
+const SEND_ENABLE: u16 = 0x0400;
+/// Selects UART mode in [`SIOCNT`].
+const UART_MODE: u16 = 0x3000;
+
+/// The rate at which bytes are sent over the serial port.
+///
+/// This is set using [`Backend::Uart`](crate::Backend::Uart). Every byte is sent as 8 data bits
+/// with one stop bit and no parity.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BaudRate {
+    /// 9600 bits per second.
+    Baud9600,
+    /// 38400 bits per second.
+    Baud38400,
+    /// 57600 bits per second.
+    Baud57600,
+    /// 115200 bits per second.
+    #[default]
+    Baud115200,
+}
+
+impl BaudRate {
+    /// Returns the bits of [`SIOCNT`] selecting this baud rate.
+    fn bits(self) -> u16 {
+        match self {
+            Self::Baud9600 => 0,
+            Self::Baud38400 => 1,
+            Self::Baud57600 => 2,
+            Self::Baud115200 => 3,
+        }
+    }
+}
+
+/// Switches the serial port to UART mode, sending at the given `baud_rate`.
+pub(crate) fn init(baud_rate: BaudRate) {
+    let control = baud_rate.bits() | DATA_8_BIT | SEND_ENABLE | UART_MODE;
+    // SAFETY: These are all valid writes to the serial registers.
+    unsafe {
+        RCNT.write_volatile(0);
+        // Enabling the FIFO after configuring the port resets it.
+        SIOCNT.write_volatile(control);
+        SIOCNT.write_volatile(control | FIFO_ENABLE);
+    }
+}
+
+/// Prints `text` at the given `level`, followed by a newline.
+pub(crate) fn print(level: Level, text: &[u8]) {
+    for &byte in level_prefix(level).iter().chain(text).chain(b"\n") {
+        // SAFETY: These are valid accesses to the serial registers.
+        unsafe {
+            while SIOCNT.read_volatile() & SEND_FULL != 0 {}
+            SIODATA8.write_volatile(byte);
+        }
+    }
+}
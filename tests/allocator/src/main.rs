@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::UnsafeCell,
+    ptr,
+};
+use mgba_log::LoggingAllocator;
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+/// The size of the heap, in bytes.
+const HEAP_SIZE: usize = 1024;
+
+/// An allocator handing out memory from a fixed heap, which is never freed.
+struct BumpAllocator {
+    heap: UnsafeCell<[u8; HEAP_SIZE]>,
+    next: UnsafeCell<usize>,
+}
+
+unsafe impl Sync for BumpAllocator {}
+
+unsafe impl GlobalAlloc for BumpAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let heap = self.heap.get() as *mut u8;
+        let start = (heap as usize + *self.next.get() + layout.align() - 1) & !(layout.align() - 1);
+        let end = start - heap as usize + layout.size();
+        if end > HEAP_SIZE {
+            return ptr::null_mut();
+        }
+        *self.next.get() = end;
+        start as *mut u8
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+}
+
+#[global_allocator]
+static ALLOCATOR: LoggingAllocator<BumpAllocator> = LoggingAllocator::new(BumpAllocator {
+    heap: UnsafeCell::new([0; HEAP_SIZE]),
+    next: UnsafeCell::new(0),
+})
+.with_heap_size(HEAP_SIZE);
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+
+    let boxed = Box::new([0u8; 100]);
+    let mut vec = Vec::<u8>::new();
+    if vec.try_reserve(2000).is_err() {
+        log::info!("recovered from failed allocation");
+    }
+    drop(boxed);
+
+    STATUS_REGISTER.write(3);
+}
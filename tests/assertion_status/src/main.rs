@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+use core::ptr::addr_of;
+use voladdress::{Safe, VolAddress};
+
+/// The halfword containing the status register used to communicate the current execution status
+/// directly with the test runner.
+///
+/// The status register itself is the upper byte of this halfword.
+const STATUS_HALFWORD: usize = 0x0203FFFE;
+
+/// The current scanline.
+const VCOUNT: VolAddress<u16, Safe, ()> = unsafe { VolAddress::new(0x0400_0006) };
+/// DMA 3 source address.
+const DMA3SAD: VolAddress<usize, (), Safe> = unsafe { VolAddress::new(0x0400_00D4) };
+/// DMA 3 destination address.
+const DMA3DAD: VolAddress<usize, (), Safe> = unsafe { VolAddress::new(0x0400_00D8) };
+/// DMA 3 transfer count.
+const DMA3CNT_L: VolAddress<u16, (), Safe> = unsafe { VolAddress::new(0x0400_00DC) };
+/// DMA 3 control.
+const DMA3CNT_H: VolAddress<u16, (), Safe> = unsafe { VolAddress::new(0x0400_00DE) };
+
+/// The halfword copied to the status halfword at the next vblank.
+///
+/// The failed assertion writes the finished status to its upper byte, so the test only finishes if
+/// the assertion status is written.
+static mut ASSERTION_STATUS: u16 = 0;
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    let status = unsafe { addr_of!(ASSERTION_STATUS) } as usize;
+    let builder = unsafe { mgba_log::builder().with_assertion_status(status + 1, 3) };
+    builder.init().expect("unable to initialize");
+
+    // A failed assertion never returns, so the assertion status is copied to the status halfword
+    // by a DMA transfer at the next vblank instead. Waiting for the start of a frame leaves plenty
+    // of time to log the message first.
+    while VCOUNT.read() != 0 {}
+    DMA3SAD.write(status);
+    DMA3DAD.write(STATUS_HALFWORD);
+    DMA3CNT_L.write(1);
+    // Enable a single 16-bit transfer, started at vblank.
+    DMA3CNT_H.write(0x9000);
+
+    mgba_log::mgba_assert_eq!(1 + 1, 3);
+}
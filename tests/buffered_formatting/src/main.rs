@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+use core::fmt;
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+/// Interrupt master enable.
+const IME: VolAddress<bool, Safe, Safe> = unsafe { VolAddress::new(0x0400_0208) };
+
+/// Displays the value of `IME` at the time it is formatted.
+struct Ime;
+
+impl fmt::Display for Ime {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", IME.read())
+    }
+}
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::builder()
+        .with_buffered_formatting(true)
+        .init()
+        .expect("unable to initialize");
+    IME.write(true);
+    log::info!("IME: {}", Ime);
+    log::info!("first\nsecond");
+    log::info!("abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyz");
+    log::info!("IME: {}", IME.read());
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
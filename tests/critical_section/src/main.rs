@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+/// Interrupt master enable.
+const IME: VolAddress<bool, Safe, Safe> = unsafe { VolAddress::new(0x0400_0208) };
+
+/// The number of times a critical section has been acquired.
+///
+/// This is stored in otherwise unused EWRAM, as atomic types are not available on the Game Boy
+/// Advance.
+const ACQUIRED: VolAddress<u32, Safe, Safe> = unsafe { VolAddress::new(0x0203_FF00) };
+
+/// A critical section implementation that disables interrupts using `IME`.
+struct CriticalSection;
+
+critical_section::set_impl!(CriticalSection);
+
+unsafe impl critical_section::Impl for CriticalSection {
+    unsafe fn acquire() -> bool {
+        let previous = IME.read();
+        IME.write(false);
+        ACQUIRED.write(ACQUIRED.read() + 1);
+        previous
+    }
+
+    unsafe fn release(previous: bool) {
+        IME.write(previous);
+    }
+}
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+    log::info!("Hello, world!");
+    let acquired = ACQUIRED.read() > 0;
+    log::info!("acquired: {}", acquired);
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
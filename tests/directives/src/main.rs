@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::builder()
+        .with_directives(mgba_log::directives!(
+            "warn,game::ai=debug,game::ai::pathfinding=off,sound"
+        ))
+        .init()
+        .expect("unable to initialize");
+    log::info!(target: "other", "not logged");
+    log::warn!(target: "other", "default level");
+    log::debug!(target: "game::ai::state", "submodule");
+    log::error!(target: "game::ai::pathfinding", "not logged");
+    log::debug!(target: "sound::mixer", "bare target");
+    log::debug!(target: "sound_effects", "not logged");
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
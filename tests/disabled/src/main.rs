@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+    log::info!("not logged");
+    mgba_log::println!("not printed");
+    write!(mgba_log::MgbaWriter::new(mgba_log::Level::Info), "not written").unwrap();
+    mgba_log::log_from_irq(log::Level::Info, format_args!("not deferred"));
+    mgba_log::flush();
+    if mgba_log::is_mgba() {
+        panic!("mGBA was detected");
+    }
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+use mgba_log::IoRegisters;
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+/// Display control.
+const DISPCNT: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0000) };
+/// Background 0 control.
+const BG0CNT: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0008) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+
+    // Mode 3 with background 2 enabled.
+    DISPCNT.write(0x0403);
+    // Character base block 0, screen base block 1.
+    BG0CNT.write(0x0100);
+
+    mgba_log::dump_io_registers(IoRegisters::DISPLAY | IoRegisters::BACKGROUNDS);
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
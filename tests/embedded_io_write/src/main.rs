@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+
+use embedded_io::Write;
+use mgba_log::{Level, MgbaWriter};
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+    let mut writer = MgbaWriter::new(Level::Warning);
+    writer.write_all(b"Hello, ").expect("unable to write");
+    writeln!(writer, "world! {}", 42).expect("unable to write");
+    writer.write_all(b"foo").expect("unable to write");
+    writer.flush().expect("unable to flush");
+    writer.write_all(b"bar").expect("unable to write");
+    drop(writer);
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
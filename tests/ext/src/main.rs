@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+use mgba_log::{OptionExt, ResultExt};
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+    let ok: Result<u32, &str> = Ok(1);
+    let err: Result<u32, &str> = Err("bad");
+    let value = ok.log_err("ext").expect_fatal("ok");
+    let result = err.log_err("ext");
+    let value = value + Some(2).expect_fatal("some");
+    log::info!("{} {:?}", value, result);
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
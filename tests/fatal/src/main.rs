@@ -3,8 +3,25 @@
 
 use voladdress::{Safe, VolAddress};
 
-/// This address is used to communicate the current execution status directly with the test runner.
-const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+/// The halfword containing the status register used to communicate the current execution status
+/// directly with the test runner.
+///
+/// The status register itself is the upper byte of this halfword.
+const STATUS_HALFWORD: usize = 0x0203FFFE;
+
+/// The current scanline.
+const VCOUNT: VolAddress<u16, Safe, ()> = unsafe { VolAddress::new(0x0400_0006) };
+/// DMA 3 source address.
+const DMA3SAD: VolAddress<usize, (), Safe> = unsafe { VolAddress::new(0x0400_00D4) };
+/// DMA 3 destination address.
+const DMA3DAD: VolAddress<usize, (), Safe> = unsafe { VolAddress::new(0x0400_00D8) };
+/// DMA 3 transfer count.
+const DMA3CNT_L: VolAddress<u16, (), Safe> = unsafe { VolAddress::new(0x0400_00DC) };
+/// DMA 3 control.
+const DMA3CNT_H: VolAddress<u16, (), Safe> = unsafe { VolAddress::new(0x0400_00DE) };
+
+/// The finished status, positioned in the upper byte of a halfword.
+static FINISHED: u16 = 3 << 8;
 
 #[panic_handler]
 fn panic_handler(_: &core::panic::PanicInfo) -> ! {
@@ -17,9 +34,16 @@ pub fn __sync_synchronize() {}
 #[no_mangle]
 pub fn main() {
     mgba_log::init().expect("unable to initialize");
-    mgba_log::fatal!("Hello, world!");
 
-    STATUS_REGISTER.write(3);
+    // `fatal!` never returns, so the finished status is written by a DMA transfer at the next
+    // vblank instead. Waiting for the start of a frame leaves plenty of time to log the message
+    // first.
+    while VCOUNT.read() != 0 {}
+    DMA3SAD.write(&FINISHED as *const u16 as usize);
+    DMA3DAD.write(STATUS_HALFWORD);
+    DMA3CNT_L.write(1);
+    // Enable a single 16-bit transfer, started at vblank.
+    DMA3CNT_H.write(0x9000);
 
-    loop {}
+    mgba_log::fatal!("Hello, world!");
 }
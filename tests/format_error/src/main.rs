@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use core::{fmt, fmt::Display};
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+/// A value whose formatting fails partway through.
+struct Failing;
+
+impl Display for Failing {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("partial")?;
+        Err(fmt::Error)
+    }
+}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+    log::info!("{}", Failing);
+    log::info!("after");
+    mgba_log::println!("format errors: {}", mgba_log::format_errors());
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
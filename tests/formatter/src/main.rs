@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+use log::Record;
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+fn format(writer: &mut dyn Write, record: &Record) -> core::fmt::Result {
+    write!(writer, "<{}> {}", record.level(), record.args())
+}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::builder()
+        .with_target(true)
+        .with_formatter(&format)
+        .init()
+        .expect("unable to initialize");
+    log::info!("Hello, world!");
+    log::warn!("foo\nbar");
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
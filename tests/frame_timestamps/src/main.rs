@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::builder()
+        .with_frame_timestamps(true)
+        .init()
+        .expect("unable to initialize");
+    log::info!("Hello, world!");
+    mgba_log::tick_frame();
+    mgba_log::tick_frame();
+    log::warn!("foo\nbar");
+    mgba_log::log_bytes(log::Level::Info, b"baz");
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
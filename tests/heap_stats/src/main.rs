@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+use log::Level;
+use mgba_log::HeapUsage;
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+/// The number of bytes reported as allocated, standing in for an allocator's own bookkeeping.
+static mut USED: usize = 0;
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+fn heap_stats() -> HeapUsage {
+    let used = unsafe { USED };
+    let free = 1024 - used;
+    HeapUsage {
+        used,
+        free,
+        // The free space is split into blocks of at most 64 bytes.
+        largest_free: Some(free.min(64)),
+    }
+}
+
+#[no_mangle]
+pub fn main() {
+    // Nothing is logged before a source is registered.
+    mgba_log::log_heap_stats!();
+
+    mgba_log::builder()
+        .with_heap_stats(heap_stats)
+        .init()
+        .expect("unable to initialize");
+
+    unsafe { USED = 768 };
+    mgba_log::log_heap_stats!();
+    unsafe { USED = 1000 };
+    mgba_log::log_heap_stats!(Level::Info);
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
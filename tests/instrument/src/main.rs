@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[mgba_log::instrument(frame, name)]
+fn update(frame: u32, name: &str, _ignored: u8) -> u32 {
+    log::info!("updating");
+    physics();
+    frame + 1
+}
+
+#[mgba_log::instrument]
+fn physics() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+    let frame = update(42, "level 1", 0);
+    log::info!("frame {}", frame);
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+
+use mgba_log::fmt::{dec_u32, hex_u16, hex_u32};
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+    log::info!("{} {}", hex_u16(0x04a0), hex_u16(u16::MAX));
+    log::info!("{} {}", hex_u32(0x0800_04a0), hex_u32(0));
+    log::info!(
+        "{} {} {}",
+        dec_u32(0),
+        dec_u32(1_000_000),
+        dec_u32(u32::MAX)
+    );
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
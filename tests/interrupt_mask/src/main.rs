@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+use mgba_log::InterruptMask;
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+/// Interrupt enable.
+const IE: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0200) };
+
+/// The number of times the interrupt mask has been acquired.
+///
+/// This is stored in otherwise unused EWRAM, as atomic types are not available on the Game Boy
+/// Advance.
+const ACQUIRED: VolAddress<u32, Safe, Safe> = unsafe { VolAddress::new(0x0203_FF00) };
+
+/// Masks all interrupts using `IE`.
+fn acquire() -> u16 {
+    let previous = IE.read();
+    IE.write(0);
+    ACQUIRED.write(ACQUIRED.read() + 1);
+    previous
+}
+
+fn release(previous: u16) {
+    IE.write(previous);
+}
+
+static MASK: InterruptMask = InterruptMask::new(acquire, release);
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::builder()
+        .with_interrupt_mask(&MASK)
+        .init()
+        .expect("unable to initialize");
+    ACQUIRED.write(0);
+    log::info!("Hello, world!");
+    let acquired = ACQUIRED.read() > 0;
+    log::info!("acquired: {}", acquired);
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
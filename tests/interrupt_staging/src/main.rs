@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+use core::fmt;
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+/// The address of the interrupt handler called by the BIOS.
+const IRQ_HANDLER: VolAddress<unsafe extern "C" fn(), Safe, Safe> =
+    unsafe { VolAddress::new(0x0300_7FFC) };
+/// Timer 0 reload value.
+const TM0CNT_L: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0100) };
+/// Timer 0 control.
+const TM0CNT_H: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0102) };
+/// Interrupt enable.
+const IE: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0200) };
+/// Interrupt request flags.
+const IF: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0202) };
+/// Interrupt master enable.
+const IME: VolAddress<bool, Safe, Safe> = unsafe { VolAddress::new(0x0400_0208) };
+
+/// The interrupt flag for timer 0.
+const TIMER_0: u16 = 0x0008;
+
+extern "C" {
+    /// Defined in `rsrt0.s`.
+    fn irq_handler();
+}
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub extern "C" fn on_interrupt() {
+    TM0CNT_H.write(0);
+    IF.write(TIMER_0);
+    log::info!("interrupt");
+}
+
+/// Starts a timer interrupt while it is being formatted, and then takes a while to finish.
+struct Slow;
+
+impl fmt::Display for Slow {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        TM0CNT_L.write(0xFF00);
+        // Enable the timer with an interrupt on overflow.
+        TM0CNT_H.write(0x00C0);
+        for _ in 0..100 {
+            formatter.write_str("a")?;
+        }
+        Ok(())
+    }
+}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::builder()
+        .with_interrupt_staging(true)
+        .init()
+        .expect("unable to initialize");
+
+    IRQ_HANDLER.write(irq_handler);
+    IE.write(TIMER_0);
+    IME.write(true);
+
+    log::info!("{}", Slow);
+    log::info!("done");
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,35 @@
+@ linker entry point
+.global __start
+
+.arm
+__start: b init
+@ this is replaced with correct header info by `gbafix`
+.space 188
+
+init:
+  @ We boot in Supervisor mode, change to System mode.
+  mov r0, #0x1f
+  msr CPSR_c, r0
+
+  @ Set stack pointer.
+  ldr sp, =0x3007F00
+
+  @ call Rust `main`
+  ldr r2, =main
+  bx r2
+
+  @ `main` should never return.
+  1: b 1b
+
+@ Interrupt handler, called by the BIOS in ARM state.
+.global irq_handler
+irq_handler:
+  stmfd sp!, {lr}
+
+  @ call Rust `on_interrupt`
+  ldr r0, =on_interrupt
+  mov lr, pc
+  bx r0
+
+  ldmfd sp!, {lr}
+  bx lr
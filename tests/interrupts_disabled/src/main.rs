@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+/// Interrupt master enable.
+const IME: VolAddress<bool, Safe, Safe> = unsafe { VolAddress::new(0x0400_0208) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+
+    // Measures the cycles spent logging the same record with interrupts enabled and disabled.
+    IME.write(true);
+    {
+        mgba_log::time_scope!("enabled");
+        log::info!("Hello, world!");
+    }
+    IME.write(false);
+    {
+        mgba_log::time_scope!("disabled");
+        log::info!("Hello, world!");
+    }
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -4,12 +4,18 @@
 //! versions.
 
 use cargo_metadata::Message;
-use mgba_log_reporter::{Level, Record};
+use mgba_log_reporter::{
+    check_sequence_numbers, compare, reassemble, Core, CrashDump, Difference, Level, MemoryDump,
+    Record, Report, TestOutcome, TestReport, WatchpointHit,
+};
+use regex::Regex;
 use std::{
     convert::AsRef,
+    env,
     io::BufReader,
+    ops::ControlFlow,
     path::Path,
-    process::{Command, Stdio},
+    process::{Command, Output, Stdio},
 };
 
 fn build_rom<P>(path: P) -> String
@@ -53,6 +59,15 @@ fn execute_rom(rom: &str) -> Vec<Record> {
         .expect("failed to deserialize output")
 }
 
+fn execute_rom_with_flags(rom: &str, flags: &[&str]) -> Output {
+    Command::new("cargo")
+        .args(["run", "--", rom])
+        .args(flags)
+        .current_dir("tests/mgba_log_reporter")
+        .output()
+        .expect("failed to run rom")
+}
+
 #[test]
 fn trace() {
     let rom = build_rom("tests/trace");
@@ -62,6 +77,19 @@ fn trace() {
     assert!(records.is_empty());
 }
 
+#[test]
+fn trace_as_debug() {
+    let rom = build_rom("tests/trace_as_debug");
+
+    let records = execute_rom(&rom);
+
+    assert!(records.contains(&Record {
+        level: Level::Debug,
+        message: "[TRACE] Hello, world!".to_owned(),
+        timing: None,
+    }));
+}
+
 #[test]
 fn debug() {
     let rom = build_rom("tests/debug");
@@ -71,6 +99,7 @@ fn debug() {
     assert!(records.contains(&Record {
         level: Level::Debug,
         message: "Hello, world!".to_owned(),
+        timing: None,
     }));
 }
 
@@ -83,7 +112,48 @@ fn info() {
     assert!(records.contains(&Record {
         level: Level::Info,
         message: "Hello, world!".to_owned(),
+        timing: None,
+    }));
+}
+
+#[test]
+fn core_api() {
+    let rom = build_rom("tests/info");
+
+    let mut core = Core::load(&rom).expect("failed to load rom");
+    while core.status_register() != 3 {
+        core.step();
+    }
+
+    assert!(core.drain_logs().contains(&Record {
+        level: Level::Info,
+        message: "Hello, world!".to_owned(),
+        timing: None,
     }));
+    assert_eq!(core.drain_logs(), vec![]);
+    assert_eq!(core.read_memory(0x0203FFFF, 1), vec![3]);
+}
+
+#[test]
+fn run_with() {
+    let rom = build_rom("tests/sequence_numbers");
+
+    // The run is stopped as soon as the first record is received.
+    let mut records = Vec::new();
+    let flow = mgba_log_reporter::run_with(&rom, |record| {
+        records.push(record);
+        ControlFlow::Break(())
+    });
+
+    assert_eq!(flow, Some(ControlFlow::Break(())));
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Info,
+            message: "[#0] Hello, world!".to_owned(),
+            timing: None,
+        }]
+    );
 }
 
 #[test]
@@ -95,6 +165,7 @@ fn warn() {
     assert!(records.contains(&Record {
         level: Level::Warning,
         message: "Hello, world!".to_owned(),
+        timing: None,
     }));
 }
 
@@ -107,6 +178,7 @@ fn error() {
     assert!(records.contains(&Record {
         level: Level::Error,
         message: "Hello, world!".to_owned(),
+        timing: None,
     }));
 }
 
@@ -119,72 +191,2318 @@ fn fatal() {
     assert!(records.contains(&Record {
         level: Level::Fatal,
         message: "Hello, world!".to_owned(),
+        timing: None,
     }));
 }
 
 #[test]
-fn null() {
-    let rom = build_rom("tests/null");
+fn fatal_location() {
+    let rom = build_rom("tests/fatal_location");
 
     let records = execute_rom(&rom);
 
     assert!(records.contains(&Record {
-        level: Level::Info,
-        message: "\x1a".to_owned(),
+        level: Level::Fatal,
+        message: "src/main.rs:51: Hello, world!".to_owned(),
+        timing: None,
     }));
 }
 
 #[test]
-fn new_line() {
-    let rom = build_rom("tests/new_line");
+fn fatal_fixed() {
+    let rom = build_rom("tests/fatal_fixed");
 
     let records = execute_rom(&rom);
 
-    assert!(records.contains(&Record {
-        level: Level::Info,
-        message: "Hello,".to_owned(),
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Fatal,
+            message: "frame 42 ééééééééé…".to_owned(),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn deferred_interrupt_logs() {
+    let rom = build_rom("tests/deferred_interrupt_logs");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "direct".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "interrupt 0".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "main".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "interrupt 1".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn init_with_level() {
+    let rom = build_rom("tests/init_with_level");
+
+    let records = execute_rom(&rom);
+
+    assert!(!records.contains(&Record {
+        level: Level::Debug,
+        message: "Hello, debug!".to_owned(),
+        timing: None,
     }));
     assert!(records.contains(&Record {
         level: Level::Info,
-        message: "world!".to_owned(),
+        message: "Hello, info!".to_owned(),
+        timing: None,
     }));
 }
 
 #[test]
-fn overflow() {
-    let rom = build_rom("tests/overflow");
+fn init_scoped() {
+    let rom = build_rom("tests/init_scoped");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Info,
+            message: "Hello, world!".to_owned(),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn buffered_formatting() {
+    let rom = build_rom("tests/buffered_formatting");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "IME: true".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "first".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "second".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuv".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "IME: true".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn builder() {
+    let rom = build_rom("tests/builder");
 
     let records = execute_rom(&rom);
 
+    assert!(!records.iter().any(|record| record.level == Level::Debug));
     assert!(records.contains(&Record {
         level: Level::Info,
-        message: "abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuv".to_owned(),
+        message: "custom: Hello, info! (src/main.rs:26)".to_owned(),
+        timing: None,
     }));
+}
+
+#[test]
+fn reentrant() {
+    let rom = build_rom("tests/reentrant");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "outer: before after".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "inner".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "done".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn target_filter() {
+    let rom = build_rom("tests/target_filter");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "allowed by deny".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "allowed".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "cleared".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn directives() {
+    let rom = build_rom("tests/directives");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Warning,
+                message: "default level".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Debug,
+                message: "submodule".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Debug,
+                message: "bare target".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn try_init_or_noop() {
+    let rom = build_rom("tests/try_init_or_noop");
+
+    let records = execute_rom(&rom);
+
     assert!(records.contains(&Record {
         level: Level::Info,
-        message: "wxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyz".to_owned(),
+        message: "Hello, world!".to_owned(),
+        timing: None,
     }));
 }
 
 #[test]
-fn sync() {
-    let rom = build_rom("tests/sync");
+fn init_auto() {
+    let rom = build_rom("tests/init_auto");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Info,
+            message: "Mgba".to_owned(),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn sram_log() {
+    let rom = build_rom("tests/sram_log");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Warning,
+                message: "foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "bar".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Error,
+                message: "baz".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "[WARN] foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "[ERROR] baz".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn post_mortem() {
+    let rom = build_rom("tests/post_mortem");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Warning,
+                message: "foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "bar".to_owned(),
+                timing: None,
+            },
+            // Recovered after the reset.
+            Record {
+                level: Level::Warning,
+                message: "foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "bar".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn secondary_logger() {
+    let rom = build_rom("tests/secondary_logger");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Warning,
+                message: "foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "secondary: foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "bar".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "secondary: baz".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn interrupt_staging() {
+    let rom = build_rom("tests/interrupt_staging");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "a".repeat(100),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "interrupt".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "done".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn interrupts_disabled() {
+    let rom = build_rom("tests/interrupts_disabled");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(records.len(), 4);
+    let cycles = |record: &Record, name: &str| {
+        record
+            .message
+            .strip_prefix(name)
+            .and_then(|message| message.strip_prefix(": "))
+            .and_then(|message| message.strip_suffix(" cycles"))
+            .expect("unexpected time_scope message")
+            .parse::<u32>()
+            .expect("invalid cycle count")
+    };
+    let enabled = cycles(&records[1], "enabled");
+    let disabled = cycles(&records[3], "disabled");
+    // Logging while interrupts are already disabled skips writing to `IME`.
+    assert!(disabled < enabled);
+}
+
+#[test]
+fn iwram() {
+    let rom = build_rom("tests/iwram");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "first".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "second\x1athird".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn is_mgba() {
+    let rom = build_rom("tests/is_mgba");
 
     let records = execute_rom(&rom);
 
     assert!(records.contains(&Record {
         level: Level::Info,
-        message: "Hello, world!".to_owned(),
+        message: "is_mgba: true".to_owned(),
+        timing: None,
     }));
+}
+
+#[test]
+fn writer() {
+    let rom = build_rom("tests/writer");
+
+    let records = execute_rom(&rom);
+
     assert!(records.contains(&Record {
-        level: Level::Debug,
-        message: "in irq".to_owned(),
+        level: Level::Warning,
+        message: "Hello, world! 42".to_owned(),
+        timing: None,
     }));
-    // Synchronization issues will cause empty messages to be included in the output. This happens
-    // because the buffer is flushed before writing has finished, and mGBA then interprets the null
-    // character at the start of the buffer as the end of the message.
-    assert!(!records.contains(&Record {
-        level: Level::Info,
-        message: "".to_owned(),
-    }))
+}
+
+#[test]
+fn embedded_io_write() {
+    let rom = build_rom("tests/embedded_io_write");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Warning,
+                message: "Hello, world! 42".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "foobar".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn pause() {
+    let rom = build_rom("tests/pause");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "before".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "printed".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "after".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn write_speed() {
+    let rom = build_rom("tests/write_speed");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records
+            .into_iter()
+            .filter(|record| record.level == Level::Info)
+            .collect::<Vec<_>>(),
+        vec![
+            Record {
+                level: Level::Info,
+                message: "The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog.".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog.".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "faster: true".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn print() {
+    let rom = build_rom("tests/print");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "Hello, ".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "trailing".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn eprint() {
+    let rom = build_rom("tests/eprint");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Error,
+                message: "Hello, ".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Error,
+                message: "world!".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn assert() {
+    let rom = build_rom("tests/assert");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Fatal,
+            message: "assertion `left == right` failed (left: `2`, right: `3`)".to_owned(),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn critical_section() {
+    let rom = build_rom("tests/critical_section");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "acquired: true".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn interrupt_mask() {
+    let rom = build_rom("tests/interrupt_mask");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "acquired: true".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn dbg() {
+    let rom = build_rom("tests/dbg");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Debug,
+                message: "src/main.rs:20: 1 + 2 = 3".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "value: 3".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn hexdump() {
+    let rom = build_rom("tests/hexdump");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(records.len(), 2);
+    assert!(records.iter().all(|record| record.level == Level::Debug));
+    assert!(records[0]
+        .message
+        .ends_with(": 48 65 6c 6c 6f 2c 20 77 6f 72 6c 64 21 00 01 02  |Hello, world!...|"));
+    assert!(records[1]
+        .message
+        .ends_with(&format!(": 61 62 63 64{}  |abcd|", " ".repeat(36))));
+    // The second line starts sixteen bytes after the first.
+    let address = |message: &str| {
+        usize::from_str_radix(&message[..8], 16).expect("invalid address in hex dump")
+    };
+    assert_eq!(
+        address(&records[1].message),
+        address(&records[0].message) + 16
+    );
+}
+
+#[test]
+fn log_once() {
+    let rom = build_rom("tests/log_once");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "first: 0".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "second: 0".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn log_every_n() {
+    let rom = build_rom("tests/log_every_n");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "first: 0".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "second: 0".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "second: 1".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "second: 2".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "first: 3 (2 skipped)".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "second: 3".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "second: 4".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "second: 5".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "first: 6 (2 skipped)".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "second: 6".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn time_scope() {
+    let rom = build_rom("tests/time_scope");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(records.len(), 3);
+    let cycles = |record: &Record, name: &str| {
+        assert_eq!(record.level, Level::Debug);
+        record
+            .message
+            .strip_prefix(name)
+            .and_then(|message| message.strip_prefix(": "))
+            .and_then(|message| message.strip_suffix(" cycles"))
+            .expect("unexpected time_scope message")
+            .parse::<u32>()
+            .expect("invalid cycle count")
+    };
+    let inner = cycles(&records[0], "inner");
+    assert_eq!(
+        records[1],
+        Record {
+            level: Level::Info,
+            message: "between".to_owned(),
+            timing: None,
+        }
+    );
+    let outer = cycles(&records[2], "outer");
+    assert!(inner > 0);
+    assert!(outer > inner);
+}
+
+#[test]
+fn unreachable() {
+    let rom = build_rom("tests/unreachable");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Fatal,
+            message: "src/main.rs:25: internal error: entered unreachable code: invalid state: 3"
+                .to_owned(),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn ext() {
+    let rom = build_rom("tests/ext");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Error,
+                message: "\"bad\"".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "3 Err(\"bad\")".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn no_ime() {
+    let rom = build_rom("tests/no_ime");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Info,
+            message: "IME: true".to_owned(),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn log_from_irq() {
+    let rom = build_rom("tests/log_from_irq");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "direct".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "interrupt 0".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "main".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "interrupt 1".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn null() {
+    let rom = build_rom("tests/null");
+
+    let records = execute_rom(&rom);
+
+    assert!(records.contains(&Record {
+        level: Level::Info,
+        message: "\x1a".to_owned(),
+        timing: None,
+    }));
+}
+
+#[test]
+fn new_line() {
+    let rom = build_rom("tests/new_line");
+
+    let records = execute_rom(&rom);
+
+    assert!(records.contains(&Record {
+        level: Level::Info,
+        message: "Hello,".to_owned(),
+        timing: None,
+    }));
+    assert!(records.contains(&Record {
+        level: Level::Info,
+        message: "world!".to_owned(),
+        timing: None,
+    }));
+}
+
+#[test]
+fn overflow() {
+    let rom = build_rom("tests/overflow");
+
+    let records = execute_rom(&rom);
+
+    assert!(records.contains(&Record {
+        level: Level::Info,
+        message: "abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuv".to_owned(),
+        timing: None,
+    }));
+    assert!(records.contains(&Record {
+        level: Level::Info,
+        message: "wxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyz".to_owned(),
+        timing: None,
+    }));
+}
+
+#[test]
+fn sync() {
+    let rom = build_rom("tests/sync");
+
+    let records = execute_rom(&rom);
+
+    assert!(records.contains(&Record {
+        level: Level::Info,
+        message: "Hello, world!".to_owned(),
+        timing: None,
+    }));
+    assert!(records.contains(&Record {
+        level: Level::Debug,
+        message: "in irq".to_owned(),
+        timing: None,
+    }));
+    // Synchronization issues will cause empty messages to be included in the output. This happens
+    // because the buffer is flushed before writing has finished, and mGBA then interprets the null
+    // character at the start of the buffer as the end of the message.
+    assert!(!records.contains(&Record {
+        level: Level::Info,
+        message: "".to_owned(),
+        timing: None,
+    }))
+}
+
+#[test]
+fn ufmt_macros() {
+    let rom = build_rom("tests/ufmt_macros");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "frame 42".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Error,
+                message: "-1".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Debug,
+                message: "written 42".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "temporary 42".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn format_error() {
+    let rom = build_rom("tests/format_error");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "partial".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "after".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "format errors: 1".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn static_max_level() {
+    let rom = build_rom("tests/static_max_level");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "max level: INFO".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn disabled() {
+    let rom = build_rom("tests/disabled");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(records, vec![]);
+}
+
+#[test]
+fn int_fmt() {
+    let rom = build_rom("tests/int_fmt");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "0x04a0 0xffff".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "0x080004a0 0x00000000".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "0 1000000 4294967295".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn text_runs() {
+    let rom = build_rom("tests/text_runs");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "foo\x1abar".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "baz\x1a".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\x1a".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "b".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn utf8_boundary() {
+    let rom = build_rom("tests/utf8_boundary");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "éa".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "😀b".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn continuation_marker() {
+    let rom = build_rom("tests/continuation_marker");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa…".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "…aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+    assert_eq!(
+        reassemble(records, "…"),
+        vec![Record {
+            level: Level::Info,
+            message: "a".repeat(300),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn truncation() {
+    let rom = build_rom("tests/truncation");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa…".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb…".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "c".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn null_substitute() {
+    let rom = build_rom("tests/null_substitute");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Info,
+            message: "foo␀bar".to_owned(),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn escaped_control_bytes() {
+    let rom = build_rom("tests/escaped_control_bytes");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "foo\\x00bar\\x01\\x09\\x7f\\\\é".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "baz".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn carriage_return() {
+    let rom = build_rom("tests/carriage_return");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "bar".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "baz".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "qux".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "quux".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn tab_width() {
+    let rom = build_rom("tests/tab_width");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Info,
+            message: "a   bc  d       e".to_owned(),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn continuation_line_prefix() {
+    let rom = build_rom("tests/continuation_line_prefix");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "  | bar".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "  | baz".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "qux".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn log_bytes() {
+    let rom = build_rom("tests/log_bytes");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "foo\\x00\u{fffd}".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "bar".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn slice_fmt() {
+    let rom = build_rom("tests/slice_fmt");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "2e 00 00 ea 41 47 42 20  09 ff".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "2e 00 00 ea 41 47 42 20  09 ff  |....AGB ..|".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "[]".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn formatter() {
+    let rom = build_rom("tests/formatter");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "<INFO> Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "<WARN> foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "bar".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn key_values() {
+    let rom = build_rom("tests/key_values");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Info,
+            message: "Hello, world! (src/main.rs:23) frame=42 scene=title".to_owned(),
+            timing: None,
+        }]
+    );
+    assert_eq!(
+        records[0].key_values(),
+        vec![("frame", "42"), ("scene", "title")]
+    );
+}
+
+#[test]
+fn frame_timestamps() {
+    let rom = build_rom("tests/frame_timestamps");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "[frame 0] Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "[frame 2] foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "bar".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "[frame 2] baz".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn timestamps() {
+    let rom = build_rom("tests/timestamps");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(records.len(), 2);
+    // Timestamps are parsed as microseconds.
+    let micros = |record: &Record, message: &str| {
+        assert_eq!(record.level, Level::Info);
+        let (timestamp, rest) = record
+            .message
+            .strip_prefix('[')
+            .and_then(|message| message.split_once("ms] "))
+            .expect("missing timestamp");
+        assert_eq!(rest, message);
+        let (whole, fraction) = timestamp.split_once('.').expect("invalid timestamp");
+        assert_eq!(fraction.len(), 3);
+        format!("{whole}{fraction}")
+            .parse::<u32>()
+            .expect("invalid timestamp")
+    };
+    let first = micros(&records[0], "Hello, world!");
+    let second = micros(&records[1], "foo");
+    assert!(second > first);
+}
+
+#[test]
+fn golden() {
+    let rom = build_rom("tests/timestamps");
+
+    let records = execute_rom(&rom);
+
+    // The timestamps differ between runs, so they are normalized.
+    let normalize = [Regex::new(r"^\[\d+\.\d+ms\]").unwrap()];
+    let golden = |second: &str| {
+        vec![
+            Record {
+                level: Level::Info,
+                message: "[999999.999ms] Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: format!("[999999.999ms] {second}"),
+                timing: None,
+            },
+        ]
+    };
+    assert_eq!(compare(&golden("foo"), &records, &normalize), vec![]);
+    assert_eq!(
+        compare(&golden("bar"), &records, &normalize),
+        vec![Difference::Missing(1), Difference::Unexpected(1)]
+    );
+    assert_eq!(
+        compare(&golden("foo"), &records, &[]),
+        vec![
+            Difference::Missing(0),
+            Difference::Missing(1),
+            Difference::Unexpected(0),
+            Difference::Unexpected(1),
+        ]
+    );
+}
+
+#[test]
+fn sequence_numbers() {
+    let rom = build_rom("tests/sequence_numbers");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "[#0] Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "[#1] foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "bar".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "[#2] baz".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+    assert_eq!(check_sequence_numbers(&records), vec![]);
+}
+
+#[test]
+fn stats() {
+    let rom = build_rom("tests/stats");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records[4..],
+        [
+            Record {
+                level: Level::Info,
+                message: "info: 1 messages, 13 bytes, 0 truncated, 0 dropped".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "warning: 1 messages, 255 bytes, 1 truncated, 0 dropped".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "debug: 2 messages, 6 bytes, 0 truncated, 0 dropped".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "total: 4 messages, 274 bytes, 1 truncated, 0 dropped".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn dump_io_registers() {
+    let rom = build_rom("tests/dump_io_registers");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].level, Level::Debug);
+    assert!(records[0].message.starts_with("DISPCNT=0x0403 DISPSTAT=0x"));
+    assert_eq!(
+        records[1],
+        Record {
+            level: Level::Debug,
+            message: "BG0CNT=0x0100 BG1CNT=0x0000 BG2CNT=0x0000 BG3CNT=0x0000".to_owned(),
+            timing: None,
+        }
+    );
+}
+
+#[test]
+fn heap_stats() {
+    let rom = build_rom("tests/heap_stats");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Debug,
+                message: "heap: used=768 free=256 largest_free=64 fragmentation=75%".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "heap: used=1000 free=24 largest_free=24 fragmentation=0%".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn stack_usage() {
+    let rom = build_rom("tests/stack_usage");
+
+    let records = execute_rom(&rom);
+
+    // The exact usage depends on the compiler, so only its bounds are checked.
+    let usages: Vec<(usize, usize)> = records
+        .iter()
+        .map(|record| {
+            assert_eq!(record.level, Level::Debug);
+            let (used, free) = record
+                .message
+                .strip_prefix("stack: used=")
+                .and_then(|usage| usage.split_once(" free="))
+                .expect("unexpected message");
+            (used.parse().unwrap(), free.parse().unwrap())
+        })
+        .collect();
+    assert_eq!(usages.len(), 2);
+    for (used, free) in &usages {
+        assert_eq!(used + free, 0x1f00);
+        assert!(*free > 0);
+    }
+    assert!(usages[0].0 < 4096);
+    assert!(usages[1].0 > 4096);
+}
+
+#[test]
+fn scope() {
+    let rom = build_rom("tests/scope");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Debug,
+                message: "enter outer".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "  Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Debug,
+                message: "  enter inner".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "    foo".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "bar".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Debug,
+                message: "  exit inner".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Debug,
+                message: "exit outer".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "baz".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn instrument() {
+    let rom = build_rom("tests/instrument");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Debug,
+                message: "enter update(frame=42, name=\"level 1\")".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "  updating".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Debug,
+                message: "  enter physics".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Debug,
+                message: "  exit physics".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Debug,
+                message: "exit update".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "frame 43".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn panic_handler() {
+    let rom = build_rom("tests/panic_handler");
+
+    let records = execute_rom(&rom);
+
+    // The register dump and the stack are logged at the error level before the panic message.
+    let (panic, dump) = records.split_last().expect("no records");
+    assert_eq!(
+        panic,
+        &Record {
+            level: Level::Fatal,
+            message: "panicked at 'Hello, world!', src/main.rs:43:5".to_owned(),
+            timing: None,
+        }
+    );
+    assert!(dump.iter().all(|record| record.level == Level::Error));
+    let lines = dump
+        .iter()
+        .map(|record| record.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(lines.len(), 9);
+    for (line, names) in lines.iter().zip([
+        ["r0", "r1", "r2", "r3"].as_slice(),
+        &["r4", "r5", "r6", "r7"],
+        &["r8", "r9", "r10", "r11"],
+        &["r12", "sp", "lr", "pc"],
+        &["cpsr"],
+    ]) {
+        let registers = line.split(' ').collect::<Vec<_>>();
+        assert_eq!(registers.len(), names.len());
+        for (register, name) in registers.into_iter().zip(names) {
+            let value = register
+                .strip_prefix(&format!("{name}=0x"))
+                .expect("missing register");
+            assert_eq!(value.len(), 8);
+        }
+    }
+    // The stack is in IWRAM, starting at the stack pointer.
+    let sp = lines[3].split(' ').nth(1).expect("missing stack pointer");
+    assert_eq!(lines[5].split(':').next(), sp.strip_prefix("sp="));
+    assert!(lines[5..].iter().all(|line| line.starts_with("0x0300")));
+}
+
+#[test]
+fn entry() {
+    let rom = build_rom("tests/entry");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records[..2],
+        [
+            Record {
+                level: Level::Info,
+                message: "entry v0.1.0".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "Hello, world!".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+    // The panic handler is installed by the `entry` feature.
+    let (panic, dump) = records[2..].split_last().expect("no records");
+    assert_eq!(
+        panic,
+        &Record {
+            level: Level::Fatal,
+            message: "panicked at 'Goodbye, world!', src/main.rs:44:5".to_owned(),
+            timing: None,
+        }
+    );
+    assert_eq!(dump.len(), 9);
+    assert!(dump.iter().all(|record| record.level == Level::Error));
+}
+
+#[test]
+fn test_runner() {
+    let rom = build_rom("tests/test_runner");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "running 2 tests".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "TEST test_runner::addition ... ok".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "TEST test_runner::logging ... ok".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Fatal,
+                message: "test result: ok. 2 passed; 0 failed".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+    let report = TestReport::from_records(&records).expect("no test report");
+    assert!(report.passed());
+    assert_eq!(report.outcomes.len(), 2);
+}
+
+#[test]
+fn test_runner_failure() {
+    let rom = build_rom("tests/test_runner_failure");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records[..4],
+        [
+            Record {
+                level: Level::Info,
+                message: "running 3 tests".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "TEST test_runner_failure::addition ... ok".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Error,
+                message: "TEST test_runner_failure::failure ... FAILED".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Error,
+                message: "test result: FAILED. 1 passed; 1 failed; 1 not run".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+    assert_eq!(
+        records.last(),
+        Some(&Record {
+            level: Level::Fatal,
+            message: "panicked at 'wrong answer', src/main.rs:12:5".to_owned(),
+            timing: None,
+        })
+    );
+    let report = TestReport::from_records(&records).expect("no test report");
+    assert!(!report.passed());
+    assert_eq!(
+        report.outcomes,
+        vec![
+            TestOutcome {
+                name: "test_runner_failure::addition".to_owned(),
+                passed: true,
+            },
+            TestOutcome {
+                name: "test_runner_failure::failure".to_owned(),
+                passed: false,
+            },
+        ]
+    );
+}
+
+#[test]
+fn crash_dump() {
+    let rom = build_rom("tests/crash_dump");
+
+    let records = execute_rom(&rom);
+
+    // The crash dump is logged at the error level before the panic message.
+    let (panic, dump) = records.split_last().expect("no records");
+    assert_eq!(
+        panic,
+        &Record {
+            level: Level::Fatal,
+            message: "panicked at 'Hello, world!', src/main.rs:46:5".to_owned(),
+            timing: None,
+        }
+    );
+    assert_eq!(dump.len(), 3);
+    let dump = CrashDump::from_records(dump).expect("no crash dump");
+    assert_eq!(dump.build_id, 0x1234_5678);
+    // The stack is in IWRAM, starting at the stack pointer.
+    assert_eq!(dump.registers[13] & 0xffff_8000, 0x0300_0000);
+    assert_eq!(dump.stack.len(), 16);
+}
+
+#[test]
+fn backtrace() {
+    let rom = build_rom("tests/backtrace");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].level, Level::Error);
+    // The reporter annotates each return address with the function it returns into, which are
+    // listed from the innermost call outwards.
+    let backtrace = &records[0].message;
+    assert!(backtrace.starts_with("backtrace: 0x"));
+    let position = |function: &str| {
+        backtrace
+            .find(&format!(" ({function}+0x"))
+            .expect("missing function")
+    };
+    assert!(position("backtrace::inner") < position("backtrace::outer"));
+    assert!(position("backtrace::outer") < position("main"));
+    assert_eq!(
+        records[1],
+        Record {
+            level: Level::Fatal,
+            message: "Hello, world!".to_owned(),
+            timing: None,
+        }
+    );
+}
+
+#[test]
+fn allocator() {
+    let rom = build_rom("tests/allocator");
+
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Error,
+                message:
+                    "allocation failed: size=2000 align=1 allocated=100 allocations=1 peak=100 free=924"
+                        .to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Info,
+                message: "recovered from failed allocation".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn assertion_status() {
+    let rom = build_rom("tests/assertion_status");
+
+    // The test only finishes if the failed assertion wrote its status.
+    let records = execute_rom(&rom);
+
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Fatal,
+            message: "assertion `left == right` failed (left: `2`, right: `3`)".to_owned(),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn max_frames() {
+    let rom = build_rom("tests/info");
+
+    // The ROM never writes this status, so it runs until it is stopped.
+    let output = execute_rom_with_flags(&rom, &["--stop-on-status", "7", "--max-frames", "10"]);
+    let records: Vec<Record> =
+        serde_json::from_slice(&output.stdout).expect("failed to deserialize output");
+
+    assert_eq!(output.status.code(), Some(124));
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Timeout,
+                message: "timed out after 10 frames".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn timeout_secs() {
+    let rom = build_rom("tests/info");
+
+    // The ROM never writes this status, so it runs until it is stopped.
+    let output = execute_rom_with_flags(&rom, &["--stop-on-status", "7", "--timeout-secs", "1"]);
+    let records: Vec<Record> =
+        serde_json::from_slice(&output.stdout).expect("failed to deserialize output");
+
+    assert_eq!(output.status.code(), Some(124));
+    assert_eq!(
+        records.last(),
+        Some(&Record {
+            level: Level::Timeout,
+            message: "timed out after 1 seconds".to_owned(),
+            timing: None,
+        })
+    );
+}
+
+#[test]
+fn stop_on_status() {
+    let rom = build_rom("tests/sequence_numbers");
+
+    let output = execute_rom_with_flags(&rom, &["--stop-on-status", "3"]);
+    let records: Vec<Record> =
+        serde_json::from_slice(&output.stdout).expect("failed to deserialize output");
+
+    assert!(output.status.success());
+    assert_eq!(
+        records.last(),
+        Some(&Record {
+            level: Level::Info,
+            message: "[#2] baz".to_owned(),
+            timing: None,
+        })
+    );
+}
+
+#[test]
+fn stop_on_fatal() {
+    let rom = build_rom("tests/fatal");
+
+    // The ROM is stopped before the status it writes at the next vblank.
+    let output = execute_rom_with_flags(&rom, &["--stop-on-fatal"]);
+    let records: Vec<Record> =
+        serde_json::from_slice(&output.stdout).expect("failed to deserialize output");
+
+    assert!(output.status.success());
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Fatal,
+            message: "Hello, world!".to_owned(),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn stop_on_message() {
+    let rom = build_rom("tests/sequence_numbers");
+
+    let output = execute_rom_with_flags(&rom, &["--stop-on-message", "[#1] foo"]);
+    let records: Vec<Record> =
+        serde_json::from_slice(&output.stdout).expect("failed to deserialize output");
+
+    assert!(output.status.success());
+    assert_eq!(
+        records,
+        vec![
+            Record {
+                level: Level::Info,
+                message: "[#0] Hello, world!".to_owned(),
+                timing: None,
+            },
+            Record {
+                level: Level::Warning,
+                message: "[#1] foo".to_owned(),
+                timing: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn stop_after_frames() {
+    let rom = build_rom("tests/info");
+
+    // Unlike `--max-frames`, running out of frames is not a failure.
+    let output = execute_rom_with_flags(&rom, &["--stop-after-frames", "10"]);
+    let records: Vec<Record> =
+        serde_json::from_slice(&output.stdout).expect("failed to deserialize output");
+
+    assert!(output.status.success());
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Info,
+            message: "Hello, world!".to_owned(),
+            timing: None,
+        }]
+    );
+}
+
+#[test]
+fn dump() {
+    let rom = build_rom("tests/info");
+
+    let output = execute_rom_with_flags(&rom, &["--dump", "0x0203FFFF:1"]);
+    let report: Report =
+        serde_json::from_slice(&output.stdout).expect("failed to deserialize output");
+
+    assert!(output.status.success());
+    assert!(report.records.contains(&Record {
+        level: Level::Info,
+        message: "Hello, world!".to_owned(),
+        timing: None,
+    }));
+    assert_eq!(
+        report.memory,
+        vec![MemoryDump {
+            address: 0x0203FFFF,
+            bytes: vec![3],
+        }]
+    );
+    assert_eq!(report.watchpoint, None);
+}
+
+#[test]
+fn save_states() {
+    let rom = build_rom("tests/info");
+    let state = env::temp_dir().join("mgba_log_save_states.ss0");
+    let state = state.to_str().expect("invalid save state path");
+
+    let output = execute_rom_with_flags(&rom, &["--save-state-on-exit", state]);
+    let records: Vec<Record> =
+        serde_json::from_slice(&output.stdout).expect("failed to deserialize output");
+
+    assert!(output.status.success());
+    assert_eq!(
+        records,
+        vec![Record {
+            level: Level::Info,
+            message: "Hello, world!".to_owned(),
+            timing: None,
+        }]
+    );
+
+    // The ROM resumes after it has already logged its message and finished.
+    let output = execute_rom_with_flags(&rom, &["--load-state", state]);
+    let records: Vec<Record> =
+        serde_json::from_slice(&output.stdout).expect("failed to deserialize output");
+
+    assert!(output.status.success());
+    assert_eq!(records, vec![]);
+}
+
+#[test]
+fn fail_on_level() {
+    let rom = build_rom("tests/warn");
+
+    let output = execute_rom_with_flags(&rom, &["--fail-on-level", "warn"]);
+    let records: Vec<Record> =
+        serde_json::from_slice(&output.stdout).expect("failed to deserialize output");
+
+    // The results are still output before the failure is reported.
+    assert_eq!(output.status.code(), Some(1));
+    assert!(records.contains(&Record {
+        level: Level::Warning,
+        message: "Hello, world!".to_owned(),
+        timing: None,
+    }));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("message logged at a failing level: [Warning] Hello, world!"));
+}
+
+#[test]
+fn break_on_write() {
+    let rom = build_rom("tests/info");
+
+    let output = execute_rom_with_flags(&rom, &["--break-on-write", "0x0203FFFF"]);
+    let report: Report =
+        serde_json::from_slice(&output.stdout).expect("failed to deserialize output");
+
+    // Hitting a watchpoint is not a failure.
+    assert!(output.status.success());
+    let hello = Record {
+        level: Level::Info,
+        message: "Hello, world!".to_owned(),
+        timing: None,
+    };
+    assert_eq!(report.records, vec![hello.clone()]);
+    assert_eq!(report.memory, vec![]);
+    let WatchpointHit {
+        address,
+        before,
+        after,
+        log_tail,
+        ..
+    } = report.watchpoint.expect("watchpoint was not hit");
+    assert_eq!(address, 0x0203FFFF);
+    assert_eq!(before, vec![0]);
+    assert_eq!(after, vec![3]);
+    assert_eq!(log_tail, vec![hello]);
 }
@@ -0,0 +1,82 @@
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+/// The address of the interrupt handler called by the BIOS.
+const IRQ_HANDLER: VolAddress<unsafe extern "C" fn(), Safe, Safe> =
+    unsafe { VolAddress::new(0x0300_7FFC) };
+/// Timer 0 reload value.
+const TM0CNT_L: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0100) };
+/// Timer 0 control.
+const TM0CNT_H: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0102) };
+/// Interrupt enable.
+const IE: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0200) };
+/// Interrupt request flags.
+const IF: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0202) };
+/// Interrupt master enable.
+const IME: VolAddress<bool, Safe, Safe> = unsafe { VolAddress::new(0x0400_0208) };
+
+/// The interrupt flag for timer 0.
+const TIMER_0: u16 = 0x0008;
+
+extern "C" {
+    /// Defined in `rsrt0.s`.
+    fn irq_handler();
+}
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+/// The number of interrupts that have been handled.
+///
+/// This is stored in otherwise unused EWRAM, as atomic types are not available on the Game Boy
+/// Advance.
+const INTERRUPTS: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203_FF00) };
+
+#[no_mangle]
+pub extern "C" fn on_interrupt() {
+    TM0CNT_H.write(0);
+    IF.write(TIMER_0);
+    let interrupts = INTERRUPTS.read();
+    mgba_log::log_from_irq(log::Level::Info, format_args!("interrupt {}", interrupts));
+    INTERRUPTS.write(interrupts + 1);
+}
+
+/// Triggers a timer interrupt, waiting until it has been handled.
+fn interrupt() {
+    let interrupts = INTERRUPTS.read();
+    TM0CNT_L.write(0xFF00);
+    // Enable the timer with an interrupt on overflow.
+    TM0CNT_H.write(0x00C0);
+    while INTERRUPTS.read() == interrupts {}
+}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+
+    IRQ_HANDLER.write(irq_handler);
+    IE.write(TIMER_0);
+    IME.write(true);
+
+    interrupt();
+    // Printing directly does not send deferred records.
+    mgba_log::println!("direct");
+    // Logging sends deferred records first.
+    log::info!("main");
+    interrupt();
+    mgba_log::flush();
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
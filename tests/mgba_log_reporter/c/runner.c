@@ -2,21 +2,27 @@
 
 #include <mgba/core/core.h>
 #include <mgba/core/log.h>
+#include <mgba/core/serialize.h>
 #include <mgba/internal/gba/gba.h>
 #include <mgba/internal/gba/io.h>
+#include <mgba-util/vfs.h>
+#include <fcntl.h>
 #include <stdio.h>
 
 struct MGBA {
-    struct mLogger logger;
     struct mCore* core;
     struct callback log_callback;
 };
 
+// mGBA only has a single default logger, so logs are routed to the instance that was last run on
+// the thread they were logged from. This allows separate instances to run on separate threads, or
+// to take turns on the same thread.
+static _Thread_local struct MGBA* current_mgba = NULL;
+
 void log_catcher(struct mLogger* logger, int category, enum mLogLevel level, const char* format, va_list args) {
-    // This is a safe cast, because the logger is the first entry in MGBA.
-    struct MGBA* mgba = (struct MGBA*)logger;
+    struct MGBA* mgba = current_mgba;
     
-    if (!strcmp(mLogCategoryName(category), "GBA Debug")) {
+    if (mgba && !strcmp(mLogCategoryName(category), "GBA Debug")) {
         int32_t size = 0;
 
         va_list args_copy;
@@ -30,7 +36,9 @@ void log_catcher(struct mLogger* logger, int category, enum mLogLevel level, con
         vsnprintf(str, size, format, args);
 
         if (mgba->log_callback.callback != NULL) {
-            mgba->log_callback.callback(mgba->log_callback.data, str, level);
+            uint32_t frame = mgba->core->frameCounter(mgba->core);
+            uint64_t cycles = mTimingGlobalTime(&((struct GBA*)mgba->core->board)->timing);
+            mgba->log_callback.callback(mgba->log_callback.data, str, level, frame, cycles);
         } else {
             printf("log_callback not set\n");
         }
@@ -40,13 +48,15 @@ void log_catcher(struct mLogger* logger, int category, enum mLogLevel level, con
 }
 
 struct MGBA* load(char* rom) {
-    struct MGBA* mgba = calloc(1, sizeof(struct MGBA));
+    static struct mLogger logger = { .log = log_catcher };
+    mLogSetDefaultLogger(&logger);
 
-    mgba->logger.log = log_catcher;
-    mLogSetDefaultLogger(&mgba->logger);
+    struct MGBA* mgba = calloc(1, sizeof(struct MGBA));
+    current_mgba = mgba;
 
     struct mCore* core = mCoreFind(rom);
     if (!core) {
+        current_mgba = NULL;
         free(mgba);
         return NULL;
     }
@@ -63,17 +73,65 @@ void set_log_callback(struct MGBA* mgba, struct callback callback) {
     mgba->log_callback = callback;
 }
 
-bool is_finished(struct MGBA* mgba) {
-    uint8_t status_register = ((uint8_t*)((struct GBA*)(mgba->core->board))->memory.wram)[0x3FFFF];
-    return status_register == 3;
+uint8_t status_register(struct MGBA* mgba) {
+    return ((uint8_t*)((struct GBA*)(mgba->core->board))->memory.wram)[0x3FFFF];
+}
+
+uint32_t frame_count(struct MGBA* mgba) {
+    return mgba->core->frameCounter(mgba->core);
+}
+
+uint64_t cycle_count(struct MGBA* mgba) {
+    return mTimingGlobalTime(&((struct GBA*)mgba->core->board)->timing);
+}
+
+uint8_t read_memory(struct MGBA* mgba, uint32_t address) {
+    return mgba->core->rawRead8(mgba->core, address, -1);
+}
+
+void set_keys(struct MGBA* mgba, uint16_t keys) {
+    mgba->core->setKeys(mgba->core, keys);
+}
+
+// The parts of the emulator's state that are saved and loaded, which are compatible with the mGBA
+// GUI. Screenshots are left out, as no video buffer is set.
+#define STATE_FLAGS (SAVESTATE_SAVEDATA | SAVESTATE_RTC | SAVESTATE_METADATA)
+
+bool load_state(struct MGBA* mgba, char* path) {
+    current_mgba = mgba;
+    struct VFile* vf = VFileOpen(path, O_RDONLY);
+    if (!vf) {
+        return false;
+    }
+    bool loaded = mCoreLoadStateNamed(mgba->core, vf, STATE_FLAGS);
+    vf->close(vf);
+    return loaded;
+}
+
+bool save_state(struct MGBA* mgba, char* path) {
+    struct VFile* vf = VFileOpen(path, O_CREAT | O_TRUNC | O_WRONLY);
+    if (!vf) {
+        return false;
+    }
+    bool saved = mCoreSaveStateNamed(mgba->core, vf, STATE_FLAGS);
+    vf->close(vf);
+    return saved;
 }
 
 void step(struct MGBA* mgba) {
+    current_mgba = mgba;
     mgba->core->step(mgba->core);
 }
 
+void run_frame(struct MGBA* mgba) {
+    current_mgba = mgba;
+    mgba->core->runFrame(mgba->core);
+}
+
 void drop(struct MGBA* mgba) {
+    current_mgba = mgba;
     mgba->core->deinit(mgba->core);
     mgba->log_callback.destroy(mgba->log_callback.data);
+    current_mgba = NULL;
     free(mgba);
 }
@@ -13,20 +13,42 @@ struct MGBA* load(char* rom);
 
 struct callback {
     void* data;
-    void (*callback)(void*, char[], uint8_t);
+    // Called with the message, its level, and the frame and CPU cycle it was logged at.
+    void (*callback)(void*, char[], uint8_t, uint32_t, uint64_t);
     void (*destroy)(void*);
 };
 
 // Sets a function to be called when logs are received.
 void set_log_callback(struct MGBA* mgba, struct callback callback);
 
-// Reports whether the ROM processing has finished.
+// Returns the value of the status register.
 //
-// This is reported by the ROM itself by writing the value `3` to `0x0203FFFF`.
-bool is_finished(struct MGBA* mgba);
+// This is written by the ROM itself to `0x0203FFFF`, usually to report that it has finished.
+uint8_t status_register(struct MGBA* mgba);
+
+// Returns the number of frames emulated so far.
+uint32_t frame_count(struct MGBA* mgba);
+
+// Returns the number of CPU cycles emulated so far.
+uint64_t cycle_count(struct MGBA* mgba);
+
+// Reads a byte of emulated memory, without the side effects of reading it from the ROM.
+uint8_t read_memory(struct MGBA* mgba, uint32_t address);
+
+// Sets the keys currently held, with each bit set for a held key, in the order of `KEYINPUT`.
+void set_keys(struct MGBA* mgba, uint16_t keys);
+
+// Loads the save state at the provided path, returning whether it was loaded.
+bool load_state(struct MGBA* mgba, char* path);
+
+// Writes a save state to the provided path, returning whether it was written.
+bool save_state(struct MGBA* mgba, char* path);
 
 // Advance emulation by a single step.
 void step(struct MGBA* mgba);
 
+// Advance emulation to the end of the current frame.
+void run_frame(struct MGBA* mgba);
+
 // Free the mGBA instance.
 void drop(struct MGBA* mgba);
@@ -0,0 +1,23 @@
+//! This binary decodes a crash dump written by `mgba_log`'s `crash-dump` feature to cartridge SRAM,
+//! as recovered from a save file.
+//!
+//! The path to the save file is passed as the first argument. If the path to the ROM's ELF file is
+//! passed as the second argument, return addresses are resolved to the functions they return into.
+
+use mgba_log_reporter::{CrashDump, Symbols};
+use std::{env, fs, process::exit};
+
+fn main() {
+    let save = env::args().nth(1).expect("no save filename provided");
+    let save = fs::read(save).expect("could not read save file");
+    let symbols = env::args().nth(2).map(|elf| {
+        Symbols::from_elf(&fs::read(elf).expect("could not read ELF file"))
+            .expect("could not parse ELF file")
+    });
+
+    let Some(dump) = CrashDump::from_save(&save) else {
+        eprintln!("no crash dump found");
+        exit(1);
+    };
+    print!("{}", dump.pretty(symbols.as_ref()));
+}
@@ -0,0 +1,20 @@
+//! This binary decodes the output of `mgba_log`'s UART backend, as captured from a real Game Boy
+//! Advance through a USB link cable adapter.
+//!
+//! The captured bytes are read from standard input, for example by piping in the serial device.
+//! Each decoded record is output as serialized JSON on its own line as soon as it is received.
+
+use mgba_log_reporter::Record;
+use std::io::{stdin, stdout, BufRead, Write};
+
+fn main() {
+    let mut output = stdout().lock();
+    for line in stdin().lock().split(b'\n') {
+        let line = line.expect("could not read input");
+        if let Some(record) = Record::from_prefixed_line(&String::from_utf8_lossy(&line)) {
+            serde_json::to_writer(&mut output, &record).expect("could not serialize record");
+            writeln!(output).expect("could not write output");
+            output.flush().expect("could not write output");
+        }
+    }
+}
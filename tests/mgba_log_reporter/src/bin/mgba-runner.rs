@@ -0,0 +1,92 @@
+//! This binary runs a GBA ROM in a headless instance of mGBA, printing the logs emitted by that ROM
+//! to the terminal as they are received.
+//!
+//! It is meant to be used as a cargo runner, by setting `runner = "mgba-runner"` for the
+//! `thumbv4t-none-eabi` target in `.cargo/config.toml`, which allows `cargo run` and `cargo test`
+//! to run ROMs directly. Each record is printed with its level, as in `[WARN] Hello, world!`, and
+//! is colored unless standard output is not a terminal or `NO_COLOR` is set.
+//!
+//! The ROM runs until it writes `3` to the status register at `0x0203FFFF`, in which case this
+//! exits successfully, or until it logs a fatal message. A fatal message ends the run with a
+//! failure status, unless it is the summary of a passing test run from `mgba_log`'s `test-runner`
+//! feature. Failing test runs exit with `101`, as `cargo test` does.
+
+// Not every stop condition is used by this binary.
+#[allow(dead_code)]
+#[path = "../emulator.rs"]
+mod emulator;
+
+use emulator::{InputScript, Limits, SaveStates, Stepping, StopCondition};
+use mgba_log_reporter::{symbolicate, CrashDump, Level, Symbols, TestReport};
+use std::{
+    env, fs,
+    io::{stdout, IsTerminal, Write},
+    path::Path,
+    process,
+};
+
+/// Returns the label and ANSI color code that records at `level` are printed with.
+fn style(level: &Level) -> (&'static str, &'static str) {
+    match level {
+        Level::Fatal => ("FATAL", "1;31"),
+        Level::Error => ("ERROR", "31"),
+        Level::Warning => ("WARN", "33"),
+        Level::Info => ("INFO", "32"),
+        Level::Debug => ("DEBUG", "34"),
+        Level::Timeout => ("TIMEOUT", "1;35"),
+    }
+}
+
+fn main() {
+    // Cargo passes any further arguments after the ROM, which the ROM has no way of receiving.
+    let rom = env::args().nth(1).expect("no gba rom filename provided");
+    if !Path::new(&rom).exists() {
+        panic!("{} does not exist", rom);
+    }
+    let symbols = fs::read(&rom).ok().and_then(|elf| Symbols::from_elf(&elf));
+    let color = stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+
+    let mut records = Vec::new();
+    let mut halted = false;
+    emulator::run(
+        &rom,
+        &[StopCondition::FINISHED],
+        Limits::default(),
+        &[],
+        &[],
+        &InputScript::default(),
+        &SaveStates::default(),
+        Stepping::Instruction,
+        |record| {
+            let record = match &symbols {
+                Some(symbols) => symbolicate(vec![record], symbols).remove(0),
+                None => record,
+            };
+            let (label, code) = style(&record.level);
+            let mut output = stdout().lock();
+            if color {
+                writeln!(output, "\x1b[{code}m[{label}]\x1b[0m {}", record.message)
+            } else {
+                writeln!(output, "[{label}] {}", record.message)
+            }
+            .and_then(|()| output.flush())
+            .expect("could not write output");
+
+            halted = record.level == Level::Fatal;
+            records.push(record);
+            halted
+        },
+    );
+
+    if let Some(dump) = CrashDump::from_records(&records) {
+        eprint!("{}", dump.pretty(symbols.as_ref()));
+    }
+    if let Some(report) = TestReport::from_records(&records) {
+        eprintln!("{}", report.summary);
+        if !report.passed() {
+            process::exit(101);
+        }
+    } else if halted {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,306 @@
+//! Running ROMs in an instance of mGBA, capturing the logs they emit.
+//!
+//! This is shared by the binaries of this crate, which each include it as a module.
+
+use mgba_log_reporter::{Core, Level, MemoryDump, Record, WatchpointHit};
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// A condition under which a ROM is finished running.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StopCondition {
+    /// The ROM wrote the given value to the status register at `0x0203FFFF`.
+    Status(u8),
+    /// The ROM logged a message at the fatal level.
+    Fatal,
+    /// The ROM logged exactly the given message, at any level.
+    Message(String),
+    /// The given number of frames were emulated.
+    Frames(u32),
+}
+
+impl StopCondition {
+    /// The condition ROMs are run until by default, which is the status register holding `3`.
+    pub const FINISHED: Self = Self::Status(3);
+
+    /// Returns whether logging `record` meets this condition.
+    fn is_met_by(&self, record: &Record) -> bool {
+        match self {
+            Self::Fatal => record.level == Level::Fatal,
+            Self::Message(message) => record.message == *message,
+            Self::Status(_) | Self::Frames(_) => false,
+        }
+    }
+}
+
+/// Limits on how long a ROM is run for.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    /// The number of frames after which the ROM is stopped, if any.
+    pub max_frames: Option<u32>,
+    /// The wall-clock time after which the ROM is stopped, if any.
+    pub timeout: Option<Duration>,
+}
+
+/// A limit that was exceeded, stopping the ROM before it finished.
+#[derive(Clone, Copy, Debug)]
+pub enum Exceeded {
+    /// The ROM ran for the given number of frames.
+    Frames(u32),
+    /// The ROM ran for the given wall-clock time.
+    Timeout(Duration),
+}
+
+impl Display for Exceeded {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Frames(frames) => write!(formatter, "timed out after {frames} frames"),
+            Self::Timeout(timeout) => {
+                write!(formatter, "timed out after {} seconds", timeout.as_secs())
+            }
+        }
+    }
+}
+
+/// How far emulation is advanced between checks of whether a ROM is finished.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Stepping {
+    /// A single instruction, stopping the ROM as soon as it is finished.
+    #[default]
+    Instruction,
+    /// A whole frame, which is much faster, as mGBA runs the frame without returning. The ROM
+    /// runs until the end of the frame it finished on, although records logged after it halted
+    /// are discarded.
+    Frame,
+}
+
+/// A region of emulated memory, read once a ROM stops running or watched while it runs.
+#[derive(Clone, Copy, Debug)]
+pub struct Region {
+    /// The address of the first byte.
+    pub address: u32,
+    /// The number of bytes.
+    pub len: u32,
+}
+
+/// The names of the keys, in the order of their bits in `KEYINPUT`.
+const KEYS: [&str; 10] = [
+    "A", "B", "SELECT", "START", "RIGHT", "LEFT", "UP", "DOWN", "R", "L",
+];
+
+/// Keys held while a ROM is running, scripted by the frame they are held from.
+#[derive(Clone, Debug, Default)]
+pub struct InputScript(Vec<(u32, u16)>);
+
+impl InputScript {
+    /// Parses a script of the keys held on each frame.
+    ///
+    /// Each line of the script is a frame number followed by the names of the keys held from
+    /// that frame on, separated by whitespace, until the frame of the next line. A line with no
+    /// keys releases all of them. The keys are `A`, `B`, `SELECT`, `START`, `RIGHT`, `LEFT`, `UP`,
+    /// `DOWN`, `R`, and `L`, in any case. Frames must be in increasing order. Blank lines, and
+    /// anything following a `#`, are ignored.
+    ///
+    /// No keys are held before the frame of the first line.
+    pub fn parse(script: &str) -> Result<Self, String> {
+        let mut changes = Vec::<(u32, u16)>::new();
+        for (index, line) in script.lines().enumerate() {
+            let line_number = index + 1;
+            let mut words = line
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace();
+            let Some(frame) = words.next() else {
+                continue;
+            };
+            let frame = frame
+                .parse()
+                .map_err(|_| format!("invalid frame {frame:?} on line {line_number}"))?;
+            if changes.last().is_some_and(|&(last, _)| frame <= last) {
+                return Err(format!(
+                    "frame {frame} on line {line_number} is out of order"
+                ));
+            }
+            let keys = words.try_fold(0, |keys, name| {
+                KEYS.iter()
+                    .position(|key| key.eq_ignore_ascii_case(name))
+                    .map(|bit| keys | 1 << bit)
+                    .ok_or_else(|| format!("unknown key {name:?} on line {line_number}"))
+            })?;
+            changes.push((frame, keys));
+        }
+        Ok(Self(changes))
+    }
+
+    /// Returns the keys held on `frame`, with a bit set for each held key.
+    fn keys_at(&self, frame: u32) -> u16 {
+        match self.0.partition_point(|&(from, _)| from <= frame) {
+            0 => 0,
+            index => self.0[index - 1].1,
+        }
+    }
+}
+
+/// Save states loaded before and written after running a ROM.
+#[derive(Clone, Debug, Default)]
+pub struct SaveStates {
+    /// The save state the ROM is resumed from, if any.
+    pub load: Option<PathBuf>,
+    /// The path the state of the ROM is saved to once it stops, if any.
+    pub save_on_exit: Option<PathBuf>,
+}
+
+/// The result of running a ROM.
+#[derive(Debug)]
+pub struct Outcome {
+    /// The limit that stopped the ROM before it finished, if any.
+    pub exceeded: Option<Exceeded>,
+    /// The contents of the requested regions of memory once the ROM stopped.
+    pub memory: Vec<MemoryDump>,
+    /// The change to watched memory that stopped the ROM, if any, without its log tail, which is
+    /// left to the caller.
+    pub watchpoint: Option<WatchpointHit>,
+}
+
+/// Runs the provided ROM file, passing each captured record to `on_record` as soon as it is
+/// logged.
+///
+/// The ROM is run until any of the `conditions` is met, or until `on_record` returns `true`,
+/// signaling that the ROM has halted. If it is still running once one of the `limits` is
+/// exceeded, it is stopped, and the exceeded limit is returned. Each of the `regions` of memory is
+/// read once the ROM has stopped. If any of the `watches` changes, the ROM is stopped, and the
+/// change is returned.
+///
+/// The keys held on each frame are set as scripted by `input`, from the start of that frame. If
+/// `states` includes a save state to load, the ROM is resumed from it, continuing from the frame
+/// it was saved on. If it includes a path to save to, the state of the ROM is saved there once it
+/// stops.
+///
+/// Emulation is advanced by `stepping` at a time, checking the status register and the watched
+/// regions in between. Watched regions should therefore only be given when stepping by
+/// instruction, as a change reverted within a frame is otherwise missed.
+///
+/// # Panics
+/// Panics if a save state could not be loaded or saved.
+#[allow(clippy::too_many_arguments)]
+pub fn run<F>(
+    rom: &str,
+    conditions: &[StopCondition],
+    limits: Limits,
+    regions: &[Region],
+    watches: &[Region],
+    input: &InputScript,
+    states: &SaveStates,
+    stepping: Stepping,
+    mut on_record: F,
+) -> Outcome
+where
+    F: FnMut(Record) -> bool,
+{
+    let mut core = Core::load(rom).expect("could not initialize mgba core");
+    if let Some(path) = &states.load {
+        if !core.load_state(path) {
+            panic!("could not load save state");
+        }
+    }
+
+    // Execute ROM.
+    let mut halted = deliver(&mut core, conditions, &mut on_record);
+    let start = Instant::now();
+    let mut frame = core.frame_count();
+    core.set_keys(input.keys_at(frame));
+    let mut exceeded = None;
+    let mut watchpoint = None;
+    let watched = watches
+        .iter()
+        .map(|region| core.read_memory(region.address, region.len))
+        .collect::<Vec<_>>();
+    while !halted {
+        if conditions.contains(&StopCondition::Status(core.status_register())) {
+            break;
+        }
+        match stepping {
+            Stepping::Instruction => core.step(),
+            Stepping::Frame => core.run_frames(1),
+        }
+        halted = deliver(&mut core, conditions, &mut on_record);
+        watchpoint = watches.iter().zip(&watched).find_map(|(region, before)| {
+            let after = core.read_memory(region.address, region.len);
+            (after != *before).then(|| WatchpointHit {
+                address: region.address,
+                before: before.clone(),
+                after,
+                timing: core.timing(),
+                log_tail: Vec::new(),
+            })
+        });
+        if watchpoint.is_some() {
+            break;
+        }
+        // Frames are only checked once per frame, as a step may be a single instruction.
+        let frame_count = core.frame_count();
+        if frame_count != frame {
+            frame = frame_count;
+            core.set_keys(input.keys_at(frame));
+            if conditions.iter().any(
+                |condition| matches!(condition, StopCondition::Frames(frames) if frame >= *frames),
+            ) {
+                break;
+            }
+            if limits
+                .max_frames
+                .is_some_and(|max_frames| frame >= max_frames)
+            {
+                exceeded = Some(Exceeded::Frames(frame));
+            } else if let Some(timeout) =
+                limits.timeout.filter(|&timeout| start.elapsed() >= timeout)
+            {
+                exceeded = Some(Exceeded::Timeout(timeout));
+            }
+            if exceeded.is_some() {
+                break;
+            }
+        }
+    }
+
+    if let Some(path) = &states.save_on_exit {
+        if !core.save_state(path) {
+            panic!("could not save state");
+        }
+    }
+
+    let memory = regions
+        .iter()
+        .map(|region| MemoryDump {
+            address: region.address,
+            bytes: core.read_memory(region.address, region.len),
+        })
+        .collect();
+
+    Outcome {
+        exceeded,
+        memory,
+        watchpoint,
+    }
+}
+
+/// Passes the records logged since they were last drained from `core` to `on_record`, returning
+/// whether the ROM halted, either by meeting one of the `conditions` or as signaled by
+/// `on_record`.
+///
+/// Once the ROM has halted, the remaining records are discarded.
+fn deliver<F>(core: &mut Core, conditions: &[StopCondition], on_record: &mut F) -> bool
+where
+    F: FnMut(Record) -> bool,
+{
+    core.drain_logs().into_iter().any(|record| {
+        let met = conditions
+            .iter()
+            .any(|condition| condition.is_met_by(&record));
+        on_record(record) || met
+    })
+}
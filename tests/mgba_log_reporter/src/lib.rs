@@ -1,18 +1,29 @@
 //! A publicly exposed library for interoperating with the output of the binary.
 //!
 //! These types can be used to deserialize the JSON output from the binary. This allows reading the
-//! reported log messages.
+//! reported log messages. ROMs can also be run directly using [`Core`] or [`run_with()`], without
+//! running the binary at all.
 
-use serde::{Deserialize, Serialize};
+mod mgba;
+mod mgba_bindings;
+
+pub use mgba::{run_with, Core};
+
+use object::{Object, ObjectSymbol, SymbolKind};
+use regex::Regex;
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
 /// The level of a log message.
-#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Level {
     Fatal,
     Error,
     Warning,
     Info,
     Debug,
+    /// Not logged by ROMs. This marks that the binary stopped the ROM after it ran for longer
+    /// than allowed by `--max-frames` or `--timeout-secs`, without meeting any stop condition.
+    Timeout,
 }
 
 impl TryFrom<u8> for Level {
@@ -33,11 +44,832 @@ impl TryFrom<u8> for Level {
     }
 }
 
+/// When a message was logged, as emulated by mGBA.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Timing {
+    /// The number of frames emulated before the message was logged.
+    pub frame: u32,
+    /// The number of CPU cycles emulated before the message was logged.
+    pub cycles: u64,
+}
+
 /// A single logged message.
-#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+///
+/// When serialized, any key-value pairs parsed from the message are included as a `key_values`
+/// object. See [`Record::key_values()`] for details.
+///
+/// Records are compared by their level and message only, ignoring their timing, which changes
+/// whenever the code of a ROM does.
+#[derive(Clone, Debug, Deserialize, Eq)]
 pub struct Record {
     /// The message's level.
     pub level: Level,
     /// The log message itself.
     pub message: String,
+    /// When the message was logged, if it was captured from mGBA.
+    #[serde(default)]
+    pub timing: Option<Timing>,
+}
+
+impl PartialEq for Record {
+    fn eq(&self, other: &Self) -> bool {
+        self.level == other.level && self.message == other.message
+    }
+}
+
+impl Record {
+    /// Parses a line written by a backend that prefixes messages with their level, as in
+    /// `[WARN] Hello, world!`.
+    ///
+    /// These are written by `mgba_log` when logging through its NO$GBA, AGBPrint, or UART
+    /// backends. A trailing carriage return is ignored. Returns `None` if the line does not start
+    /// with a level prefix.
+    pub fn from_prefixed_line(line: &str) -> Option<Self> {
+        let (level, message) = line.strip_prefix('[')?.split_once("] ")?;
+        let level = match level {
+            "FATAL" => Level::Fatal,
+            "ERROR" => Level::Error,
+            "WARN" => Level::Warning,
+            "INFO" => Level::Info,
+            "DEBUG" => Level::Debug,
+            _ => return None,
+        };
+        Some(Self {
+            level,
+            message: message.strip_suffix('\r').unwrap_or(message).to_owned(),
+            timing: None,
+        })
+    }
+
+    /// Returns the sequence number the message is prefixed with, as in `[#17] Hello, world!`.
+    ///
+    /// These are written by `mgba_log` when `LoggerBuilder::with_sequence_numbers()` is enabled.
+    /// Lines of a multi-line message after the first have no sequence number.
+    pub fn sequence_number(&self) -> Option<u32> {
+        let (number, _) = self.message.strip_prefix("[#")?.split_once("] ")?;
+        number.parse().ok()
+    }
+
+    /// Returns the `key=value` pairs at the end of the message, in order.
+    ///
+    /// These are written by `mgba_log` when its `kv` feature is enabled. Pairs whose key or value
+    /// contains a space cannot be told apart from the rest of the message, so parsing stops at
+    /// the first word from the end that is not a pair.
+    pub fn key_values(&self) -> Vec<(&str, &str)> {
+        let mut key_values: Vec<(&str, &str)> = self
+            .message
+            .rsplit(' ')
+            .map_while(|word| word.split_once('=').filter(|(key, _)| !key.is_empty()))
+            .collect();
+        key_values.reverse();
+        key_values
+    }
+}
+
+impl Serialize for Record {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        /// Serializes key-value pairs as a map, preserving their order.
+        struct KeyValues<'a>(&'a [(&'a str, &'a str)]);
+
+        impl Serialize for KeyValues<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.collect_map(self.0.iter().copied())
+            }
+        }
+
+        let key_values = self.key_values();
+        let len = 2 + usize::from(!key_values.is_empty()) + usize::from(self.timing.is_some());
+        let mut record = serializer.serialize_struct("Record", len)?;
+        record.serialize_field("level", &self.level)?;
+        record.serialize_field("message", &self.message)?;
+        if !key_values.is_empty() {
+            record.serialize_field("key_values", &KeyValues(&key_values))?;
+        }
+        if let Some(timing) = &self.timing {
+            record.serialize_field("timing", timing)?;
+        }
+        record.end()
+    }
+}
+
+/// Joins messages that were split across multiple sends using a continuation marker.
+///
+/// A message ending with `marker` is joined with the following message if that message has the
+/// same level and starts with `marker`. The markers themselves are removed. If `marker` is empty,
+/// the records are returned unchanged.
+pub fn reassemble(records: Vec<Record>, marker: &str) -> Vec<Record> {
+    if marker.is_empty() {
+        return records;
+    }
+    let mut reassembled: Vec<Record> = Vec::with_capacity(records.len());
+    for record in records {
+        if let Some(previous) = reassembled.last_mut() {
+            if previous.level == record.level && previous.message.ends_with(marker) {
+                if let Some(continuation) = record.message.strip_prefix(marker) {
+                    previous
+                        .message
+                        .truncate(previous.message.len() - marker.len());
+                    previous.message.push_str(continuation);
+                    continue;
+                }
+            }
+        }
+        reassembled.push(record);
+    }
+    reassembled
+}
+
+/// An inconsistency in the sequence numbers of a list of records.
+#[derive(Debug, Eq, PartialEq)]
+pub enum SequenceError {
+    /// No record had this sequence number, although later sequence numbers were found.
+    Missing(u32),
+    /// A record with this sequence number was found after a record with a later sequence number.
+    OutOfOrder(u32),
+}
+
+/// Checks that the sequence numbers of `records` are consecutive and in order.
+///
+/// Records without a sequence number are ignored, and the sequence may start at any number. A
+/// record that was sent late is reported both as missing where it was expected and as out of
+/// order where it was found.
+pub fn check_sequence_numbers(records: &[Record]) -> Vec<SequenceError> {
+    let mut errors = Vec::new();
+    let mut expected = None;
+    for number in records.iter().filter_map(Record::sequence_number) {
+        let next = expected.unwrap_or(number);
+        if number < next {
+            errors.push(SequenceError::OutOfOrder(number));
+            continue;
+        }
+        errors.extend((next..number).map(SequenceError::Missing));
+        expected = Some(number + 1);
+    }
+    errors
+}
+
+/// A difference between a golden capture and the records captured from a ROM.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Difference {
+    /// The golden record at this index was not captured.
+    Missing(usize),
+    /// The captured record at this index was not in the golden capture.
+    Unexpected(usize),
+}
+
+/// Compares the captured `records` against a `golden` capture, returning the smallest set of
+/// differences between them, in the order they occur.
+///
+/// Records are compared by their level and message, ignoring their timing. Before messages are
+/// compared, each match of any of the `normalize` patterns is replaced with `*`, allowing volatile
+/// parts of messages, such as addresses or counters, to differ.
+pub fn compare(golden: &[Record], records: &[Record], normalize: &[Regex]) -> Vec<Difference> {
+    fn normalized<'a>(records: &'a [Record], normalize: &[Regex]) -> Vec<(&'a Level, String)> {
+        records
+            .iter()
+            .map(|record| {
+                let message = normalize
+                    .iter()
+                    .fold(record.message.clone(), |message, pattern| {
+                        pattern.replace_all(&message, "*").into_owned()
+                    });
+                (&record.level, message)
+            })
+            .collect()
+    }
+    let golden = normalized(golden, normalize);
+    let records = normalized(records, normalize);
+
+    // Matching records at either end are skipped, as they usually make up most of a capture.
+    let prefix = golden
+        .iter()
+        .zip(&records)
+        .take_while(|(golden, record)| golden == record)
+        .count();
+    let suffix = golden[prefix..]
+        .iter()
+        .rev()
+        .zip(records[prefix..].iter().rev())
+        .take_while(|(golden, record)| golden == record)
+        .count();
+    let golden = &golden[prefix..golden.len() - suffix];
+    let records = &records[prefix..records.len() - suffix];
+
+    // The length of the longest common subsequence of each pair of suffixes.
+    let width = records.len() + 1;
+    let mut common = vec![0; (golden.len() + 1) * width];
+    for i in (0..golden.len()).rev() {
+        for j in (0..records.len()).rev() {
+            common[i * width + j] = if golden[i] == records[j] {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut differences = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < golden.len() || j < records.len() {
+        if i < golden.len() && j < records.len() && golden[i] == records[j] {
+            i += 1;
+            j += 1;
+        } else if j == records.len()
+            || i < golden.len() && common[(i + 1) * width + j] >= common[i * width + j + 1]
+        {
+            differences.push(Difference::Missing(prefix + i));
+            i += 1;
+        } else {
+            differences.push(Difference::Unexpected(prefix + j));
+            j += 1;
+        }
+    }
+    differences
+}
+
+/// Decodes the bytes captured from `mgba_log`'s UART backend into records.
+///
+/// Each message is sent as a line prefixed with its level. Lines that cannot be parsed, such as
+/// noise captured before the serial port was configured, are skipped. A final line without a
+/// newline is assumed to still be in transit and is skipped as well.
+pub fn decode_serial(bytes: &[u8]) -> Vec<Record> {
+    let text = String::from_utf8_lossy(bytes);
+    let complete = text.rsplit_once('\n').map_or("", |(complete, _)| complete);
+    complete
+        .split('\n')
+        .filter_map(Record::from_prefixed_line)
+        .collect()
+}
+
+/// Computes the CRC-16/CCITT-FALSE of `bytes`, as used by the header of `mgba_log`'s SRAM log.
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0xFFFF, |crc, &byte| {
+        (0..8).fold(crc ^ u16::from(byte) << 8, |crc, _| {
+            if crc & 0x8000 == 0 {
+                crc << 1
+            } else {
+                crc << 1 ^ 0x1021
+            }
+        })
+    })
+}
+
+/// Recovers the records mirrored by `mgba_log`'s `LoggerBuilder::with_sram_log()` from the
+/// contents of a save file.
+///
+/// The log is found by searching for its header, so the region it was configured with does not
+/// need to be known. Records are returned from oldest to newest. Once the ring buffer has wrapped
+/// around, the oldest line is partially overwritten and is skipped. Returns `None` if no valid log
+/// is found.
+pub fn decode_sram_log(save: &[u8]) -> Option<Vec<Record>> {
+    const HEADER_LEN: usize = 10;
+
+    let (header, ring) = (0..save.len().saturating_sub(HEADER_LEN)).find_map(|offset| {
+        let header = &save[offset..offset + HEADER_LEN];
+        let ring_len = usize::from(u16::from_le_bytes([header[4], header[5]]));
+        let ring = save.get(offset + HEADER_LEN..offset + HEADER_LEN + ring_len)?;
+        (header.starts_with(b"MLOG")
+            && u16::from_le_bytes([header[8], header[9]]) == crc16(&header[..8]))
+        .then_some((header, ring))
+    })?;
+    let head = usize::from(u16::from_le_bytes([header[6], header[7]]));
+    if head >= ring.len() {
+        return None;
+    }
+
+    // The byte at `head` is the null byte marking the end of the log. Until the ring buffer first
+    // wraps around, every byte after it is null as well.
+    let wrapped = ring[head + 1..].iter().any(|&byte| byte != 0);
+    let bytes = if wrapped {
+        let bytes = [&ring[head + 1..], &ring[..head]].concat();
+        // The first line is partially overwritten.
+        let newline = bytes.iter().position(|&byte| byte == b'\n')?;
+        bytes[newline + 1..].to_vec()
+    } else {
+        // When `head` is the last byte of the ring buffer, nothing follows it to tell whether the
+        // ring buffer has wrapped around, so the first line is kept. If it was partially
+        // overwritten, it is skipped by failing to parse, unless it happens to begin where a level
+        // prefix was written.
+        ring[..head].to_vec()
+    };
+    Some(
+        String::from_utf8_lossy(&bytes)
+            .lines()
+            .filter_map(Record::from_prefixed_line)
+            .collect(),
+    )
+}
+
+/// The function symbols of an ELF file, used to symbolicate backtraces.
+pub struct Symbols(Vec<(u32, u32, String)>);
+
+impl Symbols {
+    /// Reads the function symbols of the ELF file `data`.
+    ///
+    /// Returns `None` if `data` is not an ELF file. The ROM's linker script must keep the
+    /// `.symtab` and `.strtab` sections for any symbols to be found.
+    pub fn from_elf(data: &[u8]) -> Option<Self> {
+        let file = object::File::parse(data).ok()?;
+        let mut symbols = file
+            .symbols()
+            .filter(|symbol| symbol.kind() == SymbolKind::Text && symbol.size() > 0)
+            .filter_map(|symbol| {
+                // The lowest bit of a Thumb function's address is set.
+                let start = u32::try_from(symbol.address()).ok()? & !1;
+                let end = start.checked_add(u32::try_from(symbol.size()).ok()?)?;
+                let name = format!("{:#}", rustc_demangle::demangle(symbol.name().ok()?));
+                Some((start, end, name))
+            })
+            .collect::<Vec<_>>();
+        symbols.sort_unstable_by_key(|&(start, _, _)| start);
+        Some(Self(symbols))
+    }
+
+    /// Returns the function containing the call that returns to `address`, followed by the offset
+    /// of `address` within it, as in `main::foo+0x12`.
+    pub fn resolve(&self, address: u32) -> Option<String> {
+        // The return address may be past the end of a function ending in a call that never
+        // returns, so the call instruction itself is looked up instead.
+        let address = address & !1;
+        let call = address.checked_sub(1)?;
+        let index = self.0.partition_point(|&(start, _, _)| start <= call);
+        let (start, end, name) = self.0.get(index.checked_sub(1)?)?;
+        (call < *end).then(|| format!("{name}+{:#x}", address - start))
+    }
+}
+
+/// Annotates each return address in the backtraces logged by `mgba_log`'s `backtrace` feature
+/// with the function it returns into, as in `backtrace: 0x080001c9 (main::foo+0x8)`.
+///
+/// Addresses that are not within any function are left as they are, as are all other records.
+pub fn symbolicate(records: Vec<Record>, symbols: &Symbols) -> Vec<Record> {
+    records
+        .into_iter()
+        .map(|record| {
+            let Some(addresses) = record.message.strip_prefix("backtrace:") else {
+                return record;
+            };
+            let mut message = "backtrace:".to_owned();
+            for address in addresses.split_whitespace() {
+                message.push(' ');
+                message.push_str(address);
+                if let Some(function) = address
+                    .strip_prefix("0x")
+                    .and_then(|address| u32::from_str_radix(address, 16).ok())
+                    .and_then(|address| symbols.resolve(address))
+                {
+                    message.push_str(&format!(" ({function})"));
+                }
+            }
+            Record {
+                level: record.level,
+                message,
+                timing: record.timing,
+            }
+        })
+        .collect()
+}
+
+/// The names of the registers in a crash dump, in the order they are stored.
+const REGISTER_NAMES: [&str; 17] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "sp", "lr",
+    "pc", "cpsr",
+];
+
+/// A crash dump written by `mgba_log`'s `crash-dump` feature.
+#[derive(Debug, Eq, PartialEq)]
+pub struct CrashDump {
+    /// The id of the build that wrote the dump, as set through `LoggerBuilder::with_build_id()`.
+    pub build_id: u32,
+    /// `r0` through `r15`, followed by the CPSR.
+    pub registers: [u32; 17],
+    /// The words at the top of the stack, starting at the stack pointer.
+    pub stack: Vec<u32>,
+}
+
+impl CrashDump {
+    /// Parses the crash dump at the start of `bytes`.
+    ///
+    /// Any bytes following the dump are ignored. Returns `None` if `bytes` does not start with a
+    /// complete crash dump whose CRC matches.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let word = |offset: usize| -> Option<u32> {
+            Some(u32::from_le_bytes(
+                bytes.get(offset..offset + 4)?.try_into().ok()?,
+            ))
+        };
+
+        if !bytes.starts_with(b"MCRD") {
+            return None;
+        }
+        let words = usize::from(*bytes.get(76)?);
+        let len = 77 + 4 * words;
+        let crc = bytes.get(len..len + 2)?;
+        if u16::from_le_bytes([crc[0], crc[1]]) != crc16(&bytes[..len]) {
+            return None;
+        }
+        let mut registers = [0; 17];
+        for (index, register) in registers.iter_mut().enumerate() {
+            *register = word(8 + 4 * index)?;
+        }
+        Some(Self {
+            build_id: word(4)?,
+            registers,
+            stack: (0..words)
+                .map(|index| word(77 + 4 * index))
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    /// Decodes the first crash dump logged in `records`.
+    ///
+    /// A logged dump is hex encoded and split across consecutive records at the error level, each
+    /// prefixed with `crash dump: `. Returns `None` if no valid dump is found.
+    pub fn from_records(records: &[Record]) -> Option<Self> {
+        let is_dump = |record: &&Record| {
+            record.level == Level::Error && record.message.starts_with("crash dump: ")
+        };
+        let start = records.iter().position(|record| is_dump(&record))?;
+        let hex = records[start..]
+            .iter()
+            .take_while(is_dump)
+            .map(|record| &record.message["crash dump: ".len()..])
+            .collect::<String>();
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+            .collect::<Option<Vec<_>>>()?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Recovers a crash dump written by `LoggerBuilder::with_crash_dump_sram()` from the contents
+    /// of a save file.
+    ///
+    /// The dump is found by searching for its magic bytes, so the offset it was configured with
+    /// does not need to be known. Returns `None` if no valid dump is found.
+    pub fn from_save(save: &[u8]) -> Option<Self> {
+        (0..save.len()).find_map(|offset| Self::from_bytes(&save[offset..]))
+    }
+
+    /// Renders the dump for reading, with registers and stack words four to a line.
+    ///
+    /// If `symbols` are given, the link register and every word of the stack that is a return
+    /// address into a function are listed along with that function, most recent first.
+    pub fn pretty(&self, symbols: Option<&Symbols>) -> String {
+        let mut pretty = format!("crash dump of build {:#010x}\n", self.build_id);
+        for (index, (name, value)) in REGISTER_NAMES.iter().zip(self.registers).enumerate() {
+            let separator = if index % 4 == 3 || index == 16 {
+                '\n'
+            } else {
+                ' '
+            };
+            pretty.push_str(&format!("{name}={value:#010x}{separator}"));
+        }
+
+        let sp = self.registers[13];
+        pretty.push_str("stack:\n");
+        for (line, words) in (0..).zip(self.stack.chunks(4)) {
+            pretty.push_str(&format!("{:#010x}:", sp + 16 * line));
+            for word in words {
+                pretty.push_str(&format!(" {word:#010x}"));
+            }
+            pretty.push('\n');
+        }
+
+        if let Some(symbols) = symbols {
+            pretty.push_str("return addresses:\n");
+            for address in std::iter::once(self.registers[14]).chain(self.stack.iter().copied()) {
+                if let Some(function) = symbols.resolve(address) {
+                    pretty.push_str(&format!("{address:#010x} ({function})\n"));
+                }
+            }
+        }
+        pretty
+    }
+}
+
+/// The outcome of a single test run by `mgba_log`'s `test-runner` feature.
+#[derive(Debug, Eq, PartialEq)]
+pub struct TestOutcome {
+    /// The name of the test, as in `game::tests::physics`.
+    pub name: String,
+    /// Whether the test passed.
+    pub passed: bool,
+}
+
+/// The results of a test run by `mgba_log`'s `test-runner` feature.
+#[derive(Debug, Eq, PartialEq)]
+pub struct TestReport {
+    /// The outcome of each test that was run, in the order they were run.
+    pub outcomes: Vec<TestOutcome>,
+    /// The summary logged at the end of the run, as in `test result: ok. 3 passed; 0 failed`.
+    pub summary: String,
+}
+
+impl TestReport {
+    /// Parses the results of a test run from `records`.
+    ///
+    /// Each test is logged as `TEST name ... ok` or `TEST name ... FAILED`, and the run ends with a
+    /// summary starting with `test result: `. Returns `None` if no summary was logged, meaning
+    /// either that the records are not from a test run, or that the run has not finished.
+    pub fn from_records(records: &[Record]) -> Option<Self> {
+        let summary = records
+            .iter()
+            .find(|record| record.message.starts_with("test result: "))?
+            .message
+            .clone();
+        let outcomes = records
+            .iter()
+            .filter_map(|record| {
+                let (name, outcome) = record.message.strip_prefix("TEST ")?.rsplit_once(" ... ")?;
+                let passed = match outcome {
+                    "ok" => true,
+                    "FAILED" => false,
+                    _ => return None,
+                };
+                Some(TestOutcome {
+                    name: name.to_owned(),
+                    passed,
+                })
+            })
+            .collect();
+        Some(Self { outcomes, summary })
+    }
+
+    /// Returns whether every test in the run passed.
+    pub fn passed(&self) -> bool {
+        self.summary.starts_with("test result: ok.")
+            && self.outcomes.iter().all(|outcome| outcome.passed)
+    }
+}
+
+/// A single test case reported by [`TestSuite`].
+#[derive(Debug, Eq, PartialEq)]
+pub struct TestCase {
+    /// The name of the test case.
+    pub name: String,
+    /// The reason the test case failed, or `None` if it passed.
+    pub failure: Option<String>,
+}
+
+/// The results of running a ROM as a suite of test cases, for reporting in formats understood by
+/// CI systems.
+#[derive(Debug, Eq, PartialEq)]
+pub struct TestSuite {
+    /// The name of the suite, which is usually the name of the ROM.
+    pub name: String,
+    /// The test cases in the suite, in the order they were run.
+    pub cases: Vec<TestCase>,
+}
+
+impl TestSuite {
+    /// Maps the records logged by a ROM to a suite of test cases.
+    ///
+    /// If the ROM ran tests using `mgba_log`'s `test-runner` feature, each test is a test case, and
+    /// a failing test fails with the message logged at the fatal level. Otherwise, the ROM itself
+    /// is a single test case named `name`, which fails if a message was logged at the fatal level
+    /// or the ROM was stopped for running too long, and passes if the ROM finished, usually by
+    /// writing to the status register.
+    pub fn from_records(name: &str, records: &[Record]) -> Self {
+        let failure = records
+            .iter()
+            .find(|record| matches!(record.level, Level::Fatal | Level::Timeout))
+            .map(|record| record.message.clone());
+        let cases = match TestReport::from_records(records) {
+            Some(report) => report
+                .outcomes
+                .into_iter()
+                .map(|outcome| TestCase {
+                    name: outcome.name,
+                    failure: (!outcome.passed)
+                        .then(|| failure.clone().unwrap_or_else(|| report.summary.clone())),
+                })
+                .collect(),
+            None => vec![TestCase {
+                name: name.to_owned(),
+                failure,
+            }],
+        };
+        Self {
+            name: name.to_owned(),
+            cases,
+        }
+    }
+
+    /// Returns the number of test cases that failed.
+    pub fn failures(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|case| case.failure.is_some())
+            .count()
+    }
+
+    /// Formats the suite as a JUnit XML report.
+    ///
+    /// The messages of failing test cases are included as the message of their `<failure>`
+    /// elements.
+    pub fn to_junit(&self) -> String {
+        let mut xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".to_owned();
+        self.push_junit(&mut xml, "");
+        xml
+    }
+
+    /// Formats several suites as a single JUnit XML report, with a `<testsuite>` element for each
+    /// within a `<testsuites>` element.
+    pub fn all_to_junit(suites: &[Self]) -> String {
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites tests=\"{}\" failures=\"{}\">\n",
+            suites.iter().map(|suite| suite.cases.len()).sum::<usize>(),
+            suites.iter().map(Self::failures).sum::<usize>()
+        );
+        for suite in suites {
+            suite.push_junit(&mut xml, "  ");
+        }
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+
+    /// Appends the suite's `<testsuite>` element to `xml`, with each line prefixed by `indent`.
+    fn push_junit(&self, xml: &mut String, indent: &str) {
+        xml.push_str(&format!(
+            "{indent}<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+            escape_xml(&self.name),
+            self.cases.len(),
+            self.failures()
+        ));
+        for case in &self.cases {
+            match &case.failure {
+                Some(failure) => xml.push_str(&format!(
+                    "{indent}  <testcase name=\"{}\">\n{indent}    <failure message=\"{}\"/>\n{indent}  </testcase>\n",
+                    escape_xml(&case.name),
+                    escape_xml(failure)
+                )),
+                None => xml.push_str(&format!(
+                    "{indent}  <testcase name=\"{}\"/>\n",
+                    escape_xml(&case.name)
+                )),
+            }
+        }
+        xml.push_str(&format!("{indent}</testsuite>\n"));
+    }
+
+    /// Formats the suite as a TAP version 13 report.
+    ///
+    /// The message of each failing test case follows it as a diagnostic line.
+    pub fn to_tap(&self) -> String {
+        tap(&self
+            .cases
+            .iter()
+            .map(|case| (case.name.clone(), case.failure.as_deref()))
+            .collect::<Vec<_>>())
+    }
+
+    /// Formats several suites as a single TAP version 13 report.
+    ///
+    /// The test cases of every suite are numbered together, with each named after its suite, as
+    /// in `suite: case`.
+    pub fn all_to_tap(suites: &[Self]) -> String {
+        tap(&suites
+            .iter()
+            .flat_map(|suite| {
+                suite.cases.iter().map(|case| {
+                    (
+                        format!("{}: {}", suite.name, case.name),
+                        case.failure.as_deref(),
+                    )
+                })
+            })
+            .collect::<Vec<_>>())
+    }
+}
+
+/// Formats a TAP version 13 report of the named test cases, each with its failure, if any.
+fn tap(cases: &[(String, Option<&str>)]) -> String {
+    let mut tap = format!("TAP version 13\n1..{}\n", cases.len());
+    for (number, (name, failure)) in cases.iter().enumerate() {
+        // Names can not contain `#`, as it starts a directive.
+        let name = name.replace('#', "\\#");
+        match failure {
+            Some(failure) => {
+                tap.push_str(&format!("not ok {} - {name}\n", number + 1));
+                for line in failure.lines() {
+                    tap.push_str(&format!("# {line}\n"));
+                }
+            }
+            None => tap.push_str(&format!("ok {} - {name}\n", number + 1)),
+        }
+    }
+    tap
+}
+
+/// Escapes `text` for use within XML attributes.
+///
+/// Control characters that can not be represented in XML 1.0, such as the substitutes `mgba_log`
+/// writes in place of null characters, are replaced with `U+FFFD`.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' => escaped.push_str("&#9;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            character if character < ' ' => {
+                escaped.push(char::REPLACEMENT_CHARACTER);
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// A region of emulated memory, read once a ROM stopped running.
+///
+/// When serialized, the bytes are encoded as a string of hex digits.
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MemoryDump {
+    /// The address of the first byte.
+    pub address: u32,
+    /// The bytes read, starting at `address`.
+    #[serde(with = "hex")]
+    pub bytes: Vec<u8>,
+}
+
+/// A change to a watched region of emulated memory, which stopped a ROM.
+///
+/// The watched bytes are compared after each instruction, so a write that leaves them unchanged is
+/// not detected.
+///
+/// When serialized, the bytes are encoded as strings of hex digits.
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct WatchpointHit {
+    /// The address of the first byte of the watched region.
+    pub address: u32,
+    /// The bytes of the region before the change.
+    #[serde(with = "hex")]
+    pub before: Vec<u8>,
+    /// The bytes of the region after the change.
+    #[serde(with = "hex")]
+    pub after: Vec<u8>,
+    /// When the change was detected, as emulated by mGBA.
+    pub timing: Timing,
+    /// The last records logged before the change, oldest first.
+    pub log_tail: Vec<Record>,
+}
+
+/// The output of the binary when memory is dumped with `--dump` or watched with
+/// `--break-on-write`.
+///
+/// Otherwise, only the records are output.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Report {
+    /// The captured logs.
+    pub records: Vec<Record>,
+    /// The requested regions of memory, in the order they were requested.
+    pub memory: Vec<MemoryDump>,
+    /// The change to watched memory that stopped the ROM, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watchpoint: Option<WatchpointHit>,
+}
+
+/// Serialization of bytes as a string of hex digits.
+mod hex {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(
+            &bytes
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>(),
+        )
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let hex = String::deserialize(deserializer)?;
+        (0..hex.len())
+            .step_by(2)
+            .map(|index| {
+                hex.get(index..index + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or_else(|| D::Error::custom("invalid hex digits"))
+            })
+            .collect()
+    }
 }
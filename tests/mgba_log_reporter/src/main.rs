@@ -2,97 +2,624 @@
 //! that ROM.
 //!
 //! Logs that are captured are output as serialized JSON. They can be deserialized into the types
-//! exposed in this crate's library interface.
+//! exposed in this crate's library interface. By default, the records are output as a single array
+//! once the ROM stops. Passing `--stream` after the ROM instead outputs each record on its own line
+//! as soon as it is logged, allowing long-running ROMs to be monitored, and keeping the records
+//! output so far if either the ROM or this binary crashes.
+//!
+//! Passing `--format junit` or `--format tap` instead outputs the results of the ROM as a JUnit XML
+//! or TAP report once it stops, as mapped by `TestSuite`. Each test run by `mgba_log`'s
+//! `test-runner` feature is a test case. Otherwise, the ROM is a single test case, which fails if
+//! it logs a fatal message or runs too long, and passes if it finishes.
+//!
+//! Emulated memory can be inspected by passing `--dump <address>:<length>` any number of times,
+//! with the address and length each given in decimal or, prefixed with `0x`, in hexadecimal. Each
+//! region is read once the ROM stops, and the output is then a JSON object holding the `records`
+//! and the hex encoded `memory` dumps, which can be deserialized as a `Report`. When streaming,
+//! each dump is instead output on its own line after the records.
+//!
+//! Memory can also be watched by passing `--break-on-write <address>[:<length>]` any number of
+//! times, with a length of `1` by default. The ROM is stopped as soon as any of the watched bytes
+//! changes, which is checked by comparing them after each instruction. A write that leaves the
+//! bytes unchanged is not detected, and as the bytes are only compared between instructions, this
+//! cannot be combined with `--turbo`. The output is then a `Report` holding the `watchpoint` that
+//! was hit, along with the last records logged before it. When streaming, the hit is instead
+//! output on its own line after the records.
+//!
+//! Controller input can be scripted by passing `--input <path>`, naming a file where each line is a
+//! frame number followed by the keys held from that frame on, such as `120 START` or `121`, which
+//! releases them. This allows code behind menus or pause screens to be run deterministically.
+//!
+//! Long boot sequences can be skipped by passing `--load-state <path>`, resuming the ROM from a save
+//! state, and the state of the ROM once it stops can be saved by passing
+//! `--save-state-on-exit <path>`, preserving it to be debugged later in the mGBA GUI. Frames
+//! continue from the frame the loaded state was saved on, including for stop conditions, limits,
+//! and scripted input.
+//!
+//! The logs can also be checked by passing `--expect <regex>` or `--deny <regex>`, each of which
+//! may be passed any number of times. If an expected pattern is not found in any logged message,
+//! or a denied pattern is found in one, this is reported once the results are output, and the
+//! binary exits with the status `1`.
+//!
+//! Passing `--fail-on-level <level>` fails the ROM if it logs a message at the level, which is one
+//! of `fatal`, `error`, `warn`, `info`, or `debug`, or at any more severe level, which is likewise
+//! reported once the results are output, and the binary exits with the status `1`. Passing
+//! `--fail-on-fatal` is the same as passing `--fail-on-level fatal`. The summary logged at the
+//! fatal level by `mgba_log`'s `test-runner` feature does not fail the ROM, as its result is
+//! already reported by the exit status.
+//!
+//! The logs can also be compared against a golden capture by passing `--golden <path>`, naming a
+//! file holding a JSON array of records as output by this binary. If they differ, each golden
+//! record that was not captured is reported prefixed with `-`, and each captured record that was
+//! not in the golden capture is reported prefixed with `+`, along with its index, and the binary
+//! exits with the status `1`. Records are compared by their level and message. Volatile parts of
+//! messages can be ignored by passing `--normalize <regex>` any number of times, replacing each
+//! match with `*` in both captures before they are compared.
+//!
+//! By default, the ROM is run until it writes `3` to the status register at `0x0203FFFF`. Other
+//! conditions for when the ROM is finished can be passed after the ROM instead, stopping it once
+//! any of them is met:
+//!
+//! - `--stop-on-status <value>`: The ROM writes the value to the status register.
+//! - `--stop-on-fatal`: The ROM logs a message at the fatal level.
+//! - `--stop-on-message <message>`: The ROM logs exactly the message, at any level.
+//! - `--stop-after-frames <frames>`: The number of frames are emulated.
+//!
+//! A ROM that never finishes can be stopped by passing `--max-frames <frames>` or
+//! `--timeout-secs <seconds>`. The logs captured before it was stopped are still output, followed
+//! by a record at the `Timeout` level, and the binary exits with the status `124`.
+//!
+//! The ROM is emulated as fast as possible, as mGBA is stepped directly rather than on its own
+//! thread, so there is no audio or video sync to wait for, and no frames are rendered. By default,
+//! it is stepped a single instruction at a time, so that it is stopped as soon as it is finished.
+//! Passing `--turbo` instead steps a whole frame at a time, which is much faster, but runs the ROM
+//! until the end of the frame it finished on. Records logged after the ROM halted are discarded.
+//!
+//! Any number of ROMs can be passed before the flags, avoiding starting this binary once per ROM.
+//! Each ROM is run as configured by the flags, and up to `--jobs <count>` of them are run at once,
+//! each on its own thread. The output is then a JSON object mapping the path of each ROM to its
+//! output, or a single JUnit XML or TAP report with the test cases of every ROM, and a golden
+//! capture must be such an object as well. Failures are reported prefixed with the path of the
+//! ROM, and the binary exits with the status of the first ROM that failed. Streaming and save
+//! states can only be used with a single ROM.
 
-mod mgba_bindings;
+mod emulator;
 
-use mgba_log_reporter::Record;
+use emulator::{InputScript, Limits, Outcome, Region, SaveStates, Stepping, StopCondition};
+use mgba_log_reporter::{
+    compare, symbolicate, CrashDump, Difference, Level, Record, Symbols, TestReport, TestSuite,
+};
+use regex::Regex;
 use std::{
-    env,
-    ffi::{c_char, c_uchar, c_void, CStr, CString},
-    io::stdout,
+    collections::BTreeMap,
+    env, fs,
+    io::{stdout, Write},
     path::Path,
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
 };
 
-/// Run the provided ROM file, returning the captured logs.
-fn run(rom: &str) -> Vec<Record> {
-    // Create new mGBA core for ROM.
-    let rom_c_string = CString::new(rom).expect("failed to convert rom name to CString");
-    let mgba = unsafe { mgba_bindings::load(rom_c_string.as_ptr() as *mut c_char) };
-    if mgba.is_null() {
-        panic!("could not initialize mgba core");
+/// The format the results are output in.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum Format {
+    /// The captured records as JSON.
+    #[default]
+    Json,
+    /// A JUnit XML report.
+    Junit,
+    /// A TAP report.
+    Tap,
+}
+
+/// How the ROM is run, as configured by the flags passed after it.
+#[derive(Debug, Default)]
+struct Options {
+    /// The conditions under which the ROM is finished.
+    conditions: Vec<StopCondition>,
+    /// The limits after which the ROM is stopped, even though it is not finished.
+    limits: Limits,
+    /// Whether each record is output on its own line as soon as it is logged.
+    stream: bool,
+    /// The format the results are output in.
+    format: Format,
+    /// Patterns that must be found in at least one logged message.
+    expect: Vec<Regex>,
+    /// Patterns that must not be found in any logged message.
+    deny: Vec<Regex>,
+    /// The records the captured records must match, if any.
+    golden: Option<serde_json::Value>,
+    /// Patterns matching the parts of messages ignored when comparing against `golden`.
+    normalize: Vec<Regex>,
+    /// The severity of the least severe level that fails the ROM if logged, if any.
+    fail_on: Option<u8>,
+    /// The regions of memory read once the ROM stops.
+    regions: Vec<Region>,
+    /// The regions of memory that stop the ROM if they change.
+    watches: Vec<Region>,
+    /// The keys held on each frame.
+    input: InputScript,
+    /// The save states loaded before and written after running the ROM.
+    states: SaveStates,
+    /// The number of ROMs run at once.
+    jobs: usize,
+    /// How far emulation is advanced between checks of whether the ROM is finished.
+    stepping: Stepping,
+}
+
+/// Returns the severity of `level`, where lower is more severe, or `None` if it is not logged by
+/// ROMs.
+fn severity(level: &Level) -> Option<u8> {
+    match level {
+        Level::Fatal => Some(0),
+        Level::Error => Some(1),
+        Level::Warning => Some(2),
+        Level::Info => Some(3),
+        Level::Debug => Some(4),
+        Level::Timeout => None,
     }
+}
 
-    // Execute ROM.
-    let mut results = Vec::<Record>::new();
-    // Register callback to catch logs.
-    unsafe {
-        mgba_bindings::set_log_callback(
-            mgba,
-            generate_c_callback(|message: *mut c_char, level: u8| {
-                if let Ok(level) = level.try_into() {
-                    results.push(Record {
-                        level,
-                        message: CStr::from_ptr(message).to_string_lossy().into_owned(),
+/// The number of records logged before a watchpoint was hit that are reported with it.
+const LOG_TAIL_LEN: usize = 16;
+
+/// Parses `number` as decimal, or as hexadecimal if it is prefixed with `0x`.
+fn parse_number(number: &str) -> Option<u32> {
+    match number.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => number.parse().ok(),
+    }
+}
+
+impl Options {
+    /// Parses the flags passed after the ROM.
+    ///
+    /// If no stop conditions are passed, the ROM is finished once it writes `3` to the status
+    /// register.
+    fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self::default();
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--stop-on-fatal" => {
+                    options.conditions.push(StopCondition::Fatal);
+                    continue;
+                }
+                "--stream" => {
+                    options.stream = true;
+                    continue;
+                }
+                "--fail-on-fatal" => {
+                    options.fail_on = options.fail_on.max(severity(&Level::Fatal));
+                    continue;
+                }
+                "--turbo" => {
+                    options.stepping = Stepping::Frame;
+                    continue;
+                }
+                _ => {}
+            }
+            let value = args
+                .next()
+                .unwrap_or_else(|| panic!("no value provided for {flag}"));
+            match flag.as_str() {
+                "--stop-on-status" => options.conditions.push(StopCondition::Status(
+                    value.parse().expect("invalid status register value"),
+                )),
+                "--stop-on-message" => options.conditions.push(StopCondition::Message(value)),
+                "--stop-after-frames" => options.conditions.push(StopCondition::Frames(
+                    value.parse().expect("invalid number of frames"),
+                )),
+                "--max-frames" => {
+                    options.limits.max_frames =
+                        Some(value.parse().expect("invalid number of frames"));
+                }
+                "--expect" => options
+                    .expect
+                    .push(Regex::new(&value).expect("invalid expected pattern")),
+                "--deny" => options
+                    .deny
+                    .push(Regex::new(&value).expect("invalid denied pattern")),
+                "--fail-on-level" => {
+                    let level = match value.to_ascii_lowercase().as_str() {
+                        "fatal" => Level::Fatal,
+                        "error" => Level::Error,
+                        "warn" | "warning" => Level::Warning,
+                        "info" => Level::Info,
+                        "debug" => Level::Debug,
+                        _ => panic!("unknown level {value}"),
+                    };
+                    options.fail_on = options.fail_on.max(severity(&level));
+                }
+                "--golden" => {
+                    let golden = fs::read_to_string(&value).expect("could not read golden capture");
+                    options.golden =
+                        Some(serde_json::from_str(&golden).expect("invalid golden capture"));
+                }
+                "--normalize" => options
+                    .normalize
+                    .push(Regex::new(&value).expect("invalid normalization pattern")),
+                "--dump" => {
+                    let (address, len) = value
+                        .split_once(':')
+                        .expect("memory regions must be given as <address>:<length>");
+                    options.regions.push(Region {
+                        address: parse_number(address).expect("invalid address"),
+                        len: parse_number(len).expect("invalid length"),
                     });
                 }
-            }),
-        );
-    }
-    while !unsafe { mgba_bindings::is_finished(mgba) } {
-        unsafe {
-            mgba_bindings::step(mgba);
+                "--break-on-write" => {
+                    let (address, len) = value.split_once(':').unwrap_or((&value, "1"));
+                    options.watches.push(Region {
+                        address: parse_number(address).expect("invalid address"),
+                        len: parse_number(len).expect("invalid length"),
+                    });
+                }
+                "--input" => {
+                    let script = fs::read_to_string(&value).expect("could not read input script");
+                    options.input = InputScript::parse(&script)
+                        .unwrap_or_else(|error| panic!("invalid input script: {error}"));
+                }
+                "--jobs" => options.jobs = value.parse().expect("invalid number of jobs"),
+                "--load-state" => options.states.load = Some(value.into()),
+                "--save-state-on-exit" => options.states.save_on_exit = Some(value.into()),
+                "--format" => {
+                    options.format = match value.as_str() {
+                        "json" => Format::Json,
+                        "junit" => Format::Junit,
+                        "tap" => Format::Tap,
+                        _ => panic!("unknown format {value}"),
+                    };
+                }
+                "--timeout-secs" => {
+                    options.limits.timeout = Some(Duration::from_secs(
+                        value.parse().expect("invalid number of seconds"),
+                    ));
+                }
+                _ => panic!("unknown flag {flag}"),
+            }
         }
+        if options.stream && options.format != Format::Json {
+            panic!("only the json format can be streamed");
+        }
+        if options.stepping == Stepping::Frame && !options.watches.is_empty() {
+            panic!("--break-on-write cannot be combined with --turbo");
+        }
+        if options.conditions.is_empty() {
+            options.conditions.push(StopCondition::FINISHED);
+        }
+        options
     }
-
-    // Close mGBA core.
-    unsafe {
-        mgba_bindings::drop(mgba);
-    }
-
-    results
 }
 
-/// Create a callback from a function that can be passed to the mGBA bindings.
+/// Run the provided ROM file, returning the captured logs along with the outcome of the run.
+///
+/// Each record is annotated using `symbols`, if provided, and is passed to `on_record` as soon as
+/// it is captured.
 ///
-/// This can be used to create a function for capturing mGBA logs.
-unsafe fn generate_c_callback<F>(f: F) -> mgba_bindings::callback
+/// The ROM is run until one of the stop conditions in `options` is met, until a test run by
+/// `mgba_log`'s `test-runner` feature has been summarized and a fatal message logged, as mGBA
+/// halts then, until a watched region of memory changes, or until one of the limits is
+/// exceeded. In the last case, the logs end with a record at the `Timeout` level.
+fn run<F>(
+    rom: &str,
+    options: &Options,
+    symbols: Option<&Symbols>,
+    mut on_record: F,
+) -> (Vec<Record>, Outcome)
 where
-    F: FnMut(*mut c_char, c_uchar),
+    F: FnMut(&Record),
 {
-    let data = Box::into_raw(Box::new(f));
-
-    mgba_bindings::callback {
-        callback: Some(call_closure::<F>),
-        data: data as *mut _,
-        destroy: Some(drop_box::<F>),
+    let mut results = Vec::<Record>::new();
+    let mut tests_summarized = false;
+    let mut outcome = emulator::run(
+        rom,
+        &options.conditions,
+        options.limits,
+        &options.regions,
+        &options.watches,
+        &options.input,
+        &options.states,
+        options.stepping,
+        |record| {
+            let record = match symbols {
+                Some(symbols) => symbolicate(vec![record], symbols).remove(0),
+                None => record,
+            };
+            tests_summarized |= record.message.starts_with("test result: ");
+            let halted = tests_summarized && record.level == Level::Fatal;
+            on_record(&record);
+            results.push(record);
+            halted
+        },
+    );
+    if let Some(exceeded) = outcome.exceeded {
+        let record = Record {
+            level: Level::Timeout,
+            message: exceeded.to_string(),
+            timing: None,
+        };
+        on_record(&record);
+        results.push(record);
     }
+    if let Some(watchpoint) = &mut outcome.watchpoint {
+        watchpoint.log_tail = results[results.len().saturating_sub(LOG_TAIL_LEN)..].to_vec();
+    }
+    (results, outcome)
 }
 
-/// Wrapper for a function to interface directly with the callback call.
-extern "C" fn call_closure<F>(data: *mut c_void, message: *mut c_char, level: c_uchar)
-where
-    F: FnMut(*mut c_char, c_uchar),
-{
-    let callback_ptr = data as *mut F;
-    let callback = unsafe { &mut *callback_ptr };
-    callback(message, level);
+/// The results of running a single ROM.
+struct Run<'a> {
+    /// The path of the ROM.
+    rom: &'a str,
+    /// The symbols read from the ROM, if it is an ELF file.
+    symbols: Option<Symbols>,
+    /// The captured logs.
+    records: Vec<Record>,
+    /// The outcome of running the ROM.
+    outcome: Outcome,
 }
 
-/// Wrapper for a function to allow it to be dropped.
-extern "C" fn drop_box<T>(data: *mut c_void) {
-    unsafe {
-        drop(Box::from_raw(data as *mut T));
+impl<'a> Run<'a> {
+    /// Runs the ROM at `rom` as configured by `options`, passing each record to `on_record`.
+    fn new<F>(rom: &'a str, options: &Options, on_record: F) -> Self
+    where
+        F: FnMut(&Record),
+    {
+        let symbols = fs::read(rom).ok().and_then(|elf| Symbols::from_elf(&elf));
+        let (records, outcome) = run(rom, options, symbols.as_ref(), on_record);
+        Self {
+            rom,
+            symbols,
+            records,
+            outcome,
+        }
+    }
+
+    /// The name of the ROM, which is its file name without an extension.
+    fn name(&self) -> String {
+        Path::new(self.rom)
+            .file_stem()
+            .map_or(self.rom.to_owned(), |name| {
+                name.to_string_lossy().into_owned()
+            })
     }
+
+    /// The results of the run as JSON, either as the records alone or, if memory was dumped or
+    /// watched, in the layout of `Report`, which would otherwise need to own the records.
+    fn to_json(&self, options: &Options) -> serde_json::Value {
+        if options.regions.is_empty() && options.watches.is_empty() {
+            serde_json::json!(&self.records)
+        } else if let Some(watchpoint) = &self.outcome.watchpoint {
+            serde_json::json!({
+                "records": &self.records,
+                "memory": &self.outcome.memory,
+                "watchpoint": watchpoint,
+            })
+        } else {
+            serde_json::json!({"records": &self.records, "memory": &self.outcome.memory})
+        }
+    }
+
+    /// Reports any reason for the run to fail, returning the status the binary should exit with,
+    /// if any.
+    ///
+    /// Each reason is reported prefixed with `prefix`.
+    fn check(&self, options: &Options, golden: Option<&[Record]>, prefix: &str) -> Option<i32> {
+        let records = &self.records;
+
+        // Hitting a watchpoint is not a failure in itself, but is reported for reading.
+        if let Some(watchpoint) = &self.outcome.watchpoint {
+            eprintln!(
+                "{prefix}watched memory at {:#010x} changed on frame {}",
+                watchpoint.address, watchpoint.timing.frame
+            );
+        }
+
+        // Stopping a ROM that never finishes is reported as `timeout` does.
+        if let Some(exceeded) = self.outcome.exceeded {
+            eprintln!("{prefix}{exceeded}");
+            return Some(124);
+        }
+
+        let mut unmet = false;
+        for pattern in &options.expect {
+            if !records
+                .iter()
+                .any(|record| pattern.is_match(&record.message))
+            {
+                eprintln!("{prefix}expected pattern was never logged: {pattern}");
+                unmet = true;
+            }
+        }
+        for pattern in &options.deny {
+            for record in records
+                .iter()
+                .filter(|record| pattern.is_match(&record.message))
+            {
+                eprintln!(
+                    "{prefix}denied pattern {pattern} was logged: {}",
+                    record.message
+                );
+                unmet = true;
+            }
+        }
+        if let Some(fail_on) = options.fail_on {
+            for record in records.iter().filter(|record| {
+                severity(&record.level).is_some_and(|severity| severity <= fail_on)
+                    && !record.message.starts_with("test result: ")
+            }) {
+                eprintln!(
+                    "{prefix}message logged at a failing level: [{:?}] {}",
+                    record.level, record.message
+                );
+                unmet = true;
+            }
+        }
+        if let Some(golden) = golden {
+            let differences = compare(golden, records, &options.normalize);
+            if !differences.is_empty() {
+                eprintln!("{prefix}captured records differ from the golden capture:");
+                for difference in differences {
+                    let (sign, index, record) = match difference {
+                        Difference::Missing(index) => ('-', index, &golden[index]),
+                        Difference::Unexpected(index) => ('+', index, &records[index]),
+                    };
+                    eprintln!("{sign} {index}: [{:?}] {}", record.level, record.message);
+                }
+                unmet = true;
+            }
+        }
+        if unmet {
+            return Some(1);
+        }
+
+        // Failed test runs are reported through the exit status, allowing this to be used as the
+        // runner of `cargo test`.
+        if let Some(report) = TestReport::from_records(records) {
+            eprintln!("{prefix}{}", report.summary);
+            if !report.passed() {
+                return Some(101);
+            }
+        }
+        None
+    }
+}
+
+/// Runs each of the `roms` as configured by `options`, returning the runs in the same order.
+///
+/// Up to `options.jobs` ROMs are run at once, each on its own thread.
+fn run_all<'a>(roms: &'a [String], options: &Options) -> Vec<Run<'a>> {
+    let next = AtomicUsize::new(0);
+    let runs = Mutex::new(Vec::with_capacity(roms.len()));
+    thread::scope(|scope| {
+        for _ in 0..options.jobs.clamp(1, roms.len()) {
+            scope.spawn(|| {
+                while let Some(rom) = roms.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let run = Run::new(rom, options, |_| {});
+                    runs.lock().expect("could not store run").push(run);
+                }
+            });
+        }
+    });
+    let mut runs = runs.into_inner().expect("could not collect runs");
+    runs.sort_by_key(|run| roms.iter().position(|rom| rom == run.rom));
+    runs
 }
 
 fn main() {
-    let rom = env::args().nth(1).expect("no gba rom filename provided");
-    if !Path::new(&rom).exists() {
-        panic!("{} does not exist", rom);
+    let mut args = env::args().skip(1).peekable();
+    let mut roms = Vec::new();
+    while let Some(rom) = args.next_if(|arg| !arg.starts_with("--")) {
+        if !Path::new(&rom).exists() {
+            panic!("{} does not exist", rom);
+        }
+        roms.push(rom);
+    }
+    if roms.is_empty() {
+        panic!("no gba rom filename provided");
+    }
+    let options = Options::parse(args);
+    let single = roms.len() == 1;
+    if !single {
+        if options.stream {
+            panic!("only a single rom can be streamed");
+        }
+        if options.states.load.is_some() || options.states.save_on_exit.is_some() {
+            panic!("save states can only be used with a single rom");
+        }
     }
 
-    let records = run(&rom);
+    let mut stdout = stdout();
+    let runs = if options.stream {
+        vec![Run::new(&roms[0], &options, |record| {
+            serde_json::to_writer(&mut stdout, record).expect("could not serialize record");
+            writeln!(stdout).expect("could not write record");
+            stdout.flush().expect("could not flush record");
+        })]
+    } else {
+        run_all(&roms, &options)
+    };
+    // Crash dumps are printed for reading, keeping the JSON output intact.
+    for run in &runs {
+        if let Some(dump) = CrashDump::from_records(&run.records) {
+            if !single {
+                eprintln!("{}:", run.rom);
+            }
+            eprint!("{}", dump.pretty(run.symbols.as_ref()));
+        }
+    }
 
-    serde_json::to_writer(stdout(), &records).expect("could not serialize results");
+    match options.format {
+        Format::Json if options.stream => {
+            for dump in &runs[0].outcome.memory {
+                serde_json::to_writer(&mut stdout, dump).expect("could not serialize memory");
+                writeln!(stdout).expect("could not write memory");
+            }
+            if let Some(watchpoint) = &runs[0].outcome.watchpoint {
+                serde_json::to_writer(&mut stdout, watchpoint)
+                    .expect("could not serialize watchpoint");
+                writeln!(stdout).expect("could not write watchpoint");
+            }
+        }
+        Format::Json if single => {
+            serde_json::to_writer(&mut stdout, &runs[0].to_json(&options))
+                .expect("could not serialize results");
+        }
+        Format::Json => {
+            let results = runs
+                .iter()
+                .map(|run| (run.rom.to_owned(), run.to_json(&options)))
+                .collect::<serde_json::Map<_, _>>();
+            serde_json::to_writer(&mut stdout, &results).expect("could not serialize results");
+        }
+        Format::Junit | Format::Tap => {
+            let suites = runs
+                .iter()
+                .map(|run| TestSuite::from_records(&run.name(), &run.records))
+                .collect::<Vec<_>>();
+            let report = match (options.format, single) {
+                (Format::Junit, true) => suites[0].to_junit(),
+                (Format::Junit, false) => TestSuite::all_to_junit(&suites),
+                (_, true) => suites[0].to_tap(),
+                (_, false) => TestSuite::all_to_tap(&suites),
+            };
+            stdout
+                .write_all(report.as_bytes())
+                .expect("could not write results");
+        }
+    }
+    stdout.flush().expect("could not flush results");
+
+    // Failures are reported after the results, so they can still be inspected.
+    let golden = options.golden.clone().map(|golden| {
+        if single {
+            BTreeMap::from([(
+                roms[0].clone(),
+                serde_json::from_value(golden).expect("invalid golden capture"),
+            )])
+        } else {
+            serde_json::from_value::<BTreeMap<String, Vec<Record>>>(golden)
+                .expect("invalid golden capture")
+        }
+    });
+    let mut status = None;
+    for run in &runs {
+        // A ROM missing from the golden capture is expected to log nothing.
+        let golden = golden
+            .as_ref()
+            .map(|golden| golden.get(run.rom).map_or(&[][..], Vec::as_slice));
+        let prefix = if single {
+            String::new()
+        } else {
+            format!("{}: ", run.rom)
+        };
+        status = status.or(run.check(&options, golden, &prefix));
+    }
+    if let Some(status) = status {
+        process::exit(status);
+    }
 }
@@ -0,0 +1,245 @@
+//! A safe interface to an instance of mGBA.
+
+use crate::{mgba_bindings, Record, Timing};
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_uchar, c_void, CStr, CString},
+    ops::ControlFlow,
+    path::Path,
+    ptr::NonNull,
+    rc::Rc,
+};
+
+/// A headless instance of mGBA, running a single ROM.
+///
+/// This allows driving emulation directly, such as from a test harness, rather than running the
+/// binary and parsing its output. The logs emitted by the ROM are captured as it runs, and can be
+/// taken using [`Core::drain_logs()`].
+///
+/// ``` no_run
+/// use mgba_log_reporter::Core;
+///
+/// let mut core = Core::load("rom.elf").expect("could not load rom");
+/// core.run_frames(60);
+/// for record in core.drain_logs() {
+///     println!("{:?}: {}", record.level, record.message);
+/// }
+/// ```
+///
+/// A core must be run on the thread it was loaded on, as mGBA's logs are routed by thread.
+pub struct Core {
+    /// The instance of mGBA.
+    mgba: NonNull<mgba_bindings::MGBA>,
+    /// The logs captured since they were last drained, shared with the log callback.
+    logs: Rc<RefCell<Vec<Record>>>,
+}
+
+impl Core {
+    /// Loads the ROM at `rom` into a new instance of mGBA.
+    ///
+    /// Returns `None` if mGBA could not load the ROM, or if its path is not valid UTF-8.
+    pub fn load<P>(rom: P) -> Option<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let rom = c_string(rom.as_ref())?;
+        let mgba = NonNull::new(unsafe { mgba_bindings::load(rom.as_ptr() as *mut c_char) })?;
+
+        let logs = Rc::new(RefCell::new(Vec::new()));
+        let captured = Rc::clone(&logs);
+        // Register callback to catch logs.
+        unsafe {
+            mgba_bindings::set_log_callback(
+                mgba.as_ptr(),
+                generate_c_callback(
+                    move |message: *mut c_char, level: u8, frame: u32, cycles: u64| {
+                        if let Ok(level) = level.try_into() {
+                            let message = CStr::from_ptr(message).to_string_lossy().into_owned();
+                            captured.borrow_mut().push(Record {
+                                level,
+                                message,
+                                timing: Some(Timing { frame, cycles }),
+                            });
+                        }
+                    },
+                ),
+            );
+        }
+
+        Some(Self { mgba, logs })
+    }
+
+    /// Advances emulation by a single instruction.
+    pub fn step(&mut self) {
+        unsafe {
+            mgba_bindings::step(self.mgba.as_ptr());
+        }
+    }
+
+    /// Advances emulation by `frames` frames, the first of which ends at the end of the current
+    /// frame.
+    pub fn run_frames(&mut self, frames: u32) {
+        for _ in 0..frames {
+            unsafe {
+                mgba_bindings::run_frame(self.mgba.as_ptr());
+            }
+        }
+    }
+
+    /// Reads `len` bytes of emulated memory, starting at `address`.
+    ///
+    /// Memory is read without the side effects of the ROM reading it.
+    pub fn read_memory(&self, address: u32, len: u32) -> Vec<u8> {
+        (0..len)
+            .map(|offset| unsafe {
+                mgba_bindings::read_memory(self.mgba.as_ptr(), address.wrapping_add(offset))
+            })
+            .collect()
+    }
+
+    /// Takes the records logged since the logs were last drained, in the order they were logged.
+    pub fn drain_logs(&mut self) -> Vec<Record> {
+        self.logs.take()
+    }
+
+    /// Returns the value of the status register at `0x0203FFFF`, which ROMs usually write to
+    /// report that they have finished.
+    pub fn status_register(&self) -> u8 {
+        unsafe { mgba_bindings::status_register(self.mgba.as_ptr()) }
+    }
+
+    /// Returns the number of frames emulated so far.
+    pub fn frame_count(&self) -> u32 {
+        unsafe { mgba_bindings::frame_count(self.mgba.as_ptr()) }
+    }
+
+    /// Returns the number of frames and CPU cycles emulated so far.
+    pub fn timing(&self) -> Timing {
+        Timing {
+            frame: self.frame_count(),
+            cycles: unsafe { mgba_bindings::cycle_count(self.mgba.as_ptr()) },
+        }
+    }
+
+    /// Sets the keys currently held, with a bit set for each held key, in the order of the
+    /// `KEYINPUT` register.
+    pub fn set_keys(&mut self, keys: u16) {
+        unsafe {
+            mgba_bindings::set_keys(self.mgba.as_ptr(), keys);
+        }
+    }
+
+    /// Resumes emulation from the save state at `path`, returning whether it was loaded.
+    pub fn load_state<P>(&mut self, path: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        c_string(path.as_ref()).is_some_and(|path| unsafe {
+            mgba_bindings::load_state(self.mgba.as_ptr(), path.as_ptr() as *mut c_char)
+        })
+    }
+
+    /// Writes a save state to `path`, returning whether it was written.
+    ///
+    /// Save states can be loaded by the mGBA GUI, allowing the ROM to be debugged interactively.
+    pub fn save_state<P>(&self, path: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        c_string(path.as_ref()).is_some_and(|path| unsafe {
+            mgba_bindings::save_state(self.mgba.as_ptr(), path.as_ptr() as *mut c_char)
+        })
+    }
+}
+
+impl Drop for Core {
+    fn drop(&mut self) {
+        // Close mGBA core.
+        unsafe {
+            mgba_bindings::drop(self.mgba.as_ptr());
+        }
+    }
+}
+
+/// Runs the ROM at `rom`, passing each record to `f` as soon as it is logged.
+///
+/// The ROM is run until it writes `3` to the status register at `0x0203FFFF`, returning
+/// `ControlFlow::Continue`, or until `f` returns `ControlFlow::Break`, which stops the ROM
+/// immediately and is returned. Returns `None` if the ROM could not be loaded.
+///
+/// ``` no_run
+/// use std::ops::ControlFlow;
+///
+/// mgba_log_reporter::run_with("rom.elf", |record| {
+///     println!("{:?}: {}", record.level, record.message);
+///     if record.message == "done" {
+///         ControlFlow::Break(())
+///     } else {
+///         ControlFlow::Continue(())
+///     }
+/// });
+/// ```
+///
+/// A ROM that never finishes runs until `f` stops it. [`Core`] allows limiting how long a ROM is
+/// run for instead.
+pub fn run_with<P, F>(rom: P, mut f: F) -> Option<ControlFlow<()>>
+where
+    P: AsRef<Path>,
+    F: FnMut(Record) -> ControlFlow<()>,
+{
+    let mut core = Core::load(rom)?;
+    loop {
+        for record in core.drain_logs() {
+            if f(record).is_break() {
+                return Some(ControlFlow::Break(()));
+            }
+        }
+        if core.status_register() == 3 {
+            return Some(ControlFlow::Continue(()));
+        }
+        core.step();
+    }
+}
+
+/// Converts `path` into a string that can be passed to the mGBA bindings, if it is valid UTF-8.
+fn c_string(path: &Path) -> Option<CString> {
+    CString::new(path.to_str()?).ok()
+}
+
+/// Create a callback from a function that can be passed to the mGBA bindings.
+///
+/// This can be used to create a function for capturing mGBA logs.
+unsafe fn generate_c_callback<F>(f: F) -> mgba_bindings::callback
+where
+    F: FnMut(*mut c_char, c_uchar, u32, u64),
+{
+    let data = Box::into_raw(Box::new(f));
+
+    mgba_bindings::callback {
+        callback: Some(call_closure::<F>),
+        data: data as *mut _,
+        destroy: Some(drop_box::<F>),
+    }
+}
+
+/// Wrapper for a function to interface directly with the callback call.
+extern "C" fn call_closure<F>(
+    data: *mut c_void,
+    message: *mut c_char,
+    level: c_uchar,
+    frame: u32,
+    cycles: u64,
+) where
+    F: FnMut(*mut c_char, c_uchar, u32, u64),
+{
+    let callback_ptr = data as *mut F;
+    let callback = unsafe { &mut *callback_ptr };
+    callback(message, level, frame, cycles);
+}
+
+/// Wrapper for a function to allow it to be dropped.
+extern "C" fn drop_box<T>(data: *mut c_void) {
+    unsafe {
+        drop(Box::from_raw(data as *mut T));
+    }
+}
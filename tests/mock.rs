@@ -0,0 +1,83 @@
+//! Tests for the writer, run on the host using the `mock` feature.
+//!
+//! These are run with `cargo test --features mock`.
+
+#![cfg(feature = "mock")]
+
+use core::fmt::Write;
+use mgba_log::{
+    mock::{self, Message},
+    Level, MgbaWriter,
+};
+
+fn write(level: Level, text: &str) -> Vec<Message> {
+    mock::capture(|| {
+        assert!(mgba_log::is_mgba());
+        write!(MgbaWriter::new(level), "{text}").expect("unable to write");
+    })
+}
+
+#[test]
+fn single_message() {
+    assert_eq!(
+        write(Level::Warning, "Hello, world!"),
+        [Message::new(Level::Warning, "Hello, world!")]
+    );
+}
+
+#[test]
+fn split_writes() {
+    let messages = mock::capture(|| {
+        assert!(mgba_log::is_mgba());
+        let mut writer = MgbaWriter::new(Level::Info);
+        write!(writer, "Hello, ").expect("unable to write");
+        write!(writer, "world! {}", 42).expect("unable to write");
+    });
+
+    assert_eq!(messages, [Message::new(Level::Info, "Hello, world! 42")]);
+}
+
+#[test]
+fn new_line() {
+    assert_eq!(
+        write(Level::Info, "Hello,\nworld!"),
+        [
+            Message::new(Level::Info, "Hello,"),
+            Message::new(Level::Info, "world!"),
+        ]
+    );
+}
+
+#[test]
+fn overflow() {
+    let text = "abcdefghijklmnopqrstuvwxyz".repeat(16);
+
+    assert_eq!(
+        write(Level::Info, &text),
+        [
+            Message::new(Level::Info, &text[..256]),
+            Message::new(Level::Info, &text[256..]),
+        ]
+    );
+}
+
+#[test]
+fn overflow_utf8_boundary() {
+    let text = format!("{}é", "a".repeat(255));
+
+    assert_eq!(
+        write(Level::Info, &text),
+        [
+            Message::new(Level::Info, &text[..255]),
+            Message::new(Level::Info, "é"),
+        ]
+    );
+}
+
+#[test]
+fn null() {
+    assert_eq!(
+        write(Level::Info, "foo\0bar"),
+        [Message::new(Level::Info, "foo\x1abar")]
+    );
+}
@@ -0,0 +1,66 @@
+//! Tests for the logger, run on the host using the `mock` feature.
+//!
+//! The logger can only be initialized once per process, so these are kept separate from the
+//! writer tests in `mock.rs`. They are run with `cargo test --features mock`.
+
+#![cfg(feature = "mock")]
+
+use mgba_log::{
+    mock::{self, Message},
+    Level,
+};
+use std::sync::Once;
+
+fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        mgba_log::builder()
+            .with_escaped_control_bytes(true)
+            .init()
+            .expect("unable to initialize");
+    });
+}
+
+#[test]
+fn escaped_control_bytes() {
+    let messages = mock::capture(|| {
+        init();
+        log::info!("foo\0bar\x01\t\x7f\\é\nbaz");
+    });
+
+    assert_eq!(
+        messages,
+        [
+            Message::new(Level::Info, "foo\\x00bar\\x01\\x09\\x7f\\\\é"),
+            Message::new(Level::Info, "baz"),
+        ]
+    );
+}
+
+#[test]
+fn levels() {
+    let messages = mock::capture(|| {
+        init();
+        log::error!("foo");
+        log::warn!("bar");
+        log::debug!("baz");
+    });
+
+    assert_eq!(
+        messages,
+        [
+            Message::new(Level::Error, "foo"),
+            Message::new(Level::Warning, "bar"),
+            Message::new(Level::Debug, "baz"),
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "a message was logged at the fatal level")]
+fn fatal() {
+    let _ = mock::capture(|| {
+        init();
+        mgba_log::fatal!("foo");
+    });
+}
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+use core::{
+    arch::asm,
+    ptr::{addr_of, addr_of_mut},
+};
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+/// Whether the program has already been reset once.
+///
+/// This is kept in `.noinit`, so it is not cleared by the reset.
+#[link_section = ".noinit"]
+static mut RESET: bool = false;
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+
+    if unsafe { addr_of!(RESET).read_volatile() } {
+        mgba_log::recover_previous_session();
+        STATUS_REGISTER.write(3);
+
+        loop {}
+    }
+
+    log::warn!("foo");
+    log::info!("bar");
+
+    // Simulate a soft reset by jumping back to the entry point.
+    unsafe {
+        addr_of_mut!(RESET).write_volatile(true);
+        asm!("ldr r0, =__start", "bx r0", options(noreturn));
+    }
+}
@@ -0,0 +1,41 @@
+@ linker entry point
+.global __start
+
+.arm
+__start: b init
+@ this is replaced with correct header info by `gbafix`
+.space 188
+
+init:
+  @ We boot in Supervisor mode, change to System mode.
+  mov r0, #0x1f
+  msr CPSR_c, r0
+
+  @ Set stack pointer.
+  ldr sp, =0x3007F00
+
+  @ Copy `.data` sections from ROM to IWRAM.
+  ldr r0, =__data_rom_start
+  ldr r1, =__data_iwram_start
+  ldr r2, =__data_iwram_end
+copy_data:
+  cmp r1, r2
+  ldrlt r3, [r0], #4
+  strlt r3, [r1], #4
+  blt copy_data
+
+  @ Clear `.bss` sections.
+  mov r0, #0
+  ldr r1, =__bss_iwram_start
+  ldr r2, =__bss_iwram_end
+clear_bss:
+  cmp r1, r2
+  strlt r0, [r1], #4
+  blt clear_bss
+
+  @ call Rust `main`
+  ldr r2, =main
+  bx r2
+
+  @ `main` should never return.
+  1: b 1b
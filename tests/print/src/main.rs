@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    // Printing does not require initialization.
+    mgba_log::print!("Hello, ");
+    mgba_log::println!("world!");
+    mgba_log::println!("trailing\n");
+    mgba_log::println!();
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use core::fmt;
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+/// Logs another record while it is being formatted.
+struct Nested;
+
+impl fmt::Display for Nested {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("before")?;
+        log::warn!("inner");
+        formatter.write_str(" after")
+    }
+}
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+    log::info!("outer: {}", Nested);
+    log::info!("done");
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+use log::{Log, Metadata, Record};
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+/// A secondary logger printing warnings and errors directly to mGBA.
+struct Secondary;
+
+impl Log for Secondary {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        mgba_log::println!("secondary: {}", record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::builder()
+        .deny_target("hidden")
+        .with_secondary_logger(&Secondary)
+        .init()
+        .expect("unable to initialize");
+
+    log::warn!("foo");
+    log::info!("bar");
+    log::error!(target: "hidden", "baz");
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,29 @@
+#![no_std]
+#![no_main]
+
+use mgba_log::fmt::{ascii_dump, hex_slice};
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+    let header = [0x2e, 0x00, 0x00, 0xea, b'A', b'G', b'B', b' ', b'\t', 0xff];
+    log::info!("{}", hex_slice(&header));
+    log::info!("{}", ascii_dump(&header));
+    log::info!("[{}]", hex_slice(&[]));
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// The start of cartridge SRAM, where the logged warnings and errors are mirrored.
+const SRAM: *const u8 = 0x0E00_0000 as *const u8;
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::builder()
+        .with_sram_log(0, 0x400)
+        .init()
+        .expect("unable to initialize");
+
+    log::warn!("foo");
+    log::info!("bar");
+    log::error!("baz");
+
+    // Read back the ring buffer, which follows the 10 byte header and ends with a null byte.
+    let mut bytes = [0; 64];
+    let mut len = 0;
+    loop {
+        let byte = unsafe { SRAM.add(10 + len).read_volatile() };
+        if byte == 0 {
+            break;
+        }
+        bytes[len] = byte;
+        len += 1;
+    }
+    log::info!("{}", core::str::from_utf8(&bytes[..len]).unwrap());
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+use core::hint::black_box;
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+/// Uses at least 4096 bytes of stack.
+#[inline(never)]
+fn deep() {
+    let buffer = [1u8; 4096];
+    black_box(&buffer);
+}
+
+#[no_mangle]
+pub fn main() {
+    // SAFETY: The test's static data is far smaller than 0x1000 bytes, and the stack set up by
+    // `rsrt0.s` starts at 0x0300_7F00.
+    unsafe { mgba_log::builder().with_stack_painting(0x0300_6000, 0x0300_7F00) }
+        .init()
+        .expect("unable to initialize");
+
+    mgba_log::log_stack_usage!();
+    deep();
+    mgba_log::log_stack_usage!();
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::builder()
+        .with_truncation(true)
+        .init()
+        .expect("unable to initialize");
+    log::info!("Hello, world!");
+    log::warn!("{:300}", "foo");
+    log::debug!("foo\nbar");
+
+    let stats = mgba_log::stats();
+    for (name, level) in [
+        ("info", stats.info),
+        ("warning", stats.warning),
+        ("debug", stats.debug),
+        ("total", stats.total()),
+    ] {
+        log::info!(
+            "{}: {} messages, {} bytes, {} truncated, {} dropped",
+            name,
+            level.messages,
+            level.bytes,
+            level.truncated,
+            level.dropped
+        );
+    }
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::builder()
+        .deny_target("noisy")
+        .init()
+        .expect("unable to initialize");
+    log::info!(target: "noisy", "denied");
+    log::info!(target: "noisy::sound", "denied");
+    log::info!(target: "noisy_neighbor", "allowed by deny");
+
+    assert!(mgba_log::allow_target("game"));
+    log::info!(target: "other", "not allowed");
+    log::info!(target: "game::physics", "allowed");
+
+    mgba_log::clear_target_filters();
+    log::info!(target: "noisy", "cleared");
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
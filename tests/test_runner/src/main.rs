@@ -0,0 +1,21 @@
+#![no_std]
+#![no_main]
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+fn addition() {
+    assert_eq!(1 + 1, 2);
+}
+
+fn logging() {
+    log::info!("Hello, world!");
+}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+
+    // The summary is logged at the fatal level, which the reporter stops running the ROM at.
+    mgba_log::test_runner(&[&addition, &logging]);
+}
@@ -0,0 +1,21 @@
+#![no_std]
+#![no_main]
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+fn addition() {
+    assert_eq!(1 + 1, 2);
+}
+
+fn failure() {
+    panic!("wrong answer");
+}
+
+fn never_run() {}
+
+#[no_mangle]
+pub fn main() {
+    // The reporter stops running the ROM once the panic is logged at the fatal level.
+    mgba_log::test_runner(&[&addition, &failure, &never_run]);
+}
@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::builder()
+        .with_level(log::LevelFilter::Info)
+        .deny_target("denied")
+        .init()
+        .expect("unable to initialize");
+
+    let frame: u32 = 42;
+    mgba_log::udebug!("not logged");
+    mgba_log::uinfo!("Hello, {}!", "world");
+    mgba_log::uwarn!("frame {}", frame);
+    mgba_log::uerror!(target: "denied", "not logged");
+    mgba_log::ulog!(log::Level::Error, "{:?}", -1i32);
+
+    let mut writer = mgba_log::MgbaWriter::new(mgba_log::Level::Debug);
+    ufmt::uwrite!(writer, "written {}", frame).unwrap();
+    drop(writer);
+    ufmt::uwrite!(
+        mgba_log::MgbaWriter::new(mgba_log::Level::Info),
+        "temporary {}",
+        frame
+    )
+    .unwrap();
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}
@@ -0,0 +1,85 @@
+//! Benchmarks writing a message to mGBA's log buffer.
+//!
+//! The message is written both one byte at a time, which is how `mgba_log` used to write it, and
+//! using `MgbaWriter`, which writes halfwords and words wherever possible. The number of cycles
+//! taken by each is logged at the debug level.
+
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+use mgba_log::{Level, MgbaWriter};
+use voladdress::{Safe, VolAddress};
+
+/// This address is used to communicate the current execution status directly with the test runner.
+const STATUS_REGISTER: VolAddress<u8, Safe, Safe> = unsafe { VolAddress::new(0x0203FFFF) };
+/// Counter register for timer 0.
+const TM0CNT_L: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0100) };
+/// Control register for timer 0.
+const TM0CNT_H: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0102) };
+/// Counter register for timer 1.
+const TM1CNT_L: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0104) };
+/// Control register for timer 1.
+const TM1CNT_H: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x0400_0106) };
+/// mGBA's log buffer.
+const MGBA_LOG_BUFFER: *mut u8 = 0x04FF_F600 as *mut u8;
+/// mGBA's send register.
+const MGBA_LOG_SEND: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(0x04FF_F700) };
+
+/// A message long enough for the cost of writing it to dominate any fixed overhead.
+const MESSAGE: &str = "The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog.";
+
+/// Returns the number of cycles counted by timers 0 and 1.
+fn cycles() -> u32 {
+    loop {
+        let high = TM1CNT_L.read();
+        let low = TM0CNT_L.read();
+        if high == TM1CNT_L.read() {
+            return u32::from(high) << 16 | u32::from(low);
+        }
+    }
+}
+
+/// Logs `message` at the info level, writing one byte at a time.
+fn write_bytes(message: &str) {
+    for (index, &byte) in message.as_bytes().iter().enumerate() {
+        unsafe { MGBA_LOG_BUFFER.add(index).write_volatile(byte) };
+    }
+    unsafe { MGBA_LOG_BUFFER.add(message.len()).write_volatile(0) };
+    MGBA_LOG_SEND.write(0x103);
+}
+
+#[panic_handler]
+fn panic_handler(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub fn __sync_synchronize() {}
+
+#[no_mangle]
+pub fn main() {
+    mgba_log::init().expect("unable to initialize");
+
+    // Cascade timer 1 from timer 0, counting every cycle.
+    TM1CNT_H.write(0x0084);
+    TM0CNT_H.write(0x0080);
+
+    let start = cycles();
+    write_bytes(MESSAGE);
+    let byte_writes = cycles().wrapping_sub(start);
+
+    let start = cycles();
+    MgbaWriter::new(Level::Info)
+        .write_str(MESSAGE)
+        .expect("unable to write message");
+    let wide_writes = cycles().wrapping_sub(start);
+
+    log::debug!("byte writes: {} cycles", byte_writes);
+    log::debug!("wide writes: {} cycles", wide_writes);
+    log::info!("faster: {}", wide_writes < byte_writes);
+
+    STATUS_REGISTER.write(3);
+
+    loop {}
+}